@@ -0,0 +1,162 @@
+require "lua_private.Helpers"
+
+function module_info()
+    return {
+        name = "Color Sensor",
+        description = "A color sensor, read as a NormalizedColorSensor or a ColorSensor. Other components can act while it sees red, yellow or blue, such as game pieces of the alliance's color.",
+        author = "FTCreate",
+        version = "1.0.0",
+        category = "Sensors",
+        api_version = 7,
+    }
+end
+
+controlsChanged = true
+
+-- hue ranges in degrees for each color the sensor reports, from the usual FTC game pieces
+colors = {
+    { name = "red", check = "(%s < 25 || %s > 335)" },
+    { name = "yellow", check = "(%s > 45 && %s < 80)" },
+    { name = "blue", check = "(%s > 190 && %s < 250)" },
+}
+
+function get_controls()
+    controls = {}
+
+    index = 1
+
+    controls[index] = label("Color Sensor")
+    index = index + 1
+
+    controls[index] = spacer()
+    index = index + 1
+
+    controls[index] = textInput("COLOR_Name", "Color sensor", "colorSensor")
+    index = index + 1
+
+    controls[index] = comboBox("COLOR_Interface", "Read as", "NormalizedColorSensor", "NormalizedColorSensor",
+        "ColorSensor")
+    index = index + 1
+
+    -- below this saturation the sensor is looking at something grey, or at nothing
+    controls[index] = slider("COLOR_MinSaturation", "Min saturation", 0, 1, 0.4, .05, 2)
+    index = index + 1
+
+    controls[index] = slider("COLOR_Gain", "Gain (normalized only)", 1, 50, 2, 1, 0)
+    index = index + 1
+
+    controlsChanged = false
+
+    return controls
+end
+
+function tick()
+end
+
+function controls_changed()
+    return controlsChanged
+end
+
+function is_normalized()
+    return COLOR_Interface.text == "NormalizedColorSensor"
+end
+
+function hsv()
+    return COLOR_Name.text .. "_hsv"
+end
+
+-- The Java for whether the sensor sees `color` this loop
+function sees(color)
+    return COLOR_Name.text .. "_" .. color
+end
+
+function get_hardware()
+    if not exists(COLOR_Name) then
+        return {}
+    end
+
+    return { { name = COLOR_Name.text, type = COLOR_Interface.text, role = "sensor" } }
+end
+
+function get_conditions()
+    if not exists(COLOR_Name) then
+        return {}
+    end
+
+    conditions = {}
+    for i, color in ipairs(colors) do
+        conditions[i] = { java = sees(color.name), description = COLOR_Name.text .. " sees " .. color.name }
+    end
+    return conditions
+end
+
+function generate_includes()
+    if not exists(COLOR_Interface) then
+        return ""
+    end
+
+    return "import android.graphics.Color;\n" ..
+        "import com.qualcomm.robotcore.hardware." .. COLOR_Interface.text .. ";\n"
+end
+
+function generate_globals()
+    if not exists(COLOR_Name) then
+        return ""
+    end
+    return "private " .. COLOR_Interface.text .. " " .. COLOR_Name.text .. " = null;\n" ..
+        "private float[] " .. hsv() .. " = new float[3];\n\n"
+end
+
+function generate_init()
+    if not exists(COLOR_Name) then
+        return ""
+    end
+
+    local code = '// ' .. COLOR_Name.text .. ' init\n' ..
+        COLOR_Name.text .. ' = hardwareMap.get(' .. COLOR_Interface.text .. '.class, "' .. COLOR_Name.text .. '");\n'
+    if is_normalized() then
+        code = code .. COLOR_Name.text .. ".setGain(" .. COLOR_Gain.text .. ");\n"
+    end
+    return code .. "\n"
+end
+
+function generate_loop_one_time_setup()
+    if not exists(COLOR_Name) then
+        return ""
+    end
+
+    -- hue is 0-360 degrees in hsv[0], saturation 0-1 in hsv[1]
+    local code
+    if is_normalized() then
+        code = "Color.colorToHSV(" .. COLOR_Name.text .. ".getNormalizedColors().toColor(), " .. hsv() .. ");\n"
+    else
+        code = "Color.RGBToHSV(" .. COLOR_Name.text .. ".red(), " .. COLOR_Name.text .. ".green(), " ..
+            COLOR_Name.text .. ".blue(), " .. hsv() .. ");\n"
+    end
+
+    local hue = hsv() .. "[0]"
+    local saturated = hsv() .. "[1] > " .. COLOR_MinSaturation.text
+    for _, color in ipairs(colors) do
+        code = code .. "boolean " .. sees(color.name) .. " = " .. saturated .. " && " ..
+            color.check:format(hue, hue) .. ";\n"
+    end
+    return code
+end
+
+function get_telemetry()
+    if not exists(COLOR_Name) then
+        return {}
+    end
+
+    -- the first color seen, e.g. `intakeColor_red ? "red" : intakeColor_blue ? "blue" : "none"`
+    local seen = ""
+    for _, color in ipairs(colors) do
+        seen = seen .. sees(color.name) .. ' ? "' .. color.name .. '" : '
+    end
+
+    return {
+        { id = "color", java = seen .. '"none"', caption = COLOR_Name.text .. " sees" },
+        { id = "hue", java = hsv() .. "[0]", caption = COLOR_Name.text .. " hue", format = "%.0f" },
+        { id = "saturation", java = hsv() .. "[1]", caption = COLOR_Name.text .. " saturation", format = "%.2f" },
+    }
+end
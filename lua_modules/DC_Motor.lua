@@ -1,11 +1,23 @@
 require "lua_private.Helpers"
 
+function module_info()
+    return {
+        name = "DC Motor",
+        description = "A DcMotorEx driven by buttons or a joystick axis, with optional fixed positions. Can also be used as a drivetrain motor.",
+        author = "FTCreate",
+        version = "1.0.0",
+        category = "Motors",
+        api_version = 7,
+    }
+end
+
 controlsChanged = true
 
 run_mode = "Run using encoders"
 num_positions = 0
 is_drivetrain_motor = 0
 drivetrain_type = ""
+stops = false
 
 function get_controls()
     controls = {}
@@ -46,7 +58,8 @@ function get_controls()
             controls[index] = slider("DCM_Position" .. i, "Position: " .. i, -20000, 20000, 0, 1, 0)
             index = index + 1
 
-            controls[index] = keybindingComboBox("DCM_Keybind" .. i, "Keybinding", "none")
+            controls[index] = keybindingComboBox("DCM_Keybind" .. i, "Position " .. i .. " keybinding", "none", nil,
+                "held")
             index = index + 1
 
             controls[index] = spacer()
@@ -59,7 +72,12 @@ function get_controls()
     elseif is_drivetrain_motor == 0 then -- normal run mode (when not set as a drivetrain motor)
         controls[index] = spacer()
         index = index + 1
-        controls[index] = keybindingComboBox("DCM_Keybind", "Keybinding", "none")
+        -- a press or release only lasts one loop, which can't switch the power off again, so
+        -- only offer the behaviours that say when to stop
+        controls[index] = keybindingComboBox("DCM_Keybind", "Keybinding", "none", nil, "held",
+            { "held", "toggle" })
+        index = index + 1
+        controls[index] = axisShaping("DCM_AxisShaping", "Axis response")
         index = index + 1
         controls[index] = spacer()
         index = index + 1
@@ -96,6 +114,18 @@ function get_controls()
         index = index + 1
     end
 
+    if is_drivetrain_motor == 0 then
+        controls[index] = conditionComboBox("DCM_StopWhen", "Stop when")
+        index = index + 1
+        if stops then
+            controls[index] = comboBox("DCM_StopDirection", "Stopping", "Both directions", "Both directions",
+                "Forwards", "Backwards")
+            index = index + 1
+        end
+        controls[index] = spacer()
+        index = index + 1
+    end
+
     controls[index] = comboBox("DCM_Direction", "Direction", "Forward", "Forward", "Reverse")
     index = index + 1
 
@@ -145,12 +175,57 @@ function tick()
             controlsChanged = true
         end
     end
+    if exists(DCM_StopWhen) then
+        if stops ~= (DCM_StopWhen.text ~= "none") then
+            stops = DCM_StopWhen.text ~= "none"
+            controlsChanged = true
+        end
+    end
 end
 
 function controls_changed()
     return controlsChanged
 end
 
+function get_hardware()
+    if not exists(DCM_Name) then
+        return {}
+    end
+
+    if is_drivetrain_motor == 1 then
+        position = ""
+        if drivetrain_type == "Mecanum" and exists(DCM_MecanumPosition) then
+            position = DCM_MecanumPosition.text
+        elseif drivetrain_type == "Arcade" and exists(DCM_ArcadePosition) then
+            position = DCM_ArcadePosition.text
+        elseif drivetrain_type == "Tank" and exists(DCM_TankPosition) then
+            position = DCM_TankPosition.text
+        end
+
+        return { { name = DCM_Name.text, type = "DcMotorEx", role = "drive_motor", drivetrain = drivetrain_type, position = position } }
+    end
+
+    return { { name = DCM_Name.text, type = "DcMotorEx", role = "motor" } }
+end
+
+function get_telemetry()
+    if not exists(DCM_Name) or not exists(DCM_RunMode) then
+        return {}
+    end
+
+    local name = DCM_Name.text
+    local values = {
+        { id = "power", java = name .. ".getPower()", caption = name .. " power", format = "%.2f" },
+    }
+    if DCM_RunMode.text ~= "Run without encoders" then
+        values[#values + 1] = { id = "position", java = name .. ".getCurrentPosition()", caption = name .. " position", format = "%d" }
+    end
+    if DCM_RunMode.text == "Run to position" then
+        values[#values + 1] = { id = "target", java = name .. ".getTargetPosition()", caption = name .. " target", format = "%d" }
+    end
+    return values
+end
+
 function generate_includes()
     return "import com.qualcomm.robotcore.hardware.DcMotor;\n" ..
         "import org.firstinspires.ftc.robotcore.external.Telemetry;\n" ..
@@ -163,10 +238,44 @@ function generate_globals()
     string = ""
     if exists(DCM_Name) then
         string = string .. "private DcMotorEx " .. DCM_Name.text .. " = null;\n\n"
+        string = string .. button_logic().fields
+        if uses_axis() then
+            string = string .. axis_code().fields
+        end
     end
     return string
 end
 
+-- Whether the motor follows the axis DCM_Keybind is set to
+function uses_axis()
+    return is_drivetrain_motor == 0 and run_mode ~= "Run to position" and exists(DCM_Keybind) and
+        isAxis(DCM_Keybind.text) and exists(DCM_AxisShaping)
+end
+
+function axis_code()
+    return axisCode(DCM_Name.text, DCM_AxisShaping, gamepadInput(DCM_Keybind))
+end
+
+-- The keybinds in use, by position number (or just DCM_Keybind when not running to position)
+function active_keybinds()
+    local keybinds = {}
+    if is_drivetrain_motor == 1 then
+        return keybinds
+    end
+    if run_mode == "Run to position" then
+        for i = 1, num_positions, 1 do
+            keybinds[i] = _G["DCM_Keybind" .. i]
+        end
+    elseif exists(DCM_Keybind) then
+        keybinds[1] = DCM_Keybind
+    end
+    return keybinds
+end
+
+function button_logic()
+    return buttonLogic(DCM_Name.text, active_keybinds())
+end
+
 function generate_init()
     string = ""
 
@@ -219,16 +328,16 @@ function generate_drivetrain_loop()
         if exists(DCM_MecanumPosition) then
             if DCM_MecanumPosition.text == "Front Left" then
                 string = string ..
-                    DCM_Name.text .. ".setPower(Range.clip(drive - strafe + turn, -" .. power .. ", " .. power .. "));\n"
+                    DCM_Name.text .. ".setPower((drive - strafe + turn) / wheelScale * " .. power .. ");\n"
             elseif DCM_MecanumPosition.text == "Front Right" then
                 string = string ..
-                    DCM_Name.text .. ".setPower(Range.clip(drive + strafe - turn, -" .. power .. ", " .. power .. "));\n"
+                    DCM_Name.text .. ".setPower((drive + strafe - turn) / wheelScale * " .. power .. ");\n"
             elseif DCM_MecanumPosition.text == "Rear Left" then
                 string = string ..
-                    DCM_Name.text .. ".setPower(Range.clip(drive + strafe + turn, -" .. power .. ", " .. power .. "));\n"
+                    DCM_Name.text .. ".setPower((drive + strafe + turn) / wheelScale * " .. power .. ");\n"
             elseif DCM_MecanumPosition.text == "Rear Right" then
                 string = string ..
-                    DCM_Name.text .. ".setPower(Range.clip(drive - strafe - turn, -" .. power .. ", " .. power .. "));\n"
+                    DCM_Name.text .. ".setPower((drive - strafe - turn) / wheelScale * " .. power .. ");\n"
             end
         end
     elseif drivetrain_type == "Arcade" then
@@ -259,7 +368,8 @@ function generate_normal_loop()
     if is_drivetrain_motor == 1 then
         return ""
     end
-    string = ""
+    logic = button_logic()
+    string = logic.before
     default_string = ""
     added_first = false
     if run_mode == "Run to position" then
@@ -280,10 +390,10 @@ function generate_normal_loop()
                         default_string = default_string .. "}\n"
                     else
                         if added_first == false then
-                            string = string .. "if (gamepad1." .. keybind.text .. ") {\n"
+                            string = string .. "if (" .. logic.conditions[i] .. ") {\n"
                             added_first = true
                         else
-                            string = string .. "else if (gamepad1." .. keybind.text .. ") {\n"
+                            string = string .. "else if (" .. logic.conditions[i] .. ") {\n"
                         end
                         string = string .. "\t" .. DCM_Name.text .. ".setTargetPosition(" .. position.text .. ");\n"
                         string = string .. "\t" .. DCM_Name.text .. ".setMode(DcMotor.RUN_TO_POSITION);\n"
@@ -293,7 +403,7 @@ function generate_normal_loop()
                 end
                 if isAxis(_G["DCM_Keybind" .. i].text) then -- This is in "run to position" mode so we just set the target position if the axis is pushed at all
                     -- TODO: Add a "scaled position" control where we set "Position * axis value)
-                    string = string .. "if (gamepad1." .. keybind.text .. " > 0) {\n"
+                    string = string .. "if (" .. gamepadInput(keybind) .. " > 0) {\n"
                     string = string .. "\t" .. DCM_Name.text .. ".setPower(" .. position.value .. ");\n"
                     string = string .. "}\n"
                 end
@@ -304,13 +414,39 @@ function generate_normal_loop()
         keybind = DCM_Keybind
 
         if isButton(keybind.text) then
-            string = string .. "if (gamepad1." .. keybind.text .. ") {\n"
+            string = string .. "if (" .. (logic.conditions[1] or gamepadInput(keybind)) .. ") {\n"
             string = string .. "\t" .. DCM_Name.text .. ".setPower(" .. DCM_MaxPower.text .. ");\n"
             string = string .. "}\n"
-        elseif isAxis(keybind.text) then
-            string = string ..
-                DCM_Name.text .. ".setPower(gamepad1." .. keybind.text .. " * " .. DCM_MaxPower.text .. ");\n"
+            -- letting go or toggling off stops the motor again
+            string = string .. "else {\n"
+            string = string .. "\t" .. DCM_Name.text .. ".setPower(0);\n"
+            string = string .. "}\n"
+        elseif uses_axis() then
+            axis = axis_code()
+            string = string .. axis.before
+            string = string .. DCM_Name.text .. ".setPower(" .. axis.value .. " * " .. DCM_MaxPower.text .. ");\n"
         end
     end
+    string = string .. logic.after
+    string = string .. generate_stop()
     return string
 end
+
+-- Stop the motor when the picked condition holds, overriding whatever the bindings set, e.g.
+-- when a lift reaches its limit switch
+function generate_stop()
+    if not exists(DCM_StopWhen) or not hasCondition(DCM_StopWhen.text) then
+        return ""
+    end
+
+    condition = DCM_StopWhen.text
+    if exists(DCM_StopDirection) and DCM_StopDirection.text == "Forwards" then
+        condition = condition .. " && " .. DCM_Name.text .. ".getPower() > 0"
+    elseif exists(DCM_StopDirection) and DCM_StopDirection.text == "Backwards" then
+        condition = condition .. " && " .. DCM_Name.text .. ".getPower() < 0"
+    end
+
+    return "if (" .. condition .. ") {\n" ..
+        "\t" .. DCM_Name.text .. ".setPower(0);\n" ..
+        "}\n"
+end
@@ -0,0 +1,116 @@
+require "lua_private.Helpers"
+
+function module_info()
+    return {
+        name = "Distance Sensor",
+        description = "A distance sensor, such as the REV 2m distance sensor. Other components can act while something is closer than a set distance.",
+        author = "FTCreate",
+        version = "1.0.0",
+        category = "Sensors",
+        api_version = 7,
+    }
+end
+
+controlsChanged = true
+
+function get_controls()
+    controls = {}
+
+    index = 1
+
+    controls[index] = label("Distance Sensor")
+    index = index + 1
+
+    controls[index] = spacer()
+    index = index + 1
+
+    controls[index] = textInput("DIST_Name", "Distance sensor", "distanceSensor")
+    index = index + 1
+
+    controls[index] = comboBox("DIST_Unit", "Unit", "CM", "CM", "MM", "INCH")
+    index = index + 1
+
+    controls[index] = slider("DIST_Threshold", "Near when closer than", 0, 200, 10, .5, 1)
+    index = index + 1
+
+    controlsChanged = false
+
+    return controls
+end
+
+function tick()
+end
+
+function controls_changed()
+    return controlsChanged
+end
+
+function distance()
+    return DIST_Name.text .. "_distance"
+end
+
+-- The Java for whether something is within the threshold this loop
+function near()
+    return DIST_Name.text .. "_near"
+end
+
+function get_hardware()
+    if not exists(DIST_Name) then
+        return {}
+    end
+
+    return { { name = DIST_Name.text, type = "DistanceSensor", role = "sensor" } }
+end
+
+function get_conditions()
+    if not exists(DIST_Name) then
+        return {}
+    end
+
+    within = DIST_Threshold.text .. " " .. DIST_Unit.text:lower()
+    return {
+        { java = near(), description = DIST_Name.text .. " closer than " .. within },
+        { java = "!" .. near(), description = DIST_Name.text .. " further than " .. within },
+    }
+end
+
+function generate_includes()
+    return "import com.qualcomm.robotcore.hardware.DistanceSensor;\n" ..
+        "import org.firstinspires.ftc.robotcore.external.navigation.DistanceUnit;\n"
+end
+
+function generate_globals()
+    if not exists(DIST_Name) then
+        return ""
+    end
+    return "private DistanceSensor " .. DIST_Name.text .. " = null;\n\n"
+end
+
+function generate_init()
+    if not exists(DIST_Name) then
+        return ""
+    end
+
+    return '// ' .. DIST_Name.text .. ' init\n' ..
+        DIST_Name.text .. ' = hardwareMap.get(DistanceSensor.class, "' .. DIST_Name.text .. '");\n\n'
+end
+
+function generate_loop_one_time_setup()
+    if not exists(DIST_Name) then
+        return ""
+    end
+
+    return "double " .. distance() .. " = " .. DIST_Name.text .. ".getDistance(DistanceUnit." .. DIST_Unit.text .. ");\n" ..
+        "boolean " .. near() .. " = " .. distance() .. " < " .. DIST_Threshold.text .. ";\n"
+end
+
+function get_telemetry()
+    if not exists(DIST_Name) then
+        return {}
+    end
+
+    return {
+        { id = "distance", java = distance(), caption = DIST_Name.text .. " (" .. DIST_Unit.text:lower() .. ")", format = "%.1f" },
+        { id = "near", java = near(), caption = DIST_Name.text .. " near" },
+    }
+end
@@ -1,7 +1,20 @@
 require "lua_private.Helpers"
 
+function module_info()
+    return {
+        name = "Drivetrain",
+        description = "Reads the driver's joysticks for a mecanum, arcade or tank drivetrain, with optional field-centric mecanum drive using the hub's IMU. Add DC Motors marked as drivetrain motors to drive the wheels.",
+        author = "FTCreate",
+        version = "1.0.0",
+        category = "Drivetrain",
+        api_version = 7,
+    }
+end
+
 controlsChanged = true
 num_positions = 0
+drivetrain_type = ""
+field_centric = 0
 
 function get_controls()
     controls = {}
@@ -19,35 +32,230 @@ function get_controls()
         "Tank")
     index = index + 1
 
+    controls[index] = gamepadComboBox("DCM_DriveGamepad", "Driven by", "gamepad1")
+    index = index + 1
+
+    controls[index] = spacer()
+    index = index + 1
+
+    -- both sides of a tank drive share one shaping so the robot drives straight
+    controls[index] = axisShaping("DCM_DriveShaping", "Drive response")
+    index = index + 1
+
+    if drivetrain_type ~= "Tank" then
+        controls[index] = axisShaping("DCM_TurnShaping", "Turn response")
+        index = index + 1
+    end
+
+    if drivetrain_type == "Mecanum" then
+        controls[index] = axisShaping("DCM_StrafeShaping", "Strafe response")
+        index = index + 1
+
+        controls[index] = spacer()
+        index = index + 1
+
+        controls[index] = checkbox("DCM_FieldCentric", "Field centric (uses the hub's IMU)", 0)
+        index = index + 1
+
+        if field_centric == 1 then
+            controls[index] = comboBox("DCM_HubLogo", "Hub logo faces", "UP", "UP", "DOWN", "FORWARD",
+                "BACKWARD", "LEFT", "RIGHT")
+            index = index + 1
+
+            controls[index] = comboBox("DCM_HubUsb", "Hub USB ports face", "FORWARD", "UP", "DOWN", "FORWARD",
+                "BACKWARD", "LEFT", "RIGHT")
+            index = index + 1
+
+            gamepad = nil
+            if exists(DCM_DriveGamepad) then
+                gamepad = DCM_DriveGamepad.text
+            end
+            controls[index] = keybindingComboBox("DCM_ResetHeading", "Reset heading", "start", gamepad,
+                "rising_edge")
+            index = index + 1
+        end
+    end
+
     controlsChanged = false
 
     return controls
 end
 
 function tick()
+    if exists(DCM_DrivetrainType) then
+        if drivetrain_type ~= DCM_DrivetrainType.text then
+            drivetrain_type = DCM_DrivetrainType.text
+            controlsChanged = true
+        end
+    end
+    if exists(DCM_FieldCentric) then
+        if field_centric ~= DCM_FieldCentric.value then
+            field_centric = DCM_FieldCentric.value
+            controlsChanged = true
+        end
+    end
+end
+
+-- Whether mecanum drive is relative to the field rather than the robot
+function is_field_centric()
+    return drivetrain_type == "Mecanum" and field_centric == 1 and exists(DCM_HubLogo) and
+        exists(DCM_HubUsb) and exists(DCM_ResetHeading)
+end
+
+function heading_logic()
+    return buttonLogic("resetHeading", { DCM_ResetHeading })
 end
 
 function controls_changed()
     return controlsChanged
 end
 
+function get_keybindings()
+    gamepad = "gamepad1"
+    if exists(DCM_DriveGamepad) then
+        gamepad = DCM_DriveGamepad.text
+    end
+
+    if not exists(DCM_DrivetrainType) then
+        return {}
+    elseif DCM_DrivetrainType.text == "Mecanum" then
+        return {
+            { gamepad = gamepad, input = "left_stick_y", description = "Drive" },
+            { gamepad = gamepad, input = "right_stick_x", description = "Turn" },
+            { gamepad = gamepad, input = "left_stick_x", description = "Strafe" },
+        }
+    elseif DCM_DrivetrainType.text == "Arcade" then
+        return {
+            { gamepad = gamepad, input = "left_stick_y", description = "Drive" },
+            { gamepad = gamepad, input = "right_stick_x", description = "Turn" },
+        }
+    elseif DCM_DrivetrainType.text == "Tank" then
+        return {
+            { gamepad = gamepad, input = "left_stick_y", description = "Drive left" },
+            { gamepad = gamepad, input = "right_stick_y", description = "Drive right" },
+        }
+    end
+
+    return {}
+end
+
+-- The shaped code for each stick in use, as { variable, shaping control, input, comment }
+function drive_axes()
+    if drivetrain_type == "Mecanum" then
+        return {
+            { "drive", DCM_DriveShaping, "left_stick_y", "forwards and backwards movement" },
+            { "turn", DCM_TurnShaping, "right_stick_x", "rotation" },
+            { "strafe", DCM_StrafeShaping, "left_stick_x", "side to side movement" },
+        }
+    elseif drivetrain_type == "Arcade" then
+        return {
+            { "drive", DCM_DriveShaping, "left_stick_y", "forwards and backwards movement" },
+            { "turn", DCM_TurnShaping, "right_stick_x", "rotation" },
+        }
+    elseif drivetrain_type == "Tank" then
+        return {
+            { "driveLeft", DCM_DriveShaping, "left_stick_y", "left motors movement" },
+            { "driveRight", DCM_DriveShaping, "right_stick_y", "right motors movement" },
+        }
+    end
+    return {}
+end
+
+function axis_code(axis)
+    gamepad = "gamepad1"
+    if exists(DCM_DriveGamepad) then
+        gamepad = DCM_DriveGamepad.text
+    end
+    input = gamepad .. "." .. axis[3]
+
+    if not exists(axis[2]) then
+        return { fields = "", before = "", value = input }
+    end
+    return axisCode(axis[1], axis[2], input)
+end
+
+function get_telemetry()
+    if not is_field_centric() then
+        return {}
+    end
+
+    -- `heading` is read in radians by the loop's one time setup
+    return { { id = "heading", java = "Math.toDegrees(heading)", caption = "Heading", format = "%.0f deg" } }
+end
+
+function generate_includes()
+    if not is_field_centric() then
+        return ""
+    end
+    return "import com.qualcomm.hardware.rev.RevHubOrientationOnRobot;\n" ..
+        "import com.qualcomm.robotcore.hardware.IMU;\n" ..
+        "import org.firstinspires.ftc.robotcore.external.navigation.AngleUnit;\n"
+end
+
+function generate_globals()
+    string = ""
+    for _, axis in ipairs(drive_axes()) do
+        string = string .. axis_code(axis).fields
+    end
+    if is_field_centric() then
+        string = string .. "private IMU imu = null;\n\n"
+        string = string .. heading_logic().fields
+    end
+    return string
+end
+
+function generate_init()
+    if not is_field_centric() then
+        return ""
+    end
+    return "// IMU init, for field-centric drive\n" ..
+        'imu = hardwareMap.get(IMU.class, "imu");\n' ..
+        "RevHubOrientationOnRobot.LogoFacingDirection logoDirection = " ..
+        "RevHubOrientationOnRobot.LogoFacingDirection." .. DCM_HubLogo.text .. ";\n" ..
+        "RevHubOrientationOnRobot.UsbFacingDirection usbDirection = " ..
+        "RevHubOrientationOnRobot.UsbFacingDirection." .. DCM_HubUsb.text .. ";\n" ..
+        "imu.initialize(new IMU.Parameters(new RevHubOrientationOnRobot(logoDirection, usbDirection)));\n" ..
+        "imu.resetYaw();\n\n"
+end
+
 function generate_loop_one_time_setup()
     string = ""
-    if exists(DCM_DrivetrainType) then
-        if DCM_DrivetrainType.text == "Mecanum" then
-            string = string .. "\n// Mecanum drivetrain one time setup\n"
-            string = string .. "Double drive  = gamepad1.left_stick_y;  // forwards and backwards movement\n"
-            string = string .. "Double turn   = gamepad1.right_stick_x; // rotation\n"
-            string = string .. "Double strafe = gamepad1.left_stick_x;  // side to side movement\n"
-        elseif DCM_DrivetrainType.text == "Arcade" then
-            string = string .. "\n// Arcade drivetrain one time setup\n"
-            string = string .. "Double drive  = gamepad1.left_stick_y;  // forwards and backwards movement\n"
-            string = string .. "Double turn   = gamepad1.right_stick_x; // rotation \n"
-        elseif DCM_DrivetrainType.text == "Tank" then
-            string = string .. "\n// Tank drivetrain one time setup\n"
-            string = string .. "double driveLeft  = gamepad1.left_stick_y;  // left motors movement\n"
-            string = string .. "double driveRight = gamepad1.right_stick_y; // right motors movement \n"
+    axes = drive_axes()
+    if #axes == 0 then
+        return string
+    end
+
+    string = string .. "\n// " .. drivetrain_type .. " drivetrain one time setup\n"
+    for _, axis in ipairs(axes) do
+        string = string .. axis_code(axis).before
+    end
+    for _, axis in ipairs(axes) do
+        string = string .. "double " .. axis[1] .. " = " .. axis_code(axis).value .. "; // " .. axis[4] .. "\n"
+    end
+
+    if is_field_centric() then
+        logic = heading_logic()
+        string = string .. "\n" .. logic.before
+        if exists(logic.conditions[1]) then
+            string = string .. "if (" .. logic.conditions[1] .. ") {\n"
+            string = string .. "\timu.resetYaw(); // field forwards is wherever the robot now faces\n"
+            string = string .. "}\n"
         end
+        string = string .. logic.after
+
+        -- turn the stick's direction by the opposite of the robot's heading, so pushing forwards
+        -- drives away from the driver whichever way the robot faces. Every wheel then reads the
+        -- field-relative drive and strafe.
+        string = string .. "double heading = imu.getRobotYawPitchRollAngles().getYaw(AngleUnit.RADIANS);\n"
+        string = string .. "double fieldStrafe = strafe * Math.cos(heading) - drive * Math.sin(heading);\n"
+        string = string .. "drive = drive * Math.cos(heading) + strafe * Math.sin(heading);\n"
+        string = string .. "strafe = fieldStrafe;\n"
+    end
+
+    if drivetrain_type == "Mecanum" then
+        -- the fastest wheel needs |drive| + |strafe| + |turn|, so dividing by that (when it's
+        -- over 1) keeps every wheel in range without changing the direction of travel
+        string = string .. "double wheelScale = Math.max(1, Math.abs(drive) + Math.abs(strafe) + Math.abs(turn));\n"
     end
     return string
 end
@@ -0,0 +1,104 @@
+require "lua_private.Helpers"
+
+function module_info()
+    return {
+        name = "Magnetic Limit Switch",
+        description = "A magnetic limit switch, such as the REV one, read as a DigitalChannel. Other components can act while a magnet is near it, e.g. stop a lift at the end of its travel.",
+        author = "FTCreate",
+        version = "1.0.0",
+        category = "Sensors",
+        api_version = 7,
+    }
+end
+
+controlsChanged = true
+
+function get_controls()
+    controls = {}
+
+    index = 1
+
+    controls[index] = label("Magnetic Limit Switch")
+    index = index + 1
+
+    controls[index] = spacer()
+    index = index + 1
+
+    controls[index] = textInput("LIMIT_Name", "Limit switch", "limitSwitch")
+    index = index + 1
+
+    controlsChanged = false
+
+    return controls
+end
+
+function tick()
+end
+
+function controls_changed()
+    return controlsChanged
+end
+
+-- The Java for whether a magnet is at the switch this loop
+function triggered()
+    return LIMIT_Name.text .. "_triggered"
+end
+
+function get_hardware()
+    if not exists(LIMIT_Name) then
+        return {}
+    end
+
+    return { { name = LIMIT_Name.text, type = "DigitalChannel", role = "sensor" } }
+end
+
+function get_conditions()
+    if not exists(LIMIT_Name) then
+        return {}
+    end
+
+    return {
+        { java = triggered(), description = LIMIT_Name.text .. " triggered" },
+        { java = "!" .. triggered(), description = LIMIT_Name.text .. " clear" },
+    }
+end
+
+function generate_includes()
+    return "import com.qualcomm.robotcore.hardware.DigitalChannel;\n"
+end
+
+function generate_globals()
+    if not exists(LIMIT_Name) then
+        return ""
+    end
+    return "private DigitalChannel " .. LIMIT_Name.text .. " = null;\n\n"
+end
+
+function generate_init()
+    if not exists(LIMIT_Name) then
+        return ""
+    end
+
+    return '// ' .. LIMIT_Name.text .. ' init\n' ..
+        LIMIT_Name.text .. ' = hardwareMap.get(DigitalChannel.class, "' .. LIMIT_Name.text .. '");\n' ..
+        LIMIT_Name.text .. ".setMode(DigitalChannel.Mode.INPUT);\n\n"
+end
+
+function generate_loop_one_time_setup()
+    if not exists(LIMIT_Name) then
+        return ""
+    end
+
+    -- the switch pulls its line low while a magnet is near
+    return "boolean " .. triggered() .. " = !" .. LIMIT_Name.text .. ".getState();\n"
+end
+
+function get_telemetry()
+    if not exists(LIMIT_Name) then
+        return {}
+    end
+
+    return {
+        { id = "triggered", java = triggered() .. ' ? "triggered" : "clear"', caption = LIMIT_Name.text },
+    }
+end
@@ -0,0 +1,358 @@
+require "lua_private.Helpers"
+
+function module_info()
+    return {
+        name = "Arm / Lift (PIDF)",
+        description = "A motor held at preset positions by a PIDF controller with gravity feedforward, moving between them along a motion profile. Soft limits keep it in range and a joystick can nudge it.",
+        author = "FTCreate",
+        version = "1.0.0",
+        category = "Motors",
+        api_version = 7,
+    }
+end
+
+controlsChanged = true
+
+num_positions = 0
+arm_type = ""
+
+function get_controls()
+    controls = {}
+
+    index = 1
+
+    controls[index] = label("Arm / Lift (PIDF)")
+    index = index + 1
+
+    controls[index] = spacer()
+    index = index + 1
+
+    controls[index] = textInput("ARM_Name", "Motor", "arm")
+    index = index + 1
+
+    -- a lift fights the same weight everywhere, an arm the most when it's horizontal
+    controls[index] = comboBox("ARM_Type", "Type", "Arm", "Arm", "Lift")
+    index = index + 1
+
+    controls[index] = comboBox("ARM_Direction", "Direction", "Forward", "Forward", "Reverse")
+    index = index + 1
+
+    if arm_type == "Arm" then
+        controls[index] = slider("ARM_CountsPerDegree", "Encoder counts per degree", 0.1, 50, 1.49, .01, 2)
+        index = index + 1
+
+        controls[index] = slider("ARM_Horizontal", "Position when horizontal", -20000, 20000, 0, 1, 0)
+        index = index + 1
+    end
+
+    controls[index] = spacer()
+    index = index + 1
+    controls[index] = separator()
+    index = index + 1
+
+    controls[index] = label("Gains (tune them here, then generate and deploy again)")
+    index = index + 1
+
+    controls[index] = slider("ARM_kP", "kP", 0, 0.1, 0.005, .0005, 4)
+    index = index + 1
+
+    controls[index] = slider("ARM_kI", "kI", 0, 0.01, 0, .0001, 4)
+    index = index + 1
+
+    controls[index] = slider("ARM_kD", "kD", 0, 0.01, 0.0002, .0001, 4)
+    index = index + 1
+
+    controls[index] = slider("ARM_kF", "kF (gravity)", 0, 1, 0.1, .01, 2)
+    index = index + 1
+
+    controls[index] = slider("ARM_MaxPower", "Max Power", 0, 1, 1, .01, 2)
+    index = index + 1
+
+    controls[index] = spacer()
+    index = index + 1
+
+    controls[index] = slider("ARM_MaxVelocity", "Max speed (counts per second)", 100, 10000, 2000, 50, 0)
+    index = index + 1
+
+    controls[index] = slider("ARM_MaxAcceleration", "Max acceleration (counts per second²)", 100, 40000, 4000, 100, 0)
+    index = index + 1
+
+    controls[index] = slider("ARM_Tolerance", "At target within (counts)", 0, 500, 20, 1, 0)
+    index = index + 1
+
+    controls[index] = spacer()
+    index = index + 1
+
+    controls[index] = slider("ARM_MinPosition", "Soft limit: min position", -20000, 20000, 0, 1, 0)
+    index = index + 1
+
+    controls[index] = slider("ARM_MaxPosition", "Soft limit: max position", -20000, 20000, 2000, 1, 0)
+    index = index + 1
+
+    controls[index] = spacer()
+    index = index + 1
+    controls[index] = separator()
+    index = index + 1
+
+    -- fixed positons
+    controls[index] = slider("ARM_NumPositions", "Number of positions", 0, 10, 0, 1, 0)
+    index = index + 1
+
+    for i = 1, num_positions, 1 do
+        controls[index] = spacer()
+        index = index + 1
+
+        controls[index] = slider("ARM_Position" .. i, "Position: " .. i, -20000, 20000, 0, 1, 0)
+        index = index + 1
+
+        controls[index] = keybindingComboBox("ARM_Keybind" .. i, "Position " .. i .. " keybinding", "none", nil,
+            "held")
+        index = index + 1
+    end
+
+    controls[index] = spacer()
+    index = index + 1
+
+    controls[index] = keybindingComboBox("ARM_ManualKeybind", "Manual joystick", "none")
+    index = index + 1
+
+    controls[index] = slider("ARM_ManualSpeed", "Manual speed (counts per second)", 0, 5000, 1000, 50, 0)
+    index = index + 1
+
+    controls[index] = axisShaping("ARM_ManualShaping", "Joystick response")
+    index = index + 1
+
+    controlsChanged = false
+
+    return controls
+end
+
+function tick()
+    if exists(ARM_Type) then
+        if arm_type ~= ARM_Type.text then
+            arm_type = ARM_Type.text
+            controlsChanged = true
+        end
+    end
+    if exists(ARM_NumPositions) then
+        if ARM_NumPositions.value ~= num_positions then
+            num_positions = ARM_NumPositions.value
+            controlsChanged = true
+        end
+    end
+end
+
+function controls_changed()
+    return controlsChanged
+end
+
+-- The name of one of the controller's fields or variables, e.g. "lift_goal"
+function var(suffix)
+    return ARM_Name.text .. "_" .. suffix
+end
+
+function ready()
+    return exists(ARM_Name) and exists(ARM_MinPosition) and exists(ARM_ManualShaping)
+end
+
+-- Whether gravity pulls hardest when horizontal, so the feedforward follows the arm's angle
+function is_arm()
+    return arm_type == "Arm" and exists(ARM_CountsPerDegree) and exists(ARM_Horizontal)
+end
+
+function get_hardware()
+    if not exists(ARM_Name) then
+        return {}
+    end
+
+    return { { name = ARM_Name.text, type = "DcMotorEx", role = "motor" } }
+end
+
+function get_conditions()
+    if not ready() then
+        return {}
+    end
+
+    return { { java = var("atGoal"), description = ARM_Name.text .. " at its target" } }
+end
+
+function get_telemetry()
+    if not ready() then
+        return {}
+    end
+
+    return {
+        { id = "position", java = var("position"), caption = ARM_Name.text .. " position", format = "%.0f" },
+        { id = "target", java = var("goal"), caption = ARM_Name.text .. " target", format = "%.0f" },
+        { id = "setpoint", java = var("setpoint"), caption = ARM_Name.text .. " profile setpoint", format = "%.0f" },
+        { id = "power", java = ARM_Name.text .. ".getPower()", caption = ARM_Name.text .. " power", format = "%.2f" },
+    }
+end
+
+function button_logic()
+    local keybinds = {}
+    for i = 1, num_positions, 1 do
+        keybinds[i] = _G["ARM_Keybind" .. i]
+    end
+    return buttonLogic(ARM_Name.text, keybinds)
+end
+
+function uses_axis()
+    return exists(ARM_ManualKeybind) and isAxis(ARM_ManualKeybind.text)
+end
+
+function axis_code()
+    return axisCode(var("manual"), ARM_ManualShaping, gamepadInput(ARM_ManualKeybind))
+end
+
+function generate_includes()
+    return "import com.qualcomm.robotcore.hardware.DcMotor;\n" ..
+        "import com.qualcomm.robotcore.hardware.DcMotorEx;\n"
+end
+
+function generate_globals()
+    if not ready() then
+        return ""
+    end
+
+    local code = "private DcMotorEx " .. ARM_Name.text .. " = null;\n\n" ..
+        "// " .. ARM_Name.text .. " PIDF gains and motion profile. Change them here or in FTCreate and redeploy to tune\n" ..
+        "public static double " .. var("kP") .. " = " .. ARM_kP.text .. ";\n" ..
+        "public static double " .. var("kI") .. " = " .. ARM_kI.text .. ";\n" ..
+        "public static double " .. var("kD") .. " = " .. ARM_kD.text .. ";\n" ..
+        "public static double " .. var("kF") .. " = " .. ARM_kF.text .. ";\n" ..
+        "public static double " .. var("maxVelocity") .. " = " .. ARM_MaxVelocity.text .. ";\n" ..
+        "public static double " .. var("maxAcceleration") .. " = " .. ARM_MaxAcceleration.text .. ";\n" ..
+        "static final double " .. var("MIN_POSITION") .. " = " .. ARM_MinPosition.text .. ";\n" ..
+        "static final double " .. var("MAX_POSITION") .. " = " .. ARM_MaxPosition.text .. ";\n"
+    if is_arm() then
+        code = code .. "static final double " .. var("COUNTS_PER_DEGREE") .. " = " .. ARM_CountsPerDegree.text .. ";\n" ..
+            "static final double " .. var("HORIZONTAL") .. " = " .. ARM_Horizontal.text .. ";\n"
+    end
+
+    -- the goal is where the buttons sent it, the setpoint where the profile has got to on the way
+    code = code .. "\n" ..
+        "private double " .. var("goal") .. " = 0;\n" ..
+        "private double " .. var("setpoint") .. " = 0;\n" ..
+        "private double " .. var("velocity") .. " = 0;\n" ..
+        "private double " .. var("integral") .. " = 0;\n" ..
+        "private double " .. var("lastError") .. " = 0;\n" ..
+        "private ElapsedTime " .. var("timer") .. " = new ElapsedTime();\n\n"
+
+    code = code .. button_logic().fields
+    if uses_axis() then
+        code = code .. axis_code().fields
+    end
+    return code
+end
+
+function generate_init()
+    if not ready() then
+        return ""
+    end
+
+    local name = ARM_Name.text
+    local direction = "FORWARD"
+    if ARM_Direction.text == "Reverse" then
+        direction = "REVERSE"
+    end
+
+    -- the controller sets the power itself, so the motor's own velocity loop is off
+    return '// ' .. name .. ' init, at position 0 when the OpMode starts\n' ..
+        name .. ' = hardwareMap.get(DcMotorEx.class, "' .. name .. '");\n' ..
+        name .. '.setDirection(DcMotor.Direction.' .. direction .. ');\n' ..
+        name .. '.setMode(DcMotor.RunMode.STOP_AND_RESET_ENCODER);\n' ..
+        name .. '.setMode(DcMotor.RunMode.RUN_WITHOUT_ENCODER);\n' ..
+        name .. '.setZeroPowerBehavior(DcMotor.ZeroPowerBehavior.BRAKE);\n\n'
+end
+
+function generate_loop_one_time_setup()
+    if not ready() then
+        return ""
+    end
+
+    return "double " .. var("position") .. " = " .. ARM_Name.text .. ".getCurrentPosition();\n" ..
+        "boolean " .. var("atGoal") .. " = Math.abs(" .. var("goal") .. " - " .. var("position") .. ") <= " ..
+        ARM_Tolerance.text .. ";\n"
+end
+
+function generate_loop()
+    if not ready() then
+        return ""
+    end
+
+    local name = ARM_Name.text
+    local dt = var("dt")
+
+    -- long pauses, such as before the first loop, would otherwise jump the profile ahead
+    local code = "double " .. dt .. " = Math.min(" .. var("timer") .. ".seconds(), 0.1);\n" ..
+        var("timer") .. ".reset();\n\n"
+
+    logic = button_logic()
+    code = code .. logic.before
+    local default_code = ""
+    local added_first = false
+    for i = 1, num_positions, 1 do
+        local position = _G["ARM_Position" .. i]
+        local keybind = _G["ARM_Keybind" .. i]
+        if exists(position) and exists(keybind) and isButton(keybind.text) then
+            if keybind.text == "default_button" then -- has to come last, so it's added after the others
+                default_code = default_code .. "else {\n" ..
+                    "\t" .. var("goal") .. " = " .. position.text .. ";\n" ..
+                    "}\n"
+            else
+                if added_first then
+                    code = code .. "else if (" .. logic.conditions[i] .. ") {\n"
+                else
+                    code = code .. "if (" .. logic.conditions[i] .. ") {\n"
+                    added_first = true
+                end
+                code = code .. "\t" .. var("goal") .. " = " .. position.text .. ";\n" .. "}\n"
+            end
+        end
+    end
+    code = code .. default_code .. logic.after
+
+    if uses_axis() then
+        local axis = axis_code()
+        code = code .. axis.before ..
+            var("goal") .. " += " .. axis.value .. " * " .. ARM_ManualSpeed.text .. " * " .. dt .. ";\n"
+    end
+
+    code = code .. var("goal") .. " = Range.clip(" .. var("goal") .. ", " .. var("MIN_POSITION") .. ", " ..
+        var("MAX_POSITION") .. ");\n\n"
+
+    -- move the setpoint along a trapezoidal profile, stopping on the goal rather than past it
+    code = code .. "// " .. name .. " motion profile\n" ..
+        var("velocity") .. " = profileVelocity(" .. var("goal") .. " - " .. var("setpoint") .. ", " ..
+        var("velocity") .. ", " .. var("maxVelocity") .. ", " .. var("maxAcceleration") .. ", " .. dt .. ");\n" ..
+        var("setpoint") .. " += " .. var("velocity") .. " * " .. dt .. ";\n" ..
+        "if ((" .. var("goal") .. " - " .. var("setpoint") .. ") * " .. var("velocity") .. " < 0) {\n" ..
+        "\t" .. var("setpoint") .. " = " .. var("goal") .. ";\n" ..
+        "\t" .. var("velocity") .. " = 0;\n" ..
+        "}\n\n"
+
+    local err = var("error")
+    local feedforward = var("kF")
+    if is_arm() then
+        feedforward = var("kF") .. " * Math.cos(Math.toRadians((" .. var("position") .. " - " ..
+            var("HORIZONTAL") .. ") / " .. var("COUNTS_PER_DEGREE") .. "))"
+    end
+
+    -- the integral starts again whenever the error changes sign, so it can't wind up
+    code = code .. "// " .. name .. " PIDF\n" ..
+        "double " .. err .. " = " .. var("setpoint") .. " - " .. var("position") .. ";\n" ..
+        "if (Math.signum(" .. err .. ") != Math.signum(" .. var("lastError") .. ")) {\n" ..
+        "\t" .. var("integral") .. " = 0;\n" ..
+        "}\n" ..
+        var("integral") .. " += " .. err .. " * " .. dt .. ";\n" ..
+        "double " .. var("derivative") .. " = " .. dt .. " > 0 ? (" .. err .. " - " .. var("lastError") ..
+        ") / " .. dt .. " : 0;\n" ..
+        var("lastError") .. " = " .. err .. ";\n" ..
+        "double " .. var("power") .. " = " .. var("kP") .. " * " .. err .. " + " .. var("kI") .. " * " ..
+        var("integral") .. " + " .. var("kD") .. " * " .. var("derivative") .. " + " .. feedforward .. ";\n" ..
+        name .. ".setPower(Range.clip(" .. var("power") .. ", -" .. ARM_MaxPower.text .. ", " ..
+        ARM_MaxPower.text .. "));\n\n"
+
+    return code
+end
@@ -1,11 +1,22 @@
 require "lua_private.Helpers"
 
+function module_info()
+    return {
+        name = "Servo",
+        description = "A positional servo moved between fixed positions with buttons or nudged with a joystick, or a continuous rotation servo driven at fixed powers.",
+        author = "FTCreate",
+        version = "1.0.0",
+        category = "Servos",
+        api_version = 7,
+    }
+end
+
 controlsChanged = true
 
-run_mode = "Run using encoders"
 num_positions = 0
-is_drivetrain_servo = 0
-drivetrain_type = ""
+servo_mode = ""
+-- servos saved before positional support was added were all continuous, see restore_controls
+default_mode = "Positional"
 
 function get_controls()
     controls = {}
@@ -21,6 +32,14 @@ function get_controls()
     controls[index] = textInput("SERVO_Name", "Servo", "Servo")
     index = index + 1
 
+    controls[index] = comboBox("SERVO_Mode", "Servo type", default_mode, "Positional", "Continuous")
+    index = index + 1
+
+    if servo_mode == "Positional" then
+        controls[index] = slider("SERVO_StartPosition", "Start position", 0, 1, 0.5, .01, 2)
+        index = index + 1
+    end
+
     -- fixed positons
     controls[index] = slider("SERVO_NumPositions", "Number of positions", 0, 10, 0, 1, 0)
     index = index + 1
@@ -29,10 +48,15 @@ function get_controls()
         controls[index] = spacer()
         index = index + 1
 
-        controls[index] = slider("SERVO_Position" .. i, "Position: " .. i, -1, 1, 0, .01, 2)
+        if servo_mode == "Positional" then
+            controls[index] = slider("SERVO_Position" .. i, "Position: " .. i, 0, 1, 0, .01, 2)
+        else
+            controls[index] = slider("SERVO_Position" .. i, "Power: " .. i, -1, 1, 0, .01, 2)
+        end
         index = index + 1
 
-        controls[index] = keybindingComboBox("SERVO_Keybind" .. i, "Keybinding", "none")
+        controls[index] = keybindingComboBox("SERVO_Keybind" .. i, "Position " .. i .. " keybinding", "none", nil,
+            "held")
         index = index + 1
 
         controls[index] = spacer()
@@ -43,6 +67,27 @@ function get_controls()
     controls[index] = spacer()
     index = index + 1
 
+    if servo_mode == "Positional" then
+        controls[index] = keybindingComboBox("SERVO_AxisKeybind", "Move with axis", "none")
+        index = index + 1
+        controls[index] = slider("SERVO_AxisSpeed", "Travel per second at full stick", 0.05, 2, 0.5, .05, 2)
+        index = index + 1
+        controls[index] = axisShaping("SERVO_AxisShaping", "Axis response")
+        index = index + 1
+
+        controls[index] = spacer()
+        index = index + 1
+
+        -- the REV hubs default to 500-2500us; some servos need a narrower range
+        controls[index] = slider("SERVO_PwmMin", "PWM min (us)", 500, 2500, 500, 10, 0)
+        index = index + 1
+        controls[index] = slider("SERVO_PwmMax", "PWM max (us)", 500, 2500, 2500, 10, 0)
+        index = index + 1
+        controls[index] = slider("SERVO_ScaleMin", "Range min", 0, 1, 0, .01, 2)
+        index = index + 1
+        controls[index] = slider("SERVO_ScaleMax", "Range max", 0, 1, 1, .01, 2)
+        index = index + 1
+    end
 
     controls[index] = comboBox("SERVO_Direction", "Direction", "Forward", "Forward", "Reverse")
     index = index + 1
@@ -59,37 +104,134 @@ function tick()
             controlsChanged = true
         end
     end
+    if exists(SERVO_Mode) then
+        if SERVO_Mode.text ~= servo_mode then
+            servo_mode = SERVO_Mode.text
+            controlsChanged = true
+        end
+    end
 end
 
 function controls_changed()
     return controlsChanged
 end
 
+function restore_controls(values)
+    if values["SERVO_Name"] ~= nil and values["SERVO_Mode"] == nil then
+        default_mode = "Continuous"
+    end
+end
+
+function is_positional()
+    return servo_mode == "Positional"
+end
+
+function servo_class()
+    if is_positional() then
+        return "Servo"
+    end
+    return "CRServo"
+end
+
+-- The Java for the servo's target, which positional servos keep between loops
+function target()
+    return SERVO_Name.text .. "_target"
+end
+
+function get_hardware()
+    if not exists(SERVO_Name) then
+        return {}
+    end
+
+    return { { name = SERVO_Name.text, type = servo_class(), role = "servo" } }
+end
+
+function get_telemetry()
+    if not exists(SERVO_Name) then
+        return {}
+    end
+
+    local name = SERVO_Name.text
+    if is_positional() then
+        return {
+            { id = "position", java = name .. ".getPosition()", caption = name .. " position", format = "%.2f" },
+            { id = "target", java = target(), caption = name .. " target", format = "%.2f" },
+        }
+    end
+    return { { id = "power", java = name .. ".getPower()", caption = name .. " power", format = "%.2f" } }
+end
+
 function generate_includes()
-    return "import com.qualcomm.robotcore.hardware.CRServo;\n" ..
+    if not exists(SERVO_Name) then
+        return ""
+    end
+    string = "import com.qualcomm.robotcore.hardware." .. servo_class() .. ";\n" ..
         "import org.firstinspires.ftc.robotcore.external.Telemetry;\n" ..
         "import com.qualcomm.robotcore.hardware.HardwareMap;\n"
+    if is_positional() then
+        string = string .. "import com.qualcomm.robotcore.hardware.PwmControl;\n"
+    end
+    return string
 end
 
 function generate_globals()
     string = ""
     if exists(SERVO_Name) then
-        string = string .. "private CRServo " .. SERVO_Name.text .. " = null;\n\n"
+        string = string .. "private " .. servo_class() .. " " .. SERVO_Name.text .. " = null;\n\n"
+        if is_positional() then
+            string = string .. "private double " .. target() .. " = " .. SERVO_StartPosition.text .. ";\n"
+            if uses_axis() then
+                string = string .. "private ElapsedTime " .. SERVO_Name.text .. "_axis_timer = new ElapsedTime();\n"
+                string = string .. axis_code().fields
+            end
+            string = string .. "\n"
+        end
+        string = string .. button_logic().fields
     end
     return string
 end
 
+function button_logic()
+    local keybinds = {}
+    for i = 1, num_positions, 1 do
+        keybinds[i] = _G["SERVO_Keybind" .. i]
+    end
+    return buttonLogic(SERVO_Name.text, keybinds)
+end
+
+-- Whether a joystick axis moves the servo
+function uses_axis()
+    return is_positional() and exists(SERVO_AxisKeybind) and isAxis(SERVO_AxisKeybind.text) and
+        exists(SERVO_AxisShaping)
+end
+
+function axis_code()
+    return axisCode(SERVO_Name.text .. "_axis", SERVO_AxisShaping, gamepadInput(SERVO_AxisKeybind))
+end
+
 function generate_init()
     string = ""
 
     if exists(SERVO_Name) then
         string = string .. '// ' .. SERVO_Name.text .. ' init\n' ..
-            SERVO_Name.text .. ' = hardwareMap.get(CRServo.class, "' .. SERVO_Name.text .. '");\n'
+            SERVO_Name.text .. ' = hardwareMap.get(' .. servo_class() .. '.class, "' .. SERVO_Name.text .. '");\n'
 
         if SERVO_Direction.text == "Reverse" then
-            string = string .. SERVO_Name.text .. '.setDirection(CRServo.Direction.REVERSE);\n'
+            string = string .. SERVO_Name.text .. '.setDirection(' .. servo_class() .. '.Direction.REVERSE);\n'
         else
-            string = string .. SERVO_Name.text .. '.setDirection(CRServo.Direction.FORWARD);\n'
+            string = string .. SERVO_Name.text .. '.setDirection(' .. servo_class() .. '.Direction.FORWARD);\n'
+        end
+
+        if is_positional() then
+            if SERVO_PwmMin.value ~= 500 or SERVO_PwmMax.value ~= 2500 then
+                string = string .. "((PwmControl) " .. SERVO_Name.text .. ").setPwmRange(new PwmControl.PwmRange(" ..
+                    SERVO_PwmMin.text .. ", " .. SERVO_PwmMax.text .. "));\n"
+            end
+            if SERVO_ScaleMin.value ~= 0 or SERVO_ScaleMax.value ~= 1 then
+                string = string .. SERVO_Name.text .. ".scaleRange(" .. SERVO_ScaleMin.text .. ", " ..
+                    SERVO_ScaleMax.text .. ");\n"
+            end
+            string = string .. SERVO_Name.text .. ".setPosition(" .. target() .. ");\n"
         end
     end
     return string
@@ -100,8 +242,21 @@ function generate_loop()
     return generate_normal_loop()
 end
 
+-- The Java moving the servo to `value`: setting the target of a positional servo, or the power
+-- of a continuous one
+function move_to(value)
+    if is_positional() then
+        return target() .. " = " .. value .. ";\n"
+    end
+    return SERVO_Name.text .. ".setPower(" .. value .. ");\n"
+end
+
 function generate_normal_loop()
-    string = ""
+    if not exists(SERVO_Name) then
+        return ""
+    end
+    logic = button_logic()
+    string = logic.before
     default_string = ""
     added_first = false
     for i = 1, num_positions, 1 do
@@ -111,28 +266,41 @@ function generate_normal_loop()
             if isButton(keybind.text) then
                 if keybind.text == "default_button" then -- This has to come last in the generated code, so save it and add it later
                     default_string = default_string .. "else {\n"
-                    default_string = default_string ..
-                    "\t" .. SERVO_Name.text .. ".setPower(" .. position.text .. ");\n"
+                    default_string = default_string .. "\t" .. move_to(position.text)
                     default_string = default_string .. "}\n"
                 else
                     if added_first == false then
-                        string = string .. "if (gamepad1." .. keybind.text .. " > 0) {\n"
+                        string = string .. "if (" .. logic.conditions[i] .. ") {\n"
                         added_first = true
                     else
-                        string = string .. "else if (gamepad1." .. keybind.text .. " > 0) {\n"
+                        string = string .. "else if (" .. logic.conditions[i] .. ") {\n"
                     end
-                    string = string .. "\t" .. SERVO_Name.text .. ".setPower(" .. position.text .. ");\n"
+                    string = string .. "\t" .. move_to(position.text)
                     string = string .. "}\n"
                 end
             end
             if isAxis(_G["SERVO_Keybind" .. i].text) then -- This is in "run to position" mode so we just set the target position if the axis is pushed at all
                 -- TODO: Add a "scaled position" control where we set "Position * axis value)
-                string = string .. "if (gamepad1." .. keybind.text .. " > 0) {\n"
-                string = string .. "\t" .. SERVO_Name.text .. ".setPower(" .. position.text .. ");\n"
+                string = string .. "if (" .. gamepadInput(keybind) .. " > 0) {\n"
+                string = string .. "\t" .. move_to(position.text)
                 string = string .. "}\n"
             end
         end
     end
     string = string .. default_string
+    string = string .. logic.after
+
+    if uses_axis() then
+        -- nudge the target by how far the stick is pushed, however long the loop took
+        axis = axis_code()
+        timer = SERVO_Name.text .. "_axis_timer"
+        string = string .. axis.before
+        string = string .. target() .. " = Range.clip(" .. target() .. " + " .. axis.value .. " * " ..
+            SERVO_AxisSpeed.text .. " * " .. timer .. ".seconds(), 0, 1);\n"
+        string = string .. timer .. ".reset();\n"
+    end
+    if is_positional() then
+        string = string .. SERVO_Name.text .. ".setPosition(" .. target() .. ");\n"
+    end
     return string
 end
@@ -0,0 +1,116 @@
+require "lua_private.Helpers"
+
+function module_info()
+    return {
+        name = "Touch Sensor",
+        description = "A touch sensor or button, read as a TouchSensor or a DigitalChannel. Other components can act while it's pressed or released.",
+        author = "FTCreate",
+        version = "1.0.0",
+        category = "Sensors",
+        api_version = 7,
+    }
+end
+
+controlsChanged = true
+
+function get_controls()
+    controls = {}
+
+    index = 1
+
+    controls[index] = label("Touch Sensor")
+    index = index + 1
+
+    controls[index] = spacer()
+    index = index + 1
+
+    controls[index] = textInput("TOUCH_Name", "Touch sensor", "touchSensor")
+    index = index + 1
+
+    -- the REV touch sensor is a digital device: as a DigitalChannel it reads false when pressed
+    controls[index] = comboBox("TOUCH_Interface", "Read as", "TouchSensor", "TouchSensor", "DigitalChannel")
+    index = index + 1
+
+    controlsChanged = false
+
+    return controls
+end
+
+function tick()
+end
+
+function controls_changed()
+    return controlsChanged
+end
+
+-- The Java for whether the sensor is pressed this loop
+function pressed()
+    return TOUCH_Name.text .. "_pressed"
+end
+
+function get_hardware()
+    if not exists(TOUCH_Name) then
+        return {}
+    end
+
+    return { { name = TOUCH_Name.text, type = TOUCH_Interface.text, role = "sensor" } }
+end
+
+function get_conditions()
+    if not exists(TOUCH_Name) then
+        return {}
+    end
+
+    return {
+        { java = pressed(), description = TOUCH_Name.text .. " pressed" },
+        { java = "!" .. pressed(), description = TOUCH_Name.text .. " released" },
+    }
+end
+
+function generate_includes()
+    if not exists(TOUCH_Interface) then
+        return ""
+    end
+    return "import com.qualcomm.robotcore.hardware." .. TOUCH_Interface.text .. ";\n"
+end
+
+function generate_globals()
+    if not exists(TOUCH_Name) then
+        return ""
+    end
+    return "private " .. TOUCH_Interface.text .. " " .. TOUCH_Name.text .. " = null;\n\n"
+end
+
+function generate_init()
+    if not exists(TOUCH_Name) then
+        return ""
+    end
+
+    string = '// ' .. TOUCH_Name.text .. ' init\n' ..
+        TOUCH_Name.text .. ' = hardwareMap.get(' .. TOUCH_Interface.text .. '.class, "' .. TOUCH_Name.text .. '");\n'
+    if TOUCH_Interface.text == "DigitalChannel" then
+        string = string .. TOUCH_Name.text .. ".setMode(DigitalChannel.Mode.INPUT);\n"
+    end
+    return string .. "\n"
+end
+
+function generate_loop_one_time_setup()
+    if not exists(TOUCH_Name) then
+        return ""
+    end
+
+    if TOUCH_Interface.text == "DigitalChannel" then
+        return "boolean " .. pressed() .. " = !" .. TOUCH_Name.text .. ".getState();\n"
+    end
+    return "boolean " .. pressed() .. " = " .. TOUCH_Name.text .. ".isPressed();\n"
+end
+
+function get_telemetry()
+    if not exists(TOUCH_Name) then
+        return {}
+    end
+
+    return {
+        { id = "pressed", java = pressed() .. ' ? "pressed" : "released"', caption = TOUCH_Name.text },
+    }
+end
@@ -103,60 +103,118 @@ function axisComboBox(name, label, default)
 end
 
 
-function keybindingComboBox(name, label, default)
+-- A keybinding picker. `gamepad` is "gamepad1" (the default) or "gamepad2"; the user can change
+-- it next to the binding. Read the choice with `control.gamepad`, or use `gamepadInput(control)`.
+--
+-- Pass a `behaviour` ("held", "toggle", "rising_edge", "falling_edge" or "cycle") to let the
+-- user pick how a button binding behaves and add modifiers that have to be held too, such as
+-- "left_bumper" or "right_trigger > 0.5" (read them with `control.modifiers`). Generate the
+-- code for it with `buttonLogic`. `behaviours` limits the ones offered to a list of those names,
+-- for bindings the module can't stop again after a single press.
+function keybindingComboBox(name, label, default, gamepad, behaviour, behaviours)
+    local value = default
+    local modifiers = nil
+    gamepad = gamepad or "gamepad1"
     if exists(_G[name]) then
-        return pack("ComboBox", name, label, _G[name].text,
-            "none",
-            "default_button",
-            "a",
-            "b",
-            "x",
-            "y",
-            "left_stick_button",
-            "right_stick_button",
-            "left_bumper",
-            "right_bumper",
-            "dpad_left",
-            "dpad_right",
-            "dpad_up",
-            "dpad_down",
-            "start",
-            "select",
-            "left_trigger",
-            "right_trigger",
-            "left_stick_x",
-            "left_stick_y",
-            "right_stick_x",
-            "right_stick_y"
-        )
+        value = _G[name].text
+        gamepad = _G[name].gamepad
+        if behaviour ~= nil then
+            behaviour = _G[name].behaviour
+            modifiers = _G[name].modifiers
+        end
+    end
+
+    local control = pack("Keybinding", name, label, value, gamepad,
+        "none",
+        "default_button",
+        "a",
+        "b",
+        "x",
+        "y",
+        "left_stick_button",
+        "right_stick_button",
+        "left_bumper",
+        "right_bumper",
+        "dpad_left",
+        "dpad_right",
+        "dpad_up",
+        "dpad_down",
+        "start",
+        "select",
+        "left_trigger",
+        "right_trigger",
+        "left_stick_x",
+        "left_stick_y",
+        "right_stick_x",
+        "right_stick_y"
+    )
+    control.behaviour = behaviour
+    control.behaviours = behaviours
+    control.modifiers = modifiers
+
+    return control
+end
+
+-- buttonLogic(prefix, keybinds) is provided by FTCreate. `keybinds` is a list of keybinding
+-- controls and `prefix` starts the names of the fields tracking their buttons, e.g. the motor's
+-- name. It returns a table with:
+--   fields      declarations to add to generate_globals
+--   before      loop code to run before checking the conditions, e.g. flipping toggles
+--   conditions  the Java condition for each keybind set to a button, at the keybind's index
+--   after       loop code to run after checking the conditions
+-- Keybinds set to the same button with the "cycle" behaviour take turns firing, one per press.
+-- A keybind doesn't fire while one with the same button and more modifiers is held, so "a"
+-- stays quiet when "left_bumper" + "a" is pressed.
+
+-- Settings for shaping an analog axis: deadband, expo (0 linear to 1 cubic), scale, inversion
+-- and a slew rate limit (0 for none), read with `.deadband`, `.expo`, `.scale`, `.inverted`
+-- and `.slew_rate`. The default passes the axis through untouched.
+function axisShaping(name, label)
+    if exists(_G[name]) then
+        local shaping = _G[name]
+        return pack("AxisShaping", name, label, shaping.deadband, shaping.expo, shaping.scale,
+            shaping.inverted, shaping.slew_rate)
     else
-        return pack("ComboBox", name, label, default,
-            "none",
-            "default_button",
-            "a",
-            "b",
-            "x",
-            "y",
-            "left_stick_button",
-            "right_stick_button",
-            "left_bumper",
-            "right_bumper",
-            "dpad_left",
-            "dpad_right",
-            "dpad_up",
-            "dpad_down",
-            "start",
-            "select",
-            "left_trigger",
-            "right_trigger",
-            "left_stick_x",
-            "left_stick_y",
-            "right_stick_x",
-            "right_stick_y"
-        )
+        return pack("AxisShaping", name, label, 0, 0, 1, false, 0)
+    end
+end
+
+-- axisCode(prefix, shaping, input) is provided by FTCreate. `shaping` is an axisShaping control,
+-- `input` the Java for the axis, e.g. gamepadInput(keybind), and `prefix` starts the names of
+-- the fields a slew limit needs. It returns a table with:
+--   fields  declarations to add to generate_globals
+--   before  loop code to run before reading the value
+--   value   a Java expression for the shaped axis
+
+-- A picker for one of the conditions other components offer through get_conditions(), such as
+-- a limit switch being pressed. `.text` is the condition's Java expression, or "none". Check it's
+-- still offered with hasCondition before using it.
+function conditionComboBox(name, label)
+    if exists(_G[name]) then
+        return pack("Condition", name, label, _G[name].text)
+    else
+        return pack("Condition", name, label, "none")
     end
 end
 
+-- Whether some component on the robot offers the condition `java`. The list is in the
+-- `sensorConditions` global, as { java = ..., description = ... } entries.
+function hasCondition(java)
+    if not exists(sensorConditions) then
+        return false
+    end
+    for _, condition in ipairs(sensorConditions) do
+        if condition.java == java then
+            return true
+        end
+    end
+    return false
+end
+
+function gamepadComboBox(name, label, default)
+    return comboBox(name, label, default, "gamepad1", "gamepad2")
+end
+
 function slider(name, label, min, max, default, step, decimals)
     if exists(_G[name]) then
         return pack("Slider", name, label, min, max,
@@ -213,3 +271,8 @@ function isButton(keybinding)
     return keybindings[keybinding] == "button"
 end
 
+-- The Java for a keybinding control's input, e.g. "gamepad2.left_bumper"
+function gamepadInput(keybind)
+    return keybind.gamepad .. "." .. keybind.text
+end
+
@@ -1,19 +1,41 @@
 use crate::app::generators::generator::Generator;
-use std::fs;
 
+pub mod gamepad_map;
 pub mod generators;
-
+pub mod hardware_config;
+pub mod importer;
+pub mod java;
+pub mod project;
+pub mod teleop_profile;
+pub mod user_code;
+
+use self::gamepad_map::{GamepadMap, Rebind};
+use self::generators::autonomous::routine::{Autonomous, Component, StepContext};
+use self::generators::condition::Condition;
 use self::generators::generator::SubsystemGenerator;
+use self::generators::keybinding::registry::KeybindingRegistry;
 use self::generators::subsystem::subsystem::Subsystem;
+use self::generators::telemetry::{Telemetry, TelemetryItem};
+use self::hardware_config::HardwareConfig;
+use self::java::opmode::OpMode;
+use self::java::robot_hardware::{RobotHardware, ROBOT_HARDWARE_CLASS};
+use self::java::{JavaFile, USER_CODE_BEGIN, USER_CODE_END};
+use self::project::{Project, SubsystemFile, PROJECT_VERSION};
+use self::teleop_profile::{AppliedProfile, TeleOpProfile};
 use self::theme::Theme;
+use self::user_code::UserCode;
 
 use mlua::Lua;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use tokio::sync::{mpsc, mpsc::unbounded_channel};
 
-use crate::app::generators::lua_generator::ControlHandler;
+use crate::app::generators::lua_generator::{ControlHandler, GeneratorError};
+use crate::app::generators::module_library;
 #[cfg(not(target_arch = "wasm32"))]
 use tokio;
 #[cfg(not(target_arch = "wasm32"))]
@@ -28,11 +50,30 @@ pub mod theme;
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TemplateApp {
     label: String,
-    file_name: String,
+    /// Every TeleOp generated from the robot. There's always at least one.
+    profiles: Vec<TeleOpProfile>,
+    /// The TeleOp being edited, shown in the code panel
+    #[serde(skip)]
+    selected_profile: usize,
+    /// The robot as each TeleOp that changes it drives it, by [`TeleOpProfile::source`]
+    #[serde(skip)]
+    applied_profiles: RefCell<BTreeMap<u64, Rc<AppliedProfile>>>,
+    #[serde(skip)]
+    show_profiles: bool,
 
     drivetrain: Subsystem,
     subsystems: Vec<Subsystem>,
+    /// The generated file on show
     code: String,
+    #[serde(skip)]
+    files: Vec<JavaFile>,
+    /// Class of the file the user picked to show, if they did
+    #[serde(skip)]
+    selected_file: Option<String>,
+    #[serde(skip)]
+    shown_file: String,
+    /// Hand edits to the generated files
+    user_code: UserCode,
 
     #[serde(skip)]
     selected_subsystem: usize,
@@ -52,6 +93,39 @@ pub struct TemplateApp {
     lua: Lua,
     control_handler: ControlHandler,
     lua_scripts: Vec<String>,
+    /// Extra folders searched for Lua modules, after the built-in modules and the user modules
+    /// folder. Later folders override earlier ones.
+    module_paths: Vec<PathBuf>,
+
+    project_path: Option<PathBuf>,
+    #[serde(skip)]
+    project_status: String,
+
+    autonomous: Autonomous,
+    #[serde(skip)]
+    editing_autonomous: bool,
+
+    /// What the TeleOp shows on the Driver Station
+    telemetry: Telemetry,
+    #[serde(skip)]
+    show_telemetry: bool,
+
+    /// Refuse to upload while two components share a gamepad input
+    block_upload_on_conflicts: bool,
+
+    #[serde(skip)]
+    gamepad_map: GamepadMap,
+    #[serde(skip)]
+    show_gamepad_map: bool,
+
+    /// The hardware configuration last read from the Robot Controller
+    hardware_config: Option<HardwareConfig>,
+    #[serde(skip)]
+    hardware_config_tx: mpsc::UnboundedSender<Result<HardwareConfig, String>>,
+    #[serde(skip)]
+    hardware_config_rx: mpsc::UnboundedReceiver<Result<HardwareConfig, String>>,
+    #[serde(skip)]
+    show_hardware_config: bool,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
@@ -77,13 +151,21 @@ impl Default for TemplateApp {
     fn default() -> Self {
         let (tx, rx) = unbounded_channel::<UploadStatus>();
         let _ = tx.send(UploadStatus::DISCONNECTED);
+        let (hardware_config_tx, hardware_config_rx) = unbounded_channel();
 
         Self {
             label: "FTCreate".to_owned(),
-            file_name: "FTCreate".to_owned(),
+            profiles: vec![TeleOpProfile::default()],
+            selected_profile: 0,
+            applied_profiles: Default::default(),
+            show_profiles: false,
             drivetrain: Subsystem::new("Drivetrain".to_owned()),
             subsystems: vec![],
             code: "".to_string(),
+            files: vec![],
+            selected_file: None,
+            shown_file: "".into(),
+            user_code: UserCode::default(),
             selected_subsystem: 0,
             upload_status_tx: tx,
             upload_status_rx: rx,
@@ -92,11 +174,22 @@ impl Default for TemplateApp {
             #[cfg(not(target_arch = "wasm32"))]
             tokio_runtime: Runtime::new().unwrap(),
             lua: Lua::new(),
-            control_handler: ControlHandler {
-                scripts: vec![],
-                generators: vec![],
-            },
+            control_handler: ControlHandler::default(),
             lua_scripts: vec![],
+            module_paths: vec![],
+            project_path: None,
+            project_status: "".into(),
+            autonomous: Autonomous::default(),
+            editing_autonomous: false,
+            telemetry: Telemetry::default(),
+            show_telemetry: false,
+            block_upload_on_conflicts: false,
+            gamepad_map: GamepadMap::default(),
+            show_gamepad_map: false,
+            hardware_config: None,
+            hardware_config_tx,
+            hardware_config_rx,
+            show_hardware_config: false,
         }
     }
 }
@@ -114,189 +207,611 @@ impl TemplateApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let mut app: TemplateApp = match cc.storage {
+            Some(storage) => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
+            None => Default::default(),
+        };
+
+        // saved state may refer to modules by paths from an older version or another machine
+        app.reload_modules();
+
+        if app.profiles.is_empty() {
+            app.profiles.push(TeleOpProfile::default());
         }
 
-        let mut obj: TemplateApp = Default::default();
+        app
+    }
 
-        let paths = fs::read_dir("./lua_modules").unwrap();
+    /// Create an empty robot offering the built-in Lua modules, the user's modules and the
+    /// modules in each of `module_paths`
+    pub fn with_modules(module_paths: &[PathBuf]) -> std::io::Result<Self> {
+        let mut obj = TemplateApp {
+            module_paths: module_paths.to_vec(),
+            ..Default::default()
+        };
+        obj.load_modules()?;
 
-        for path in paths {
-            obj.lua_scripts
-                .push(path.unwrap().path().to_str().unwrap().to_string());
-        }
+        Ok(obj)
+    }
 
-        for script in &obj.lua_scripts {
+    /// Search for Lua modules again and rebuild every component from the modules found,
+    /// keeping its configuration
+    pub fn load_modules(&mut self) -> std::io::Result<()> {
+        let scripts = module_library::find_modules(&self.module_paths)?;
+
+        for script in &scripts {
             println!("Loading: {:?}", script);
-            obj.control_handler.scripts.push(script.clone());
         }
 
-        obj
+        for handler in self.control_handlers_mut() {
+            handler.set_scripts(&scripts);
+        }
+
+        self.lua_scripts = scripts;
+
+        Ok(())
     }
 
-    pub fn generate_code(&mut self) {
-        let mut new_code = String::new();
+    fn reload_modules(&mut self) {
+        if let Err(e) = self.load_modules() {
+            self.project_status = format!("Failed to load Lua modules: {}", e);
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
 
-        // standard includes
-        new_code += "package org.firstinspires.ftc.teamcode;\n\n";
-        new_code += "import com.qualcomm.robotcore.eventloop.opmode.LinearOpMode;\n";
-        new_code += "import com.qualcomm.robotcore.eventloop.opmode.TeleOp;\n";
-        new_code += "import com.qualcomm.robotcore.util.ElapsedTime;\n";
-        new_code += "import com.qualcomm.robotcore.util.Range;\n";
+    /// Every file of the project, with the user's edits merged in. Files whose edits conflict
+    /// with the generated code keep the code as last edited; see `conflicted_files`.
+    pub fn java_files(&mut self) -> Vec<JavaFile> {
+        let mut files = self.generated_files();
+        self.user_code.apply(&mut files);
+        files
+    }
 
-        // subsystem includes
-        let mut includes = self.drivetrain.generate_includes().to_string();
+    /// Files whose hand edits conflict with the code generated now, until the user settles them
+    pub fn conflicted_files(&self) -> Vec<String> {
+        self.user_code.conflicted()
+    }
 
-        self.subsystems.iter().for_each(|subsystem| {
-            includes += &subsystem.generate_includes().to_string();
-        });
+    /// Names more than one file would be written under; see [`teleop_profile::clashing_names`]
+    pub fn clashing_names(&self) -> Vec<String> {
+        teleop_profile::clashing_names(&self.profiles)
+    }
 
-        includes = includes
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n{}", line))
-            .collect::<String>();
-
-        let mut lua_includes = self.control_handler.generate_includes();
-        lua_includes = lua_includes
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n{}", line))
-            .collect::<String>();
-        includes += &lua_includes;
-
-        // remove duplicate includes
-        let mut includes_collection = includes.lines().collect::<Vec<&str>>();
-        includes_collection.sort();
-        includes_collection.dedup();
-
-        for include in includes_collection {
-            new_code += &include;
-            new_code += "\n";
+    /// Every file generated for the project: the hardware class, each TeleOp and, once it has
+    /// steps, the autonomous OpMode
+    fn generated_files(&self) -> Vec<JavaFile> {
+        let mut files = vec![JavaFile::new(
+            ROBOT_HARDWARE_CLASS,
+            self.robot_hardware().render(),
+        )];
+
+        for profile in &self.profiles {
+            let teleop = self.teleop_opmode(profile);
+            files.push(JavaFile::new(&teleop.class_name(), teleop.render()));
         }
 
-        new_code += "\n";
+        if !self.autonomous.steps.is_empty() {
+            let autonomous = self.autonomous_opmode();
+            files.push(JavaFile::new(&autonomous.class_name(), autonomous.render()));
+        }
 
-        new_code += &format!(
-            r#"@TeleOp(name="{} Teleop", group="Linear Opmode")"#,
-            &self.file_name
-        );
-        new_code += "\n";
-        new_code += &format!(
-            "public class {} extends LinearOpMode {{\n\
-                \n\tprivate ElapsedTime runtime = new ElapsedTime();\n",
-            &self.file_name
-        );
+        files
+    }
 
-        // global variables
-        let mut globals = self.drivetrain.generate_globals();
+    fn control_handlers_mut(&mut self) -> impl Iterator<Item = &mut ControlHandler> {
+        std::iter::once(&mut self.control_handler)
+            .chain(std::iter::once(&mut self.drivetrain.control_handler))
+            .chain(self.subsystems.iter_mut().map(|s| &mut s.control_handler))
+    }
 
-        self.subsystems.iter().for_each(|subsystem| {
-            globals += &subsystem.generate_globals();
-        });
+    fn control_handlers(&self) -> impl Iterator<Item = &ControlHandler> {
+        std::iter::once(&self.control_handler)
+            .chain(std::iter::once(&self.drivetrain.control_handler))
+            .chain(self.subsystems.iter().map(|s| &s.control_handler))
+    }
+
+    /// Load and tick every Lua generator the same way a frame of the UI would, without drawing
+    /// anything. Used when generating code headlessly.
+    pub fn refresh_generators(&mut self) -> Result<(), GeneratorError> {
+        for handler in self.control_handlers_mut() {
+            handler.tick_lua()?;
+            handler.add_controls()?;
+        }
+
+        // a second pass so components see the conditions the others offer
+        self.share_conditions();
+        for handler in self.control_handlers_mut() {
+            handler.tick_lua()?;
+            handler.add_controls()?;
+        }
+        Ok(())
+    }
+
+    /// Load and tick every Lua generator, including those of subsystems that aren't on screen.
+    /// Errors are kept on the component that raised them.
+    fn tick_generators(&mut self) {
+        for handler in self.control_handlers_mut() {
+            let _ = handler.tick_lua();
+            let _ = handler.add_controls();
+        }
+    }
+
+    /// Offer every component the conditions of the whole robot, for its condition pickers
+    fn share_conditions(&mut self) {
+        let conditions: Vec<Condition> = self
+            .components()
+            .iter()
+            .flat_map(|component| component.generator.conditions())
+            .collect();
+
+        for handler in self.control_handlers_mut() {
+            for generator in &mut handler.generators {
+                generator.sensor_conditions = conditions.clone();
+            }
+        }
+    }
+
+    /// Offer every component the devices in the robot's configuration, for its name fields
+    fn share_hardware_config(&mut self) {
+        let config = self.hardware_config.clone();
+        for handler in self.control_handlers_mut() {
+            handler.robot_config = config.clone();
+        }
+    }
+
+    /// Names of the hardware components set up that aren't in `config`, with their subsystem
+    pub fn unconfigured_hardware(&self, config: &HardwareConfig) -> Vec<(String, String)> {
+        self.components()
+            .iter()
+            .flat_map(|component| {
+                component
+                    .generator
+                    .hardware()
+                    .into_iter()
+                    .filter(|hardware| config.device(&hardware.name).is_none())
+                    .map(|hardware| (component.subsystem.to_string(), hardware.name))
+            })
+            .collect()
+    }
+
+    /// Every gamepad input bound anywhere on the robot
+    pub fn keybindings(&self) -> KeybindingRegistry {
+        KeybindingRegistry::new(&self.components())
+    }
+
+    /// The name of the first TeleOp, which also names the autonomous OpMode and the project file
+    fn name(&self) -> &str {
+        &self.profiles[0].name
+    }
+
+    /// The TeleOps, each with every gamepad input it binds
+    pub fn teleop_keybindings(&self) -> Vec<(String, KeybindingRegistry)> {
+        self.profiles
+            .iter()
+            .map(|profile| {
+                let keybindings = match profile.changes_robot() {
+                    true => {
+                        let applied = self.applied(profile);
+                        KeybindingRegistry::new(&components(
+                            &applied.drivetrain,
+                            &applied.subsystems,
+                        ))
+                    }
+                    false => self.keybindings(),
+                };
+                (profile.name.clone(), keybindings)
+            })
+            .collect()
+    }
+
+    /// Tell each component which of its inputs are shared with other components
+    fn flag_keybinding_conflicts(&mut self, keybindings: &KeybindingRegistry) {
+        for subsystem in std::iter::once(&mut self.drivetrain).chain(self.subsystems.iter_mut()) {
+            for (index, generator) in subsystem.control_handler.generators.iter_mut().enumerate() {
+                generator.keybinding_conflicts = keybindings.conflicts_for(&subsystem.name, index);
+            }
+        }
+    }
+
+    /// Point a keybinding control at a different input, as picked on the gamepad map
+    fn rebind(&mut self, rebind: &Rebind) {
+        let control = &rebind.control;
+
+        let generator = std::iter::once(&mut self.drivetrain)
+            .chain(self.subsystems.iter_mut())
+            .filter(|subsystem| subsystem.name == control.subsystem)
+            .find_map(|subsystem| {
+                subsystem
+                    .control_handler
+                    .generators
+                    .get_mut(control.component)
+            });
+
+        if let Some(generator) = generator {
+            generator.set_keybinding(&control.control, rebind.input);
+        }
+    }
+
+    /// Call every generator function of every Lua module, returning the first error
+    pub fn check_generators(&self) -> Result<(), GeneratorError> {
+        for handler in self.control_handlers() {
+            for function in [
+                "generate_includes",
+                "generate_globals",
+                "generate_init",
+                "generate_loop_one_time_setup",
+                "generate_loop",
+                "generate_methods",
+            ] {
+                handler.try_generate(function)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_project(&self) -> Project {
+        Project {
+            version: PROJECT_VERSION,
+            file_name: self.name().to_string(),
+            profiles: self.profiles.clone(),
+            drivetrain: SubsystemFile::from_subsystem(&self.drivetrain),
+            subsystems: self
+                .subsystems
+                .iter()
+                .map(SubsystemFile::from_subsystem)
+                .collect(),
+            autonomous: self.autonomous.clone(),
+            telemetry: self.telemetry.clone(),
+            user_code: self.user_code.clone(),
+        }
+    }
+
+    /// Replace the current robot with the one described by `project`
+    pub fn apply_project(&mut self, project: &Project) {
+        let scripts = self.control_handler.scripts.clone();
+
+        // projects from before TeleOp profiles have only the one TeleOp's name
+        self.profiles = match project.profiles.is_empty() {
+            true => vec![TeleOpProfile::new(&project.file_name)],
+            false => project.profiles.clone(),
+        };
+        self.selected_profile = 0;
+        self.drivetrain = project.drivetrain.to_subsystem(&scripts);
+        self.subsystems = project
+            .subsystems
+            .iter()
+            .map(|subsystem| subsystem.to_subsystem(&scripts))
+            .collect();
+        self.autonomous = project.autonomous.clone();
+        self.telemetry = project.telemetry.clone();
+        self.user_code = project.user_code.clone();
+        self.selected_subsystem = 0;
+        self.editing_autonomous = false;
+    }
+
+    pub fn new_project(&mut self) {
+        let mut drivetrain = Subsystem::new("Drivetrain".to_owned());
+        drivetrain.control_handler.scripts = self.control_handler.scripts.clone();
+
+        self.profiles = vec![TeleOpProfile::default()];
+        self.selected_profile = 0;
+        self.drivetrain = drivetrain;
+        self.subsystems = vec![];
+        self.autonomous = Autonomous::default();
+        self.telemetry = Telemetry::default();
+        self.user_code = UserCode::default();
+        self.selected_subsystem = 0;
+        self.editing_autonomous = false;
+        self.project_path = None;
+        self.project_status = "New project".into();
+    }
+
+    pub fn open_project(&mut self, path: &Path) {
+        match Project::load(path) {
+            Ok(project) => {
+                self.apply_project(&project);
+                self.project_path = Some(path.to_path_buf());
+                self.project_status = format!("Opened {}", path.display());
+            }
+            Err(e) => {
+                self.project_status = format!("Failed to open {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    pub fn save_project(&mut self, path: &Path) {
+        match self.to_project().save(path) {
+            Ok(_) => {
+                self.project_path = Some(path.to_path_buf());
+                self.project_status = format!("Saved {}", path.display());
+            }
+            Err(e) => {
+                self.project_status = format!("Failed to save {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Replace the current robot with one rebuilt from `source`, the Java of a hand-written
+    /// TeleOp. The code that couldn't be turned into components is kept as the TeleOp's user
+    /// code.
+    pub fn import_opmode(&mut self, source: &str) -> Result<importer::ImportReport, String> {
+        let imported = importer::import(source)?;
 
-        globals = globals
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n\t{}", line))
-            .collect::<String>();
-        new_code += &globals;
-
-        let mut lua_globals = self.control_handler.generate_globals();
-        lua_globals = lua_globals
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n\t{}", line))
-            .collect::<String>();
-        new_code += &lua_globals;
-
-        new_code += "@Override\n\
-        \tpublic void runOpMode() {\n\n\
-            \t\ttelemetry.addData(\"Status\", \"Initialized\");\n\
-            \t\ttelemetry.update();";
-        new_code += "\n\n";
-
-        // initializers
-        let mut init = self.drivetrain.generate_init();
-
-        self.subsystems.iter().for_each(|subsystem| {
-            init += &subsystem.generate_init();
+        self.apply_project(&imported.project);
+        self.project_path = None;
+        self.refresh_generators().map_err(|e| e.to_string())?;
+
+        let teleop = self.teleop_opmode(&self.profiles[0]);
+        self.user_code
+            .fill(&teleop.class_name(), &teleop.render(), &imported.regions);
+
+        Ok(imported.report)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn import_opmode_file(&mut self, path: &Path) {
+        let result = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| self.import_opmode(&source));
+
+        self.project_status = match result {
+            Ok(report) => format!("Imported {}", report),
+            Err(e) => format!("Failed to import {}: {}", path.display(), e),
+        };
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn project_dialog(&self) -> rfd::FileDialog {
+        let dialog = rfd::FileDialog::new()
+            .add_filter("FTCreate project", &[project::PROJECT_EXTENSION])
+            .set_file_name(format!("{}.{}", self.name(), project::PROJECT_EXTENSION));
+
+        match self.project_path.as_ref().and_then(|path| path.parent()) {
+            Some(dir) => dialog.set_directory(dir),
+            None => dialog,
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_file_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("File", |ui| {
+            if ui.button("New").clicked() {
+                self.new_project();
+                ui.close_menu();
+            }
+
+            if ui.button("Open...").clicked() {
+                if let Some(path) = self.project_dialog().pick_file() {
+                    self.open_project(&path);
+                }
+                ui.close_menu();
+            }
+
+            if ui.button("Save").clicked() {
+                let path = self
+                    .project_path
+                    .clone()
+                    .or_else(|| self.project_dialog().save_file());
+                if let Some(path) = path {
+                    self.save_project(&path);
+                }
+                ui.close_menu();
+            }
+
+            if ui.button("Save As...").clicked() {
+                if let Some(path) = self.project_dialog().save_file() {
+                    self.save_project(&path);
+                }
+                ui.close_menu();
+            }
+
+            ui.separator();
+
+            if ui.button("Import Java OpMode...").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Java OpMode", &["java"])
+                    .pick_file()
+                {
+                    self.import_opmode_file(&path);
+                }
+                ui.close_menu();
+            }
         });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_modules_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Modules", |ui| {
+            if let Some(dir) = module_library::user_modules_dir() {
+                ui.label(format!("User modules: {}", dir.display()))
+                    .on_hover_text("Modules here replace built-in modules with the same name");
+            }
+
+            ui.separator();
+
+            let mut removed_path = None;
+
+            for (i, path) in self.module_paths.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(path.display().to_string());
+                    if ui
+                        .button("✖")
+                        .on_hover_text("Stop using this folder")
+                        .clicked()
+                    {
+                        removed_path = Some(i);
+                    }
+                });
+            }
+
+            if let Some(i) = removed_path {
+                self.module_paths.remove(i);
+                self.reload_modules();
+            }
 
-        init = init
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n\t\t{}", line))
-            .collect::<String>();
-        new_code += &init;
-
-        let mut lua_init = self.control_handler.generate_init();
-        lua_init = lua_init
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n\t\t{}", line))
-            .collect::<String>();
-        new_code += &lua_init;
-
-        new_code += "waitForStart();\n\n\
-            \t\t// Reset the timer (stopwatch) because we only care about time since the game\n\
-            \t\t// actually starts\n\
-            \t\truntime.reset();\n\n\
-            \t\twhile (opModeIsActive()) {";
-
-        // loop one-time setup
-        let mut loop_1x = self.drivetrain.generate_loop_one_time_setup();
-
-        self.subsystems.iter().for_each(|subsystem| {
-            loop_1x += &subsystem.generate_loop_one_time_setup();
+            if ui.button("Add module folder...").clicked() {
+                if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                    self.module_paths.push(path);
+                    self.reload_modules();
+                }
+                ui.close_menu();
+            }
         });
+    }
+
+    /// Generate every file, showing the one picked or else the OpMode being edited: the
+    /// TeleOp picked, or the autonomous routine
+    pub fn generate_code(&mut self) {
+        let sources: Vec<u64> = self
+            .profiles
+            .iter()
+            .map(|profile| profile.source(&self.drivetrain, &self.subsystems))
+            .collect();
+        self.applied_profiles
+            .get_mut()
+            .retain(|source, _| sources.contains(source));
+
+        self.files = self.java_files();
+
+        let editing = if self.editing_autonomous {
+            OpMode::autonomous(self.name()).class_name()
+        } else {
+            OpMode::new(&self.profiles[self.selected_profile].name).class_name()
+        };
+        let shown = self
+            .files
+            .iter()
+            .find(|file| Some(&file.class_name) == self.selected_file.as_ref())
+            .or_else(|| self.files.iter().find(|file| file.class_name == editing));
+
+        (self.shown_file, self.code) = shown
+            .map(|file| (file.class_name.clone(), file.code.clone()))
+            .unwrap_or_default();
+    }
+
+    /// The class holding the robot's devices, which every OpMode extends
+    pub fn robot_hardware(&self) -> RobotHardware {
+        self.generate_hardware(&self.drivetrain, &self.subsystems, &mut OpMode::default())
+    }
+
+    /// The TeleOp `profile` describes. Fields only it needs, such as the state of a toggle the
+    /// robot as configured doesn't bind, are declared in the OpMode rather than the hardware
+    /// class.
+    pub fn teleop_opmode(&self, profile: &TeleOpProfile) -> OpMode {
+        let mut opmode = OpMode::new(&profile.name);
+
+        if !profile.changes_robot() {
+            self.generate_hardware(&self.drivetrain, &self.subsystems, &mut opmode);
+            self.telemetry
+                .generate(&mut opmode, &self.telemetry_items(&self.keybindings()));
+            return opmode;
+        }
+
+        let applied = self.applied(profile);
+        let hardware =
+            self.generate_hardware(&applied.drivetrain, &applied.subsystems, &mut opmode);
+        opmode.fields = hardware.fields_not_in(&self.robot_hardware());
+
+        let components = components(&applied.drivetrain, &applied.subsystems);
+        let keybindings = KeybindingRegistry::new(&components);
+        self.telemetry.generate(
+            &mut opmode,
+            &TelemetryItem::collect(&components, &keybindings),
+        );
+        opmode
+    }
+
+    /// The robot as `profile` drives it, built again only when the TeleOp or the robot has
+    /// changed since it was last asked for
+    fn applied(&self, profile: &TeleOpProfile) -> Rc<AppliedProfile> {
+        let source = profile.source(&self.drivetrain, &self.subsystems);
+        let mut applied_profiles = self.applied_profiles.borrow_mut();
+
+        if let Some(applied) = applied_profiles.get(&source) {
+            return applied.clone();
+        }
 
-        loop_1x = loop_1x
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n\t\t\t{}", line))
-            .collect::<String>();
-        new_code += &loop_1x;
-
-        let mut lua_one_time_setup = self.control_handler.generate_loop_one_time_setup();
-        lua_one_time_setup = lua_one_time_setup
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n\t\t\t{}", line))
-            .collect::<String>();
-        new_code += &lua_one_time_setup;
-
-        // loop
-        let mut _loop = self.drivetrain.generate_loop();
-
-        self.subsystems.iter_mut().for_each(|subsystem| {
-            _loop += &subsystem.generate_loop();
+        let (drivetrain, subsystems) = profile.apply(&self.drivetrain, &self.subsystems);
+        let applied = Rc::new(AppliedProfile {
+            drivetrain,
+            subsystems,
         });
+        applied_profiles.insert(source, applied.clone());
+        applied
+    }
+
+    /// Every value the telemetry designer can show
+    fn telemetry_items(&self, keybindings: &KeybindingRegistry) -> Vec<TelemetryItem> {
+        TelemetryItem::collect(&self.components(), keybindings)
+    }
 
-        _loop = _loop
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n\t\t\t{}", line))
-            .collect::<String>();
-        new_code += &_loop;
+    pub fn autonomous_opmode(&self) -> OpMode {
+        let mut opmode = OpMode::autonomous(self.name());
+        self.generate_hardware(&self.drivetrain, &self.subsystems, &mut opmode);
 
-        let mut lua_loop = self.control_handler.generate_loop();
-        lua_loop = lua_loop
-            .to_string()
-            .split("\n")
-            .map(|line| format!("\n\t\t\t{}", line))
-            .collect::<String>();
-        new_code += &lua_loop;
+        let components = self.components();
+        let hardware = StepContext::collect_hardware(&components);
+        self.autonomous
+            .generate(&mut opmode, &StepContext::new(components, &hardware));
 
-        new_code += "\n\t\t\ttelemetry.update();\n\
-                \t\t}\n\
-            \t}\n}";
+        opmode
+    }
+
+    /// Generate `drivetrain`, `subsystems` and the robot-wide components into `opmode`, then
+    /// move the code for the devices out into a hardware class, leaving the loop
+    fn generate_hardware(
+        &self,
+        drivetrain: &Subsystem,
+        subsystems: &[Subsystem],
+        opmode: &mut OpMode,
+    ) -> RobotHardware {
+        // chords bound anywhere on the robot take precedence over shorter ones
+        let components = components(drivetrain, subsystems);
+        let keybindings = KeybindingRegistry::new(&components);
+        for component in &components {
+            component
+                .generator
+                .share_chords(keybindings.chords_outside(component.subsystem, component.index));
+        }
+
+        drivetrain.generate(opmode);
+
+        for subsystem in subsystems {
+            subsystem.generate(opmode);
+        }
+
+        self.control_handler.generate_into(opmode);
+
+        RobotHardware::take_from(opmode)
+    }
 
-        self.code = new_code.to_string();
+    fn components(&self) -> Vec<Component<'_>> {
+        components(&self.drivetrain, &self.subsystems)
     }
 }
 
+/// Every component on the robot, with the subsystem it belongs to
+fn components<'a>(drivetrain: &'a Subsystem, subsystems: &'a [Subsystem]) -> Vec<Component<'a>> {
+    std::iter::once(drivetrain)
+        .chain(subsystems.iter())
+        .flat_map(|subsystem| {
+            subsystem
+                .control_handler
+                .generators
+                .iter()
+                .enumerate()
+                .map(|(index, generator)| Component {
+                    subsystem: &subsystem.name,
+                    index,
+                    generator,
+                })
+        })
+        .collect()
+}
+
 impl eframe::App for TemplateApp {
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
@@ -313,16 +828,153 @@ impl eframe::App for TemplateApp {
             }
         }
 
+        #[cfg(not(target_arch = "wasm32"))]
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                self.render_file_menu(ui);
+                self.render_modules_menu(ui);
+
+                ui.label(&self.project_status);
+            });
+        });
+
+        // subsystems that aren't on screen still need ticking for their keybindings
+        self.tick_generators();
+        self.share_conditions();
+        self.share_hardware_config();
+        let keybindings = self.keybindings();
+        self.flag_keybinding_conflicts(&keybindings);
+        let conflicts = keybindings.conflicts();
+
+        let rebind = egui::Window::new("Gamepad map")
+            .open(&mut self.show_gamepad_map)
+            .resizable(false)
+            .show(ctx, |ui| self.gamepad_map.render(ui, &keybindings))
+            .and_then(|response| response.inner)
+            .flatten();
+        if let Some(rebind) = rebind {
+            self.rebind(&rebind);
+        }
+
+        let telemetry_items = self.telemetry_items(&keybindings);
+        egui::Window::new("Telemetry")
+            .open(&mut self.show_telemetry)
+            .show(ctx, |ui| self.telemetry.render(ui, &telemetry_items));
+
+        let unconfigured = match &self.hardware_config {
+            Some(config) => self.unconfigured_hardware(config),
+            None => vec![],
+        };
+        egui::Window::new("Robot configuration")
+            .open(&mut self.show_hardware_config)
+            .show(ctx, |ui| match &self.hardware_config {
+                Some(config) => {
+                    config.render(ui);
+                    if !unconfigured.is_empty() {
+                        ui.separator();
+                        ui.label("Not in the robot's configuration:");
+                        for (subsystem, name) in &unconfigured {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                format!("⚠ {} ({})", name, subsystem),
+                            );
+                        }
+                    }
+                }
+                None => {
+                    ui.label("Connect to the robot's WiFi and read its configuration");
+                }
+            });
+
+        let subsystem_names: Vec<String> = std::iter::once(&self.drivetrain)
+            .chain(self.subsystems.iter())
+            .map(|subsystem| subsystem.name.clone())
+            .collect();
+        let picked = egui::Window::new("TeleOps")
+            .open(&mut self.show_profiles)
+            .show(ctx, |ui| {
+                teleop_profile::render_list(
+                    ui,
+                    &mut self.profiles,
+                    &mut self.selected_profile,
+                    &subsystem_names,
+                    &components(&self.drivetrain, &self.subsystems),
+                )
+            })
+            .and_then(|response| response.inner)
+            .unwrap_or(false);
+        if picked {
+            self.editing_autonomous = false;
+            self.selected_file = None;
+        }
+
         egui::SidePanel::right("code_panel").show(ctx, |ui| {
             self.control_handler.render(ui);
 
+            let heading = if conflicts.is_empty() {
+                "Keybindings".to_string()
+            } else {
+                format!("Keybindings (⚠ {} conflicts)", conflicts.len())
+            };
+            egui::CollapsingHeader::new(heading)
+                .id_source("Keybindings")
+                .show(ui, |ui| {
+                    keybindings.render(ui);
+                    ui.checkbox(
+                        &mut self.block_upload_on_conflicts,
+                        "Block upload while keybindings conflict",
+                    );
+                });
+
             ui.heading("Generated code");
+            ui.horizontal_wrapped(|ui| {
+                for file in &self.files {
+                    // files with hand edits are marked, and those needing attention more so
+                    let label = if self.user_code.needs_attention(&file.class_name) {
+                        format!("⚠ {}", file.file_name())
+                    } else if self.user_code.is_edited(&file.class_name) {
+                        format!("✏ {}", file.file_name())
+                    } else {
+                        file.file_name()
+                    };
+                    if ui
+                        .selectable_label(file.class_name == self.shown_file, label)
+                        .clicked()
+                    {
+                        self.selected_file = Some(file.class_name.clone());
+                    }
+                }
+            });
+            ui.label(format!(
+                "Write your own code between {} and {} so it's kept when the code is generated again",
+                USER_CODE_BEGIN, USER_CODE_END
+            ));
+            if self.user_code.is_edited(&self.shown_file)
+                && ui.button("Revert to generated code").clicked()
+            {
+                self.user_code.revert(&self.shown_file);
+            }
             egui::scroll_area::ScrollArea::horizontal().show(ui, |ui| {
                 egui::scroll_area::ScrollArea::vertical()
                     .auto_shrink([true; 2])
                     .show(ui, |ui| {
                         ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
-                            show_code(ui, &self.code, ui.available_width());
+                            self.user_code.render_problems(ui, &self.shown_file);
+                            if self.conflicted_files().contains(&self.shown_file) {
+                                return;
+                            }
+
+                            let mut code = self.code.clone();
+                            if syntax_highlighting::code_editor_ui(
+                                ui,
+                                &mut code,
+                                ui.available_width(),
+                            )
+                            .changed()
+                            {
+                                self.user_code.edit(&self.shown_file, &self.code, code.clone());
+                                self.code = code;
+                            }
                         });
                     });
             });
@@ -355,66 +1007,78 @@ impl eframe::App for TemplateApp {
                 };
             }
 
-            ui.label(&self.upload_status);
-
-            if ui.button("Upload code").clicked() {
-                let code = self.code.clone();
-                let tx = self.upload_status_tx.clone();
-                let file_name = self.file_name.clone();
-
-                self.tokio_runtime.spawn(async move {
-                    upload_code(code, &tx, file_name.to_owned()).await;
-                });
+            match self.hardware_config_rx.try_recv() {
+                Ok(Ok(config)) => {
+                    self.upload_status = format!(
+                        "Read the robot's configuration: {} devices",
+                        config.devices.len()
+                    );
+                    self.hardware_config = Some(config);
+                    self.show_hardware_config = true;
+                }
+                Ok(Err(e)) => {
+                    self.upload_status = format!("Couldn't read the robot's configuration: {}", e);
+                }
+                Err(_) => {}
             }
 
-            // TODO: Should no longer add scripts to generators
-            if ui.button("Reload all lua modules").clicked() {
-                self.lua_scripts.clear();
-                self.control_handler.generators.clear();
-                self.control_handler.scripts.clear();
-
-                let paths = fs::read_dir("./lua_modules").unwrap();
+            ui.label(&self.upload_status);
 
-                for path in paths {
-                    self.lua_scripts
-                        .push(path.unwrap().path().to_str().unwrap().to_string());
+            if ui.button("Upload code").clicked() {
+                if self.block_upload_on_conflicts {
+                    // a TeleOp's own bindings can conflict even if the robot's don't
+                    let conflicting: Vec<String> = self
+                        .teleop_keybindings()
+                        .into_iter()
+                        .filter(|(_, keybindings)| !keybindings.conflicts().is_empty())
+                        .map(|(name, _)| name)
+                        .collect();
+                    if !conflicting.is_empty() {
+                        self.upload_status = format!(
+                            "Not uploaded: keybindings conflict in {}",
+                            conflicting.join(", ")
+                        );
+                        return;
+                    }
                 }
 
-                for script in &self.lua_scripts {
-                    println!("Loading: {:?}", script);
-                    self.control_handler.scripts.push(script.clone());
+                let conflicted = self.conflicted_files();
+                if !conflicted.is_empty() {
+                    self.upload_status = format!(
+                        "Not uploaded: settle the conflicts with your edits to {} first",
+                        conflicted.join(", ")
+                    );
+                    return;
                 }
 
-                self.drivetrain.control_handler.generators.clear();
-                self.drivetrain.control_handler.scripts.clear();
-                self.drivetrain.control_handler.scripts = self.control_handler.scripts.clone();
-
-                for subsystem in &mut self.subsystems {
-                    subsystem.control_handler.generators.clear();
-                    subsystem.control_handler.scripts.clear();
-                    subsystem.control_handler.scripts = self.control_handler.scripts.clone();
+                let clashing = self.clashing_names();
+                if !clashing.is_empty() {
+                    self.upload_status = format!(
+                        "Not uploaded: more than one file is named {}",
+                        clashing.join(", ")
+                    );
+                    return;
                 }
-            }
 
-            if ui.button("Load new lua modules").clicked() {
-                let paths = fs::read_dir("./lua_modules").unwrap();
-
-                for path in paths {
-                    let script = path.unwrap().path().to_str().unwrap().to_string();
-                    if !self.lua_scripts.contains(&script) {
-                        println!("Loading: {:?}", &script);
+                let files = self.java_files();
+                let tx = self.upload_status_tx.clone();
 
-                        self.lua_scripts.push(script.clone());
+                self.tokio_runtime.spawn(async move {
+                    upload_code(files, &tx).await;
+                });
+            }
 
-                        self.control_handler.scripts.push(script.clone());
+            if ui.button("Read robot configuration").clicked() {
+                let tx = self.hardware_config_tx.clone();
+                self.upload_status = "Reading the robot's configuration".into();
 
-                        self.drivetrain.control_handler.scripts.push(script.clone());
+                self.tokio_runtime.spawn(async move {
+                    let _ = tx.send(read_hardware_config().await);
+                });
+            }
 
-                        for subsystem in &mut self.subsystems {
-                            subsystem.control_handler.scripts.push(script.clone());
-                        }
-                    }
-                }
+            if ui.button("Reload all lua modules").clicked() {
+                self.reload_modules();
             }
         });
 
@@ -429,16 +1093,37 @@ impl eframe::App for TemplateApp {
             egui::TopBottomPanel::top("subsystem_panel").show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("Subsystems: ");
-                    if ui.button("Drivetrain").clicked() {
+                    // subsystems with conflicting keybindings are marked
+                    let label = |name: &str, text: &str| {
+                        if conflicts
+                            .values()
+                            .flatten()
+                            .any(|binding| binding.subsystem == name)
+                        {
+                            format!("⚠ {}", text)
+                        } else {
+                            text.to_string()
+                        }
+                    };
+
+                    if ui
+                        .button(label(&self.drivetrain.name, "Drivetrain"))
+                        .clicked()
+                    {
                         self.selected_subsystem = 0;
+                        self.editing_autonomous = false;
+                        self.selected_file = None;
                     }
 
                     self.subsystems
                         .iter()
                         .enumerate()
                         .for_each(|(i, subsystem)| {
-                            if ui.button(subsystem.get_name()).clicked() {
+                            let name = subsystem.get_name();
+                            if ui.button(label(&name, &name)).clicked() {
                                 self.selected_subsystem = i + 1;
+                                self.editing_autonomous = false;
+                                self.selected_file = None;
                             }
                         });
 
@@ -450,20 +1135,35 @@ impl eframe::App for TemplateApp {
                         subsystem.control_handler.scripts = self.control_handler.scripts.clone();
                         self.subsystems.push(subsystem);
                         self.selected_subsystem = self.subsystems.len();
+                        self.editing_autonomous = false;
                     }
+
+                    ui.separator();
+
+                    if ui.button("Autonomous").clicked() {
+                        self.editing_autonomous = true;
+                        self.selected_file = None;
+                    }
+
+                    ui.separator();
+
+                    ui.toggle_value(&mut self.show_gamepad_map, "Gamepad map");
+                    ui.toggle_value(&mut self.show_telemetry, "Telemetry");
+                    ui.toggle_value(&mut self.show_profiles, "TeleOps");
+                    ui.toggle_value(&mut self.show_hardware_config, "Robot configuration");
                 });
             });
 
             ui.add_space(30.0);
 
-            if self.selected_subsystem == 0 {
-                ui.horizontal(|ui| {
-                    ui.label("Teleop name: ");
-                    ui.text_edit_singleline(&mut self.file_name);
-                });
-
-                ui.add_space(10.0);
+            if self.editing_autonomous {
+                ui.heading(format!("{} Auto Configuration", self.name()));
 
+                let components = components(&self.drivetrain, &self.subsystems);
+                let hardware = StepContext::collect_hardware(&components);
+                self.autonomous
+                    .render(ui, &StepContext::new(components, &hardware));
+            } else if self.selected_subsystem == 0 {
                 ui.heading("Drivetrain Configuration");
             } else {
                 ui.heading(format!(
@@ -476,34 +1176,54 @@ impl eframe::App for TemplateApp {
                 ));
 
                 ui.horizontal(|ui| {
-                    let text_edit = egui::TextEdit::singleline(
-                        &mut self
-                            .subsystems
-                            .iter_mut()
-                            .nth(self.selected_subsystem - 1)
-                            .unwrap()
-                            .name,
-                    )
-                    .desired_width(100.0);
-                    ui.add(text_edit);
+                    let subsystem = &mut self.subsystems[self.selected_subsystem - 1];
+                    let old_name = subsystem.name.clone();
+                    let text_edit =
+                        egui::TextEdit::singleline(&mut subsystem.name).desired_width(100.0);
+                    if ui.add(text_edit).changed() {
+                        for profile in &mut self.profiles {
+                            profile.rename_subsystem(&old_name, &subsystem.name);
+                        }
+                        self.autonomous.rename_subsystem(&old_name, &subsystem.name);
+                        self.telemetry.rename_subsystem(&old_name, &subsystem.name);
+                        self.user_code.rename_region(
+                            &Subsystem::loop_region(&old_name),
+                            &Subsystem::loop_region(&subsystem.name),
+                        );
+                    }
                     ui.label("Rename subsystem");
                 });
 
                 ui.add_space(10.0);
 
                 if ui.button("Delete subsystem").clicked() {
-                    self.subsystems.remove(self.selected_subsystem - 1);
+                    let subsystem = self.subsystems.remove(self.selected_subsystem - 1);
+                    for profile in &mut self.profiles {
+                        profile.remove_subsystem(&subsystem.name);
+                    }
+                    self.autonomous.remove_subsystem(&subsystem.name);
+                    self.telemetry.remove_subsystem(&subsystem.name);
                     self.selected_subsystem -= 1;
                 }
             }
-            if self.selected_subsystem == 0 {
-                self.drivetrain.render_options(ui, 0);
+            if self.editing_autonomous {
+                // the routine was drawn above
             } else {
-                self.subsystems
-                    .iter_mut()
-                    .nth(self.selected_subsystem - 1)
-                    .unwrap()
-                    .render_options(ui, 0);
+                let subsystem = match self.selected_subsystem {
+                    0 => &mut self.drivetrain,
+                    selected => &mut self.subsystems[selected - 1],
+                };
+                subsystem.render_options(ui, 0);
+
+                // TeleOps, module steps and telemetry refer to components by their position in the
+                // subsystem
+                for component in subsystem.control_handler.take_removed() {
+                    for profile in &mut self.profiles {
+                        profile.remove_component(&subsystem.name, component);
+                    }
+                    self.autonomous.remove_component(&subsystem.name, component);
+                    self.telemetry.remove_component(&subsystem.name, component);
+                }
             }
 
             self.generate_code();
@@ -516,39 +1236,8 @@ impl eframe::App for TemplateApp {
     }
 }
 
-fn show_code(ui: &mut egui::Ui, code: &str, width: f32) {
-    let code = remove_leading_indentation(code.trim_start_matches('\n'));
-    syntax_highlighting::code_view_ui(ui, &code, width);
-}
-
-fn remove_leading_indentation(code: &str) -> String {
-    fn is_indent(c: &u8) -> bool {
-        matches!(*c, b' ' | b'\t')
-    }
-
-    let first_line_indent = code.bytes().take_while(is_indent).count();
-
-    let mut out = String::new();
-
-    let mut code = code;
-    while !code.is_empty() {
-        let indent = code.bytes().take_while(is_indent).count();
-        let start = first_line_indent.min(indent);
-        let end = code
-            .find('\n')
-            .map_or_else(|| code.len(), |endline| endline + 1);
-        out += &code[start..end];
-        code = &code[end..];
-    }
-    out
-}
-
 #[cfg(not(target_arch = "wasm32"))]
-async fn upload_code(
-    code: String,
-    upload_status_tx: &mpsc::UnboundedSender<UploadStatus>,
-    file_name: String,
-) {
+async fn upload_code(files: Vec<JavaFile>, upload_status_tx: &mpsc::UnboundedSender<UploadStatus>) {
     let mut opt: ftc_http::Ftc = ftc_http::Ftc::default();
     opt.upload = true;
 
@@ -563,21 +1252,26 @@ async fn upload_code(
             // create a tmp directory to write files into
             let dir = tempfile::tempdir().unwrap();
 
-            // write teleop to a file
-            let file_path = dir.path().join(&format!("{}.java", &file_name));
-            let mut tmpfile = File::create(&file_path).unwrap();
-
-            write!(tmpfile, "{}", code).unwrap();
-
             let _ = upload_status_tx.send(UploadStatus::UPLOADING);
             println!("Uploading files...");
-            match r
-                .upload_files(
-                    vec![PathBuf::from(&file_path)],
-                    &format!("{}.java", &file_name),
-                )
-                .await
-            {
+
+            // every file goes up before the one build, since the OpModes need the hardware class
+            let mut uploaded = Ok(());
+            for file in &files {
+                let file_path = dir.path().join(file.file_name());
+                let mut tmpfile = File::create(&file_path).unwrap();
+
+                write!(tmpfile, "{}", file.code).unwrap();
+
+                uploaded = r
+                    .upload_files(vec![PathBuf::from(&file_path)], &file.file_name())
+                    .await;
+                if uploaded.is_err() {
+                    break;
+                }
+            }
+
+            match uploaded {
                 Ok(_) => {
                     let _ = upload_status_tx.send(UploadStatus::BUILDING);
                     match r.build().await {
@@ -602,3 +1296,20 @@ async fn upload_code(
         }
     };
 }
+
+/// Read the active hardware configuration from the Robot Controller that code is uploaded to
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_hardware_config() -> Result<HardwareConfig, String> {
+    let mut conf = ftc_http::AppConfig::default();
+
+    if ftc_http::RobotController::new(&mut conf).await.is_err() {
+        return Err("connection failed. Ensure you're on the robot's WiFi network".into());
+    }
+
+    // the same host ftc_http connected to, so a configured address is used for both
+    let address = hardware_config::address(&conf.host);
+    tokio::task::spawn_blocking(move || hardware_config::fetch(&address))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
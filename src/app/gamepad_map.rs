@@ -0,0 +1,815 @@
+use crate::app::generators::keybinding::keybinding::{Axis, BooleanButton, Gamepad};
+use crate::app::generators::keybinding::registry::{
+    GamepadInput, Input, KeybindingControl, KeybindingRegistry,
+};
+use egui::epaint::text::{FontDefinitions, Fonts};
+use egui::epaint::{ClippedShape, Primitive, Tessellator, Vertex};
+use egui::{
+    pos2, vec2, Align2, Color32, FontId, Pos2, Rect, Sense, Shape, Stroke, TextureId, Vec2,
+};
+use strum::IntoEnumIterator;
+
+const ARTWORK: &[u8] = include_bytes!("../../resources/gamepad_white.png");
+/// Size of the artwork, in its own pixels. Positions below are in the same pixels.
+const ARTWORK_SIZE: Vec2 = vec2(513.0, 397.0);
+const ARTWORK_SCALE: f32 = 0.75;
+
+const LABEL_WIDTH: f32 = 250.0;
+const LINE_HEIGHT: f32 = 15.0;
+const FONT_SIZE: f32 = 12.0;
+const MARGIN: f32 = 20.0;
+const MARKER_RADIUS: f32 = 5.0;
+
+/// Controls the artwork doesn't show, drawn over it
+enum Outline {
+    Circle(Pos2, f32),
+    Rect(Rect),
+}
+
+fn outlines() -> Vec<Outline> {
+    vec![
+        // sticks
+        Outline::Circle(pos2(200.0, 250.0), 24.0),
+        Outline::Circle(pos2(305.0, 250.0), 24.0),
+        // back and start
+        Outline::Circle(pos2(205.0, 150.0), 9.0),
+        Outline::Circle(pos2(300.0, 150.0), 9.0),
+        // bumpers
+        Outline::Rect(Rect::from_min_max(pos2(55.0, 44.0), pos2(145.0, 58.0))),
+        Outline::Rect(Rect::from_min_max(pos2(368.0, 44.0), pos2(458.0, 58.0))),
+        // triggers
+        Outline::Rect(Rect::from_min_max(pos2(48.0, 16.0), pos2(118.0, 36.0))),
+        Outline::Rect(Rect::from_min_max(pos2(395.0, 16.0), pos2(465.0, 36.0))),
+    ]
+}
+
+/// Where an input is on the artwork
+fn anchor(input: Input) -> Pos2 {
+    match input {
+        Input::Button(button) => match button {
+            BooleanButton::a => pos2(365.0, 230.0),
+            BooleanButton::b => pos2(408.0, 190.0),
+            BooleanButton::x => pos2(322.0, 190.0),
+            BooleanButton::y => pos2(365.0, 155.0),
+            BooleanButton::dpad_up => pos2(128.0, 165.0),
+            BooleanButton::dpad_down => pos2(128.0, 230.0),
+            BooleanButton::dpad_left => pos2(95.0, 197.0),
+            BooleanButton::dpad_right => pos2(162.0, 197.0),
+            BooleanButton::left_bumper => pos2(100.0, 51.0),
+            BooleanButton::right_bumper => pos2(413.0, 51.0),
+            BooleanButton::left_stick_button => pos2(200.0, 250.0),
+            BooleanButton::right_stick_button => pos2(305.0, 250.0),
+            BooleanButton::select => pos2(205.0, 150.0),
+            BooleanButton::start => pos2(300.0, 150.0),
+            BooleanButton::default => pos2(256.0, 200.0),
+        },
+        Input::Axis(axis) => match axis {
+            Axis::left_trigger => pos2(83.0, 26.0),
+            Axis::right_trigger => pos2(430.0, 26.0),
+            Axis::left_stick_x => pos2(214.0, 250.0),
+            Axis::left_stick_y => pos2(200.0, 236.0),
+            Axis::right_stick_x => pos2(319.0, 250.0),
+            Axis::right_stick_y => pos2(305.0, 236.0),
+            Axis::default => pos2(256.0, 200.0),
+        },
+    }
+}
+
+/// Every real input on a gamepad
+fn inputs() -> impl Iterator<Item = Input> {
+    BooleanButton::iter()
+        .filter(|button| *button != BooleanButton::default)
+        .map(Input::Button)
+        .chain(
+            Axis::iter()
+                .filter(|axis| *axis != Axis::default)
+                .map(Input::Axis),
+        )
+}
+
+/// One input on the map
+struct MapInput {
+    input: GamepadInput,
+    marker: Pos2,
+    /// What uses the input, one line each
+    users: Vec<String>,
+    conflict: bool,
+    /// Top of the label block. Labels on the left are right aligned against the artwork.
+    label: Pos2,
+    left: bool,
+}
+
+impl MapInput {
+    /// Where the leader line from the marker meets the label
+    fn label_anchor(&self) -> Pos2 {
+        let offset = if self.left { 4.0 } else { -4.0 };
+        pos2(self.label.x + offset, self.label.y + LINE_HEIGHT / 2.0)
+    }
+}
+
+/// Positions of everything on the map of one gamepad, shared by the UI and the SVG export.
+/// Only bound inputs get a label; labels are stacked down either side of the artwork.
+struct MapLayout {
+    size: Vec2,
+    artwork: Rect,
+    inputs: Vec<MapInput>,
+}
+
+impl MapLayout {
+    fn new(registry: &KeybindingRegistry, gamepad: Gamepad) -> Self {
+        let artwork = Rect::from_min_size(
+            pos2(LABEL_WIDTH + MARGIN, MARGIN),
+            ARTWORK_SIZE * ARTWORK_SCALE,
+        );
+        let by_input = registry.by_input();
+        let conflicts = registry.conflicts();
+
+        let mut inputs: Vec<MapInput> = inputs()
+            .map(|input| {
+                let input = GamepadInput::new(gamepad, input);
+                let marker = artwork.min + anchor(input.input).to_vec2() * ARTWORK_SCALE;
+                MapInput {
+                    input,
+                    marker,
+                    users: by_input
+                        .get(&input)
+                        .map(|bindings| bindings.iter().map(|b| b.to_string()).collect())
+                        .unwrap_or_default(),
+                    conflict: conflicts.contains_key(&input),
+                    label: marker,
+                    left: marker.x < artwork.center().x,
+                }
+            })
+            .collect();
+
+        inputs.sort_by(|a, b| a.marker.y.total_cmp(&b.marker.y));
+
+        let mut bottom = artwork.bottom();
+        for left in [true, false] {
+            let x = if left {
+                LABEL_WIDTH
+            } else {
+                artwork.right() + MARGIN
+            };
+            let mut next = MARGIN;
+
+            for input in inputs
+                .iter_mut()
+                .filter(|input| input.left == left && !input.users.is_empty())
+            {
+                let y = next.max(input.marker.y - LINE_HEIGHT / 2.0);
+                input.label = pos2(x, y);
+                next = y + LINE_HEIGHT * (input.users.len() + 1) as f32 + 4.0;
+            }
+            bottom = bottom.max(next);
+        }
+
+        Self {
+            size: vec2(artwork.right() + MARGIN + LABEL_WIDTH, bottom + MARGIN),
+            artwork,
+            inputs,
+        }
+    }
+}
+
+/// A change to a keybinding control requested from the map
+pub struct Rebind {
+    pub control: KeybindingControl,
+    /// `None` to unbind the control
+    pub input: Option<GamepadInput>,
+}
+
+/// A drawing of a gamepad with what each input is bound to. Clicking an input opens a picker
+/// to bind any keybinding control on the robot to it.
+#[derive(Default)]
+pub struct GamepadMap {
+    pub gamepad: Gamepad,
+    selected: Option<GamepadInput>,
+    texture: Option<egui::TextureHandle>,
+    status: String,
+}
+
+impl GamepadMap {
+    pub fn render(&mut self, ui: &mut egui::Ui, registry: &KeybindingRegistry) -> Option<Rebind> {
+        ui.horizontal(|ui| {
+            for gamepad in Gamepad::iter() {
+                ui.selectable_value(&mut self.gamepad, gamepad, format!("{:?}", gamepad));
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.separator();
+                self.render_export(ui, registry);
+            }
+        });
+        if !self.status.is_empty() {
+            ui.label(&self.status);
+        }
+
+        let layout = MapLayout::new(registry, self.gamepad);
+        let (response, painter) = ui.allocate_painter(layout.size, Sense::hover());
+        let offset = response.rect.min.to_vec2();
+
+        let visuals = ui.visuals().clone();
+        painter.rect_filled(response.rect, 4.0, visuals.extreme_bg_color);
+
+        let texture = self
+            .texture
+            .get_or_insert_with(|| {
+                ui.ctx()
+                    .load_texture("gamepad", artwork(), Default::default())
+            })
+            .id();
+        painter.image(
+            texture,
+            layout.artwork.translate(offset),
+            Rect::from_min_max(Pos2::ZERO, pos2(1.0, 1.0)),
+            visuals.text_color(),
+        );
+
+        let outline = Stroke::new(3.0, visuals.text_color());
+        for shape in outlines() {
+            match shape {
+                Outline::Circle(center, radius) => {
+                    painter.circle_stroke(
+                        layout.artwork.min + offset + center.to_vec2() * ARTWORK_SCALE,
+                        radius * ARTWORK_SCALE,
+                        outline,
+                    );
+                }
+                Outline::Rect(rect) => {
+                    painter.rect_stroke(
+                        Rect::from_min_max(
+                            layout.artwork.min + rect.min.to_vec2() * ARTWORK_SCALE,
+                            layout.artwork.min + rect.max.to_vec2() * ARTWORK_SCALE,
+                        )
+                        .translate(offset),
+                        3.0,
+                        outline,
+                    );
+                }
+            }
+        }
+
+        for input in &layout.inputs {
+            let color = if input.conflict {
+                visuals.warn_fg_color
+            } else if input.users.is_empty() {
+                visuals.weak_text_color()
+            } else {
+                visuals.selection.bg_fill
+            };
+            let marker = input.marker + offset;
+
+            if !input.users.is_empty() {
+                painter.line_segment(
+                    [marker, input.label_anchor() + offset],
+                    Stroke::new(1.0, color),
+                );
+
+                let align = if input.left {
+                    Align2::RIGHT_TOP
+                } else {
+                    Align2::LEFT_TOP
+                };
+                let lines =
+                    std::iter::once(input.input.input.to_string()).chain(input.users.clone());
+                for (i, line) in lines.enumerate() {
+                    painter.text(
+                        input.label + offset + vec2(0.0, LINE_HEIGHT * i as f32),
+                        align,
+                        line,
+                        FontId::proportional(FONT_SIZE),
+                        if i == 0 { color } else { visuals.text_color() },
+                    );
+                }
+            }
+
+            let hit = ui
+                .interact(
+                    Rect::from_center_size(marker, Vec2::splat(MARKER_RADIUS * 3.0)),
+                    response.id.with(input.input),
+                    Sense::click(),
+                )
+                .on_hover_text(input.input.to_string());
+
+            let selected = self.selected == Some(input.input);
+            painter.circle(
+                marker,
+                if hit.hovered() || selected {
+                    MARKER_RADIUS * 1.5
+                } else {
+                    MARKER_RADIUS
+                },
+                color,
+                Stroke::new(1.0, visuals.extreme_bg_color),
+            );
+
+            if hit.clicked() {
+                self.selected = if selected { None } else { Some(input.input) };
+            }
+        }
+
+        self.render_picker(ui, registry)
+    }
+
+    /// List what uses the selected input, and offer every keybinding control to bind to it
+    fn render_picker(
+        &mut self,
+        ui: &mut egui::Ui,
+        registry: &KeybindingRegistry,
+    ) -> Option<Rebind> {
+        let input = self.selected?;
+        let mut rebind = None;
+
+        ui.separator();
+        ui.heading(input.to_string());
+
+        for control in registry
+            .controls()
+            .iter()
+            .filter(|control| control.input == Some(input))
+        {
+            ui.horizontal(|ui| {
+                ui.label(control.to_string());
+                if ui.button("Unbind").clicked() {
+                    rebind = Some(Rebind {
+                        control: control.clone(),
+                        input: None,
+                    });
+                }
+            });
+        }
+        for binding in registry
+            .bindings()
+            .iter()
+            .filter(|binding| binding.input == input && binding.control.is_none())
+        {
+            ui.label(binding.to_string())
+                .on_hover_text("Set by the module. Change it in the component's controls.");
+        }
+
+        egui::ComboBox::from_label("Bind")
+            .selected_text("Pick a keybinding...")
+            .width(250.0)
+            .show_ui(ui, |ui| {
+                for control in registry
+                    .controls()
+                    .iter()
+                    .filter(|control| control.input != Some(input))
+                {
+                    let current = control
+                        .input
+                        .map_or("none".to_string(), |input| input.to_string());
+                    if ui
+                        .selectable_label(false, format!("{} (now {})", control, current))
+                        .clicked()
+                    {
+                        rebind = Some(Rebind {
+                            control: control.clone(),
+                            input: Some(input),
+                        });
+                    }
+                }
+            });
+
+        rebind
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn render_export(&mut self, ui: &mut egui::Ui, registry: &KeybindingRegistry) {
+        if ui.button("Export SVG...").clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("SVG image", &["svg"])
+                .set_file_name(format!("{:?}.svg", self.gamepad))
+                .save_file()
+            {
+                self.status = match std::fs::write(&path, svg(registry, &[self.gamepad])) {
+                    Ok(()) => format!("Saved {}", path.display()),
+                    Err(e) => format!("Couldn't save {}: {}", path.display(), e),
+                };
+            }
+        }
+
+        if ui.button("Export PNG...").clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("PNG image", &["png"])
+                .set_file_name(format!("{:?}.png", self.gamepad))
+                .save_file()
+            {
+                self.status = match png(registry, &[self.gamepad]).save(&path) {
+                    Ok(()) => format!("Saved {}", path.display()),
+                    Err(e) => format!("Couldn't save {}: {}", path.display(), e),
+                };
+            }
+        }
+    }
+}
+
+fn artwork() -> egui::ColorImage {
+    let image = image::load_from_memory(ARTWORK)
+        .expect("the gamepad artwork is a valid PNG")
+        .to_rgba8();
+    egui::ColorImage::from_rgba_unmultiplied(
+        [image.width() as usize, image.height() as usize],
+        image.as_raw(),
+    )
+}
+
+/// The maps of `gamepads` one above the other, each under a title, as the exports draw them.
+/// Gives each map with the top of its layout, and the size of the whole drawing.
+fn stacked(
+    registry: &KeybindingRegistry,
+    gamepads: &[Gamepad],
+) -> (Vec<(Gamepad, MapLayout, f32)>, Vec2) {
+    let mut layouts = vec![];
+    let mut size = Vec2::ZERO;
+
+    for gamepad in gamepads {
+        let layout = MapLayout::new(registry, *gamepad);
+        // leave room above for the title
+        let top = size.y + LINE_HEIGHT * 2.0;
+        size = vec2(size.x.max(layout.size.x), top + layout.size.y);
+        layouts.push((*gamepad, layout, top));
+    }
+
+    (layouts, size)
+}
+
+/// Draw the maps of `gamepads` one above the other as a printable SVG with a white background
+pub fn svg(registry: &KeybindingRegistry, gamepads: &[Gamepad]) -> String {
+    let (layouts, size) = stacked(registry, gamepads);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="{FONT_SIZE}">
+<defs><filter id="ink" color-interpolation-filters="sRGB"><feColorMatrix type="matrix" values="0 0 0 0 0.15  0 0 0 0 0.15  0 0 0 0 0.15  0 0 0 1 0"/></filter></defs>
+<rect width="100%" height="100%" fill="white"/>
+"#,
+        w = size.x,
+        h = size.y,
+    );
+
+    let artwork = base64(ARTWORK);
+
+    for (gamepad, layout, top) in &layouts {
+        svg += &format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-weight=\"bold\">{:?}</text>\n",
+            MARGIN,
+            top - 4.0,
+            FONT_SIZE * 1.5,
+            gamepad
+        );
+        svg += &format!("<g transform=\"translate(0 {})\">\n", top);
+
+        let origin = layout.artwork.min;
+        svg += &format!(
+            "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" filter=\"url(#ink)\" href=\"data:image/png;base64,{}\"/>\n",
+            origin.x,
+            origin.y,
+            layout.artwork.width(),
+            layout.artwork.height(),
+            artwork
+        );
+
+        for shape in outlines() {
+            svg += &match shape {
+                Outline::Circle(center, radius) => {
+                    let center = origin + center.to_vec2() * ARTWORK_SCALE;
+                    format!(
+                        "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"#262626\" stroke-width=\"3\"/>\n",
+                        center.x,
+                        center.y,
+                        radius * ARTWORK_SCALE
+                    )
+                }
+                Outline::Rect(rect) => {
+                    let min = origin + rect.min.to_vec2() * ARTWORK_SCALE;
+                    format!(
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"3\" fill=\"none\" stroke=\"#262626\" stroke-width=\"3\"/>\n",
+                        min.x,
+                        min.y,
+                        rect.width() * ARTWORK_SCALE,
+                        rect.height() * ARTWORK_SCALE
+                    )
+                }
+            };
+        }
+
+        for input in layout.inputs.iter().filter(|input| !input.users.is_empty()) {
+            let color = if input.conflict { "#d2691e" } else { "#1e64c8" };
+            let anchor = input.label_anchor();
+
+            svg += &format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\"/>\n",
+                input.marker.x, input.marker.y, anchor.x, anchor.y, color
+            );
+            svg += &format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"white\"/>\n",
+                input.marker.x, input.marker.y, MARKER_RADIUS, color
+            );
+
+            let lines = std::iter::once(input.input.input.to_string()).chain(input.users.clone());
+            for (i, line) in lines.enumerate() {
+                svg += &format!(
+                    "<text x=\"{}\" y=\"{}\" text-anchor=\"{}\" dominant-baseline=\"hanging\"{}>{}</text>\n",
+                    input.label.x,
+                    input.label.y + LINE_HEIGHT * i as f32,
+                    if input.left { "end" } else { "start" },
+                    if i == 0 {
+                        format!(" fill=\"{}\" font-weight=\"bold\"", color)
+                    } else {
+                        "".to_string()
+                    },
+                    escape(&line)
+                );
+            }
+        }
+
+        svg += "</g>\n";
+    }
+
+    svg += "</svg>\n";
+    svg
+}
+
+/// Pixels per point of the layout in PNG exports, so they print sharply
+const PNG_SCALE: f32 = 2.0;
+const ARTWORK_TEXTURE: TextureId = TextureId::User(0);
+
+/// Draw the same maps as [`svg`] as an image to save as a PNG, also on a white background.
+/// The drawing is tessellated like egui paints the map on screen, then filled in on the CPU.
+pub fn png(registry: &KeybindingRegistry, gamepads: &[Gamepad]) -> image::RgbaImage {
+    let (layouts, size) = stacked(registry, gamepads);
+    let fonts = Fonts::new(PNG_SCALE, 8192, FontDefinitions::default());
+    let ink = Color32::from_gray(0x26);
+    let mut shapes = vec![];
+
+    for (gamepad, layout, top) in &layouts {
+        let offset = vec2(0.0, *top);
+
+        shapes.push(Shape::text(
+            &fonts,
+            pos2(MARGIN, top - 4.0),
+            Align2::LEFT_BOTTOM,
+            format!("{:?}", gamepad),
+            FontId::proportional(FONT_SIZE * 1.5),
+            Color32::BLACK,
+        ));
+        shapes.push(Shape::image(
+            ARTWORK_TEXTURE,
+            layout.artwork.translate(offset),
+            Rect::from_min_max(Pos2::ZERO, pos2(1.0, 1.0)),
+            ink,
+        ));
+
+        let origin = layout.artwork.min + offset;
+        for shape in outlines() {
+            shapes.push(match shape {
+                Outline::Circle(center, radius) => Shape::circle_stroke(
+                    origin + center.to_vec2() * ARTWORK_SCALE,
+                    radius * ARTWORK_SCALE,
+                    Stroke::new(3.0, ink),
+                ),
+                Outline::Rect(rect) => Shape::rect_stroke(
+                    Rect::from_min_max(
+                        origin + rect.min.to_vec2() * ARTWORK_SCALE,
+                        origin + rect.max.to_vec2() * ARTWORK_SCALE,
+                    ),
+                    3.0,
+                    Stroke::new(3.0, ink),
+                ),
+            });
+        }
+
+        for input in layout.inputs.iter().filter(|input| !input.users.is_empty()) {
+            let color = if input.conflict {
+                Color32::from_rgb(0xd2, 0x69, 0x1e)
+            } else {
+                Color32::from_rgb(0x1e, 0x64, 0xc8)
+            };
+            let marker = input.marker + offset;
+
+            shapes.push(Shape::line_segment(
+                [marker, input.label_anchor() + offset],
+                Stroke::new(1.0, color),
+            ));
+            shapes.push(Shape::circle_filled(marker, MARKER_RADIUS, color));
+            shapes.push(Shape::circle_stroke(
+                marker,
+                MARKER_RADIUS,
+                Stroke::new(1.0, Color32::WHITE),
+            ));
+
+            let align = if input.left {
+                Align2::RIGHT_TOP
+            } else {
+                Align2::LEFT_TOP
+            };
+            let lines = std::iter::once(input.input.input.to_string()).chain(input.users.clone());
+            for (i, line) in lines.enumerate() {
+                shapes.push(Shape::text(
+                    &fonts,
+                    input.label + offset + vec2(0.0, LINE_HEIGHT * i as f32),
+                    align,
+                    line,
+                    FontId::proportional(FONT_SIZE),
+                    if i == 0 { color } else { Color32::BLACK },
+                ));
+            }
+        }
+    }
+
+    // the glyphs are only in the font atlas once the text has been laid out
+    let prepared_discs = fonts.texture_atlas().lock().prepared_discs();
+    let primitives = Tessellator::new(
+        PNG_SCALE,
+        Default::default(),
+        fonts.font_image_size(),
+        prepared_discs,
+    )
+    .tessellate_shapes(
+        shapes
+            .into_iter()
+            .map(|shape| ClippedShape {
+                clip_rect: Rect::from_min_size(Pos2::ZERO, size),
+                shape,
+            })
+            .collect(),
+    );
+
+    let font = fonts.image();
+    let font = Texture {
+        size: font.size,
+        pixels: font.srgba_pixels(None).collect(),
+    };
+    let artwork = {
+        let size = ARTWORK_SIZE * ARTWORK_SCALE * PNG_SCALE;
+        let image = image::imageops::resize(
+            &image::load_from_memory(ARTWORK)
+                .expect("the gamepad artwork is a valid PNG")
+                .to_rgba8(),
+            size.x.round() as u32,
+            size.y.round() as u32,
+            image::imageops::FilterType::Triangle,
+        );
+        Texture {
+            size: [image.width() as usize, image.height() as usize],
+            pixels: image
+                .pixels()
+                .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                .collect(),
+        }
+    };
+
+    let mut png = image::RgbaImage::from_pixel(
+        (size.x * PNG_SCALE).ceil() as u32,
+        (size.y * PNG_SCALE).ceil() as u32,
+        image::Rgba([255, 255, 255, 255]),
+    );
+    for primitive in primitives {
+        if let Primitive::Mesh(mesh) = primitive.primitive {
+            let texture = if mesh.texture_id == ARTWORK_TEXTURE {
+                &artwork
+            } else {
+                &font
+            };
+            for triangle in mesh.indices.chunks_exact(3) {
+                let vertices = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+                fill_triangle(&mut png, vertices, texture);
+            }
+        }
+    }
+
+    png
+}
+
+/// A texture for [`png`]: premultiplied pixels, row by row
+struct Texture {
+    size: [usize; 2],
+    pixels: Vec<Color32>,
+}
+
+impl Texture {
+    fn sample(&self, uv: Pos2) -> Color32 {
+        let x = ((uv.x * self.size[0] as f32) as usize).min(self.size[0] - 1);
+        let y = ((uv.y * self.size[1] as f32) as usize).min(self.size[1] - 1);
+        self.pixels[y * self.size[0] + x]
+    }
+}
+
+/// Blend a tessellated triangle onto `image`, the way the GPU would draw it on screen
+fn fill_triangle(image: &mut image::RgbaImage, vertices: [&Vertex; 3], texture: &Texture) {
+    let [a, b, c] = vertices.map(|v| v.pos * PNG_SCALE);
+
+    // twice the signed area, from which the weight of each corner at a pixel is worked out
+    let edge = |from: Pos2, to: Pos2, p: Pos2| {
+        (to.x - from.x) * (p.y - from.y) - (to.y - from.y) * (p.x - from.x)
+    };
+    let area = edge(a, b, c);
+    if area == 0.0 {
+        return;
+    }
+
+    let left = a.x.min(b.x).min(c.x).floor().max(0.0) as u32;
+    let top = a.y.min(b.y).min(c.y).floor().max(0.0) as u32;
+    let right = (a.x.max(b.x).max(c.x).ceil() as u32).min(image.width());
+    let bottom = (a.y.max(b.y).max(c.y).ceil() as u32).min(image.height());
+
+    for y in top..bottom {
+        for x in left..right {
+            let p = pos2(x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [
+                edge(b, c, p) / area,
+                edge(c, a, p) / area,
+                edge(a, b, p) / area,
+            ];
+            if weights.iter().any(|w| *w < 0.0) {
+                continue;
+            }
+
+            let uv = vertices
+                .iter()
+                .zip(weights)
+                .fold(Pos2::ZERO, |uv, (v, w)| uv + v.uv.to_vec2() * w);
+            let texel = texture.sample(uv);
+
+            let pixel = image.get_pixel_mut(x, y);
+            // vertex colours and texels are both premultiplied, so the source alpha is the
+            // last channel of their product
+            let source: [f32; 4] = std::array::from_fn(|i| {
+                let color: f32 = vertices
+                    .iter()
+                    .zip(weights)
+                    .map(|(v, w)| v.color[i] as f32 * w)
+                    .sum();
+                color * texel[i] as f32 / 255.0
+            });
+            let cover = 1.0 - source[3] / 255.0;
+            for i in 0..4 {
+                pixel[i] = (source[i] + pixel[i] as f32 * cover)
+                    .round()
+                    .clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAMEPADS: [Gamepad; 2] = [Gamepad::gamepad1, Gamepad::gamepad2];
+
+    #[test]
+    fn draws_the_png_at_the_size_of_the_svg() {
+        let registry = KeybindingRegistry::default();
+        let png = png(&registry, &GAMEPADS);
+
+        let (layouts, size) = stacked(&registry, &GAMEPADS);
+        assert!(svg(&registry, &GAMEPADS)
+            .contains(&format!("width=\"{}\" height=\"{}\"", size.x, size.y)));
+        assert_eq!(
+            (png.width(), png.height()),
+            (
+                (size.x * PNG_SCALE).ceil() as u32,
+                (size.y * PNG_SCALE).ceil() as u32
+            )
+        );
+
+        assert_eq!(png.get_pixel(0, 0), &image::Rgba([255, 255, 255, 255]));
+
+        // the left edge of the outline of gamepad2's left stick
+        let (_, layout, top) = &layouts[1];
+        let stick = layout.artwork.min + vec2(0.0, *top) + vec2(176.0, 250.0) * ARTWORK_SCALE;
+        let pixel = png.get_pixel((stick.x * PNG_SCALE) as u32, (stick.y * PNG_SCALE) as u32);
+        assert!(pixel[0] < 0x60, "{:?}", pixel);
+    }
+}
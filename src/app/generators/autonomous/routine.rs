@@ -0,0 +1,835 @@
+use crate::app::generators::autonomous::step::{Step, StepParam, StepType};
+use crate::app::generators::hardware::{Hardware, HardwareRole};
+use crate::app::generators::lua_generator::LuaGenerator;
+use crate::app::java::block::Block;
+use crate::app::java::opmode::OpMode;
+
+/// Directions the Control Hub's logo and USB ports can face, for the IMU orientation
+const HUB_DIRECTIONS: [&str; 6] = ["UP", "DOWN", "LEFT", "RIGHT", "FORWARD", "BACKWARD"];
+
+/// An autonomous routine: the steps to run in order, and how to measure drive distances and
+/// headings on this robot
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Autonomous {
+    pub steps: Vec<Step>,
+    /// Drive motor encoder counts per inch the robot travels
+    pub counts_per_inch: f64,
+    /// Turn power per degree of heading error
+    pub turn_gain: f64,
+    /// How close to the target heading, in degrees, a turn has to get
+    pub heading_tolerance: f64,
+    pub imu_name: String,
+    pub logo_direction: String,
+    pub usb_direction: String,
+}
+
+impl Default for Autonomous {
+    fn default() -> Self {
+        Self {
+            steps: vec![],
+            // goBILDA 312 RPM motor (537.7 counts per revolution) with 96mm mecanum wheels
+            counts_per_inch: 45.3,
+            turn_gain: 0.02,
+            heading_tolerance: 1.0,
+            imu_name: "imu".into(),
+            logo_direction: "UP".into(),
+            usb_direction: "FORWARD".into(),
+        }
+    }
+}
+
+/// A component autonomous steps can use: where it is, and the generator running its module
+pub struct Component<'a> {
+    pub subsystem: &'a str,
+    pub index: usize,
+    pub generator: &'a LuaGenerator,
+}
+
+/// A step type offered by one component, as listed in the "Add step" menu
+#[derive(Debug, Clone)]
+pub struct ModuleStepType {
+    pub subsystem: String,
+    pub component: usize,
+    /// Shown in the menu, e.g. "Arm: intakeMotor: Run intake"
+    pub label: String,
+    pub step: StepType,
+}
+
+/// What steps are generated against: the robot's components and the hardware they set up
+pub struct StepContext<'a> {
+    pub components: Vec<Component<'a>>,
+    pub hardware: &'a [Hardware],
+}
+
+impl<'a> StepContext<'a> {
+    pub fn new(components: Vec<Component<'a>>, hardware: &'a [Hardware]) -> Self {
+        Self {
+            components,
+            hardware,
+        }
+    }
+
+    /// Every piece of hardware set up by `components`
+    pub fn collect_hardware(components: &[Component<'_>]) -> Vec<Hardware> {
+        components
+            .iter()
+            .flat_map(|component| component.generator.hardware())
+            .collect()
+    }
+
+    pub fn drive_motors(&self) -> Vec<&Hardware> {
+        self.of_role(HardwareRole::DriveMotor)
+    }
+
+    pub fn of_role(&self, role: HardwareRole) -> Vec<&Hardware> {
+        self.hardware
+            .iter()
+            .filter(|hardware| hardware.role == role)
+            .collect()
+    }
+
+    pub fn component(&self, subsystem: &str, index: usize) -> Option<&LuaGenerator> {
+        self.components
+            .iter()
+            .find(|component| component.subsystem == subsystem && component.index == index)
+            .map(|component| component.generator)
+    }
+
+    pub fn step_types(&self) -> Vec<ModuleStepType> {
+        self.components
+            .iter()
+            .flat_map(|component| {
+                let hardware = component.generator.hardware();
+                let device = hardware
+                    .first()
+                    .map(|hardware| hardware.name.clone())
+                    .unwrap_or_else(|| format!("component {}", component.index + 1));
+
+                component
+                    .generator
+                    .autonomous_steps()
+                    .into_iter()
+                    .map(move |step| ModuleStepType {
+                        subsystem: component.subsystem.to_string(),
+                        component: component.index,
+                        label: format!("{}: {}: {}", component.subsystem, device, step.name),
+                        step,
+                    })
+            })
+            .collect()
+    }
+}
+
+impl Autonomous {
+    /// Add the steps, and the helpers and constants they need, to an autonomous `opmode`
+    pub fn generate(&self, opmode: &mut OpMode, context: &StepContext<'_>) {
+        let drive_motors = context.drive_motors();
+        let uses_drivetrain =
+            !drive_motors.is_empty() && self.steps.iter().any(Step::uses_drivetrain);
+        let uses_imu = uses_drivetrain && self.steps.iter().any(Step::uses_imu);
+
+        if uses_drivetrain {
+            opmode
+                .imports
+                .add("com.qualcomm.robotcore.hardware.DcMotor");
+
+            opmode.fields.section(
+                Block::new()
+                    .line(format!(
+                        "static final double COUNTS_PER_INCH = {};",
+                        self.counts_per_inch
+                    ))
+                    .line(format!(
+                        "static final double TURN_GAIN = {};",
+                        self.turn_gain
+                    ))
+                    .line(format!(
+                        "static final double HEADING_TOLERANCE = {};",
+                        self.heading_tolerance
+                    )),
+            );
+
+            opmode.members.section(&drive_helpers(&drive_motors));
+        }
+
+        if uses_imu {
+            opmode
+                .imports
+                .add("com.qualcomm.hardware.rev.RevHubOrientationOnRobot")
+                .add("com.qualcomm.robotcore.hardware.IMU")
+                .add("org.firstinspires.ftc.robotcore.external.navigation.AngleUnit");
+
+            opmode.fields.line("private IMU imu = null;");
+
+            opmode.init.section(
+                Block::new()
+                    .line("// IMU init")
+                    .line(format!(
+                        r#"imu = hardwareMap.get(IMU.class, "{}");"#,
+                        self.imu_name
+                    ))
+                    .line(format!(
+                        "imu.initialize(new IMU.Parameters(new RevHubOrientationOnRobot(RevHubOrientationOnRobot.LogoFacingDirection.{}, RevHubOrientationOnRobot.UsbFacingDirection.{})));",
+                        self.logo_direction, self.usb_direction
+                    ))
+                    .line("imu.resetYaw();"),
+            );
+
+            opmode.members.section(Block::new().code(
+                "private double headingError(double heading) {\n\
+                 double error = heading - imu.getRobotYawPitchRollAngles().getYaw(AngleUnit.DEGREES);\n\
+                 while (error > 180) {\n\
+                 error -= 360;\n\
+                 }\n\
+                 while (error <= -180) {\n\
+                 error += 360;\n\
+                 }\n\
+                 return error;\n\
+                 }",
+            ));
+        }
+
+        for (i, step) in self.steps.iter().enumerate() {
+            let id = (i + 1).to_string();
+            let description = format!("{}. {}", id, step.describe());
+            let code = step.code(context, &id);
+
+            let mut block = Block::new();
+            block
+                .line(format!("// {}", description))
+                .append(&code.start);
+
+            if let Some(busy) = &code.busy {
+                let mut body = code.update.clone();
+                body.section(
+                    Block::new()
+                        .line(format!(
+                            r#"telemetry.addData("Step", "{}");"#,
+                            description.replace('\\', "\\\\").replace('"', "\\\"")
+                        ))
+                        .line("telemetry.update();"),
+                );
+                block.nest(&format!("while (opModeIsActive() && ({}))", busy), &body);
+            }
+
+            block.append(&code.finish);
+            opmode.run.section(&block);
+        }
+    }
+
+    pub fn render(&mut self, ui: &mut egui::Ui, context: &StepContext<'_>) {
+        egui::scroll_area::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                ui.add_space(20.0);
+
+                ui.collapsing("Robot measurements", |ui| {
+                    egui::Grid::new("autonomous_settings").show(ui, |ui| {
+                        ui.label("Encoder counts per inch");
+                        ui.add(egui::DragValue::new(&mut self.counts_per_inch).speed(0.1))
+                            .on_hover_text(
+                                "Motor encoder counts per revolution ÷ (wheel diameter in inches × π)",
+                            );
+                        ui.end_row();
+
+                        ui.label("Turn gain");
+                        ui.add(egui::DragValue::new(&mut self.turn_gain).speed(0.001));
+                        ui.end_row();
+
+                        ui.label("Heading tolerance (degrees)");
+                        ui.add(egui::DragValue::new(&mut self.heading_tolerance).speed(0.1));
+                        ui.end_row();
+
+                        ui.label("IMU name");
+                        ui.text_edit_singleline(&mut self.imu_name);
+                        ui.end_row();
+
+                        for (label, value) in [
+                            ("Hub logo faces", &mut self.logo_direction),
+                            ("Hub USB ports face", &mut self.usb_direction),
+                        ] {
+                            ui.label(label);
+                            egui::ComboBox::from_id_source(label)
+                                .selected_text(value.as_str())
+                                .show_ui(ui, |ui| {
+                                    for direction in HUB_DIRECTIONS {
+                                        ui.selectable_value(value, direction.to_string(), direction);
+                                    }
+                                });
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                if context.drive_motors().is_empty() {
+                    ui.label("Drive and turn steps need DC Motors marked as drivetrain motors.");
+                }
+
+                ui.add_space(10.0);
+
+                let step_types = context.step_types();
+                render_steps(ui, &mut self.steps, context, &step_types, true);
+            });
+    }
+
+    /// Keep module steps pointing at their component when the one at `component` in
+    /// `subsystem` is removed: its own steps are dropped and those after it move up one
+    pub fn remove_component(&mut self, subsystem: &str, component: usize) {
+        retain_module_steps(&mut self.steps, &mut |step_subsystem, step_component| {
+            if step_subsystem != subsystem || *step_component < component {
+                return true;
+            }
+            if *step_component == component {
+                return false;
+            }
+            *step_component -= 1;
+            true
+        });
+    }
+
+    /// Keep module steps of a subsystem when it's renamed
+    pub fn rename_subsystem(&mut self, old: &str, new: &str) {
+        retain_module_steps(&mut self.steps, &mut |subsystem, _| {
+            if subsystem == old {
+                *subsystem = new.to_string();
+            }
+            true
+        });
+    }
+
+    /// Drop the module steps of a deleted subsystem, so they don't carry over to a new one
+    /// given the same name
+    pub fn remove_subsystem(&mut self, name: &str) {
+        retain_module_steps(&mut self.steps, &mut |subsystem, _| subsystem != name);
+    }
+}
+
+/// Drop the module steps, in groups too, that `keep` returns false for. `keep` is given each
+/// step's subsystem and component, and can change them.
+fn retain_module_steps(
+    steps: &mut Vec<Step>,
+    keep: &mut impl FnMut(&mut String, &mut usize) -> bool,
+) {
+    steps.retain_mut(|step| match step {
+        Step::Module {
+            subsystem,
+            component,
+            ..
+        } => keep(subsystem, component),
+        Step::Parallel { steps } => {
+            retain_module_steps(steps, keep);
+            true
+        }
+        _ => true,
+    });
+}
+
+/// Show an editable list of steps. Groups run in parallel can't contain other groups.
+fn render_steps(
+    ui: &mut egui::Ui,
+    steps: &mut Vec<Step>,
+    context: &StepContext<'_>,
+    step_types: &[ModuleStepType],
+    top_level: bool,
+) {
+    let mut moved: Option<(usize, usize)> = None;
+    let mut removed: Option<usize> = None;
+    let count = steps.len();
+
+    for (i, step) in steps.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}. {}", i + 1, step.title()));
+
+                if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                    moved = Some((i, i - 1));
+                }
+                if ui
+                    .add_enabled(i + 1 < count, egui::Button::new("⬇"))
+                    .clicked()
+                {
+                    moved = Some((i, i + 1));
+                }
+                if ui.button("✖").on_hover_text("Remove step").clicked() {
+                    removed = Some(i);
+                }
+            });
+
+            ui.indent("step", |ui| {
+                render_step(ui, step, context, step_types);
+            });
+        });
+    }
+
+    if let Some((from, to)) = moved {
+        steps.swap(from, to);
+    }
+    if let Some(i) = removed {
+        steps.remove(i);
+    }
+
+    ui.menu_button(
+        if top_level {
+            "Add step"
+        } else {
+            "Add to group"
+        },
+        |ui| {
+            // the steps of a group all run at once, so only one of them can drive the robot
+            let driving = !top_level && steps.iter().any(Step::uses_drivetrain);
+
+            for template in Step::templates() {
+                if !top_level && matches!(template, Step::Parallel { .. }) {
+                    continue;
+                }
+                let button = ui
+                    .add_enabled(
+                        !(driving && template.uses_drivetrain()),
+                        egui::Button::new(template.title()),
+                    )
+                    .on_disabled_hover_text("Another step in this group already drives the robot");
+                if button.clicked() {
+                    steps.push(template);
+                    ui.close_menu();
+                }
+            }
+
+            if !step_types.is_empty() {
+                ui.separator();
+            }
+
+            for step_type in step_types {
+                if ui.button(&step_type.label).clicked() {
+                    steps.push(Step::Module {
+                        subsystem: step_type.subsystem.clone(),
+                        component: step_type.component,
+                        name: step_type.step.name.clone(),
+                        params: step_type.step.params.clone(),
+                    });
+                    ui.close_menu();
+                }
+            }
+        },
+    );
+}
+
+fn render_step(
+    ui: &mut egui::Ui,
+    step: &mut Step,
+    context: &StepContext<'_>,
+    step_types: &[ModuleStepType],
+) {
+    match step {
+        Step::Drive { inches, power } | Step::Strafe { inches, power } => {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(inches).suffix(" in").speed(0.5));
+                ui.label("at power");
+                ui.add(
+                    egui::DragValue::new(power)
+                        .clamp_range(0.0..=1.0)
+                        .speed(0.01),
+                );
+            });
+        }
+        Step::TurnToHeading { degrees, power } => {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(degrees)
+                        .suffix("°")
+                        .clamp_range(-180.0..=180.0),
+                );
+                ui.label("at power");
+                ui.add(
+                    egui::DragValue::new(power)
+                        .clamp_range(0.0..=1.0)
+                        .speed(0.01),
+                );
+            });
+        }
+        Step::RunMotorToPosition {
+            motor,
+            position,
+            velocity,
+        } => {
+            ui.horizontal(|ui| {
+                hardware_combo(ui, "Motor", motor, &context.of_role(HardwareRole::Motor));
+                ui.label("to");
+                ui.add(egui::DragValue::new(position));
+                ui.label("at velocity");
+                ui.add(egui::DragValue::new(velocity).clamp_range(0.0..=12000.0));
+            });
+        }
+        Step::SetServo { servo, position } => {
+            ui.horizontal(|ui| {
+                hardware_combo(ui, "Servo", servo, &context.of_role(HardwareRole::Servo));
+                ui.label("to");
+                ui.add(
+                    egui::DragValue::new(position)
+                        .clamp_range(-1.0..=1.0)
+                        .speed(0.01),
+                );
+            });
+        }
+        Step::Wait { seconds } => {
+            ui.add(
+                egui::DragValue::new(seconds)
+                    .suffix(" s")
+                    .clamp_range(0.0..=30.0)
+                    .speed(0.1),
+            );
+        }
+        Step::Parallel { steps } => {
+            render_steps(ui, steps, context, step_types, false);
+        }
+        Step::Module {
+            subsystem,
+            component,
+            params,
+            ..
+        } => {
+            if context.component(subsystem, *component).is_none() {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("{} no longer has the component for this step", subsystem),
+                );
+            }
+
+            for StepParam { name, value } in params {
+                ui.horizontal(|ui| {
+                    ui.label(name.as_str());
+                    ui.add(egui::TextEdit::singleline(value).desired_width(80.0));
+                });
+            }
+        }
+    }
+}
+
+fn hardware_combo(ui: &mut egui::Ui, label: &str, selected: &mut String, hardware: &[&Hardware]) {
+    egui::ComboBox::from_id_source(label)
+        .selected_text(if selected.is_empty() {
+            format!("Choose {}", label.to_lowercase())
+        } else {
+            selected.clone()
+        })
+        .show_ui(ui, |ui| {
+            for device in hardware {
+                ui.selectable_value(selected, device.name.clone(), &device.name);
+            }
+        });
+}
+
+/// Helpers the drive and turn steps call, written for the robot's drive motors
+fn drive_helpers(drive_motors: &[&Hardware]) -> Block {
+    let mut start_drive = Block::new();
+    start_drive
+        .line("int forward = (int) Math.round(forwardInches * COUNTS_PER_INCH);")
+        .line("int strafe = (int) Math.round(strafeInches * COUNTS_PER_INCH);");
+
+    let mut set_mode = Block::new();
+    let mut set_power = Block::new();
+    let mut busy = vec![];
+
+    for motor in drive_motors {
+        let (strafe, left) = match motor.position.as_str() {
+            "Front Left" => (" + strafe", true),
+            "Front Right" => (" - strafe", false),
+            "Rear Left" => (" - strafe", true),
+            "Rear Right" => (" + strafe", false),
+            "Right" => ("", false),
+            _ => ("", true),
+        };
+
+        start_drive.line(format!(
+            "{0}.setTargetPosition({0}.getCurrentPosition() + forward{1});",
+            motor.name, strafe
+        ));
+        set_mode.line(format!("{}.setMode(mode);", motor.name));
+        set_power.line(format!(
+            "{}.setPower({});",
+            motor.name,
+            if left { "left" } else { "right" }
+        ));
+        busy.push(format!("{}.isBusy()", motor.name));
+    }
+
+    start_drive
+        .line("setDriveMode(DcMotor.RunMode.RUN_TO_POSITION);")
+        .line("setDrivePower(Math.abs(power), Math.abs(power));");
+
+    let mut helpers = Block::new();
+    helpers
+        .nest(
+            "private void startDrive(double forwardInches, double strafeInches, double power)",
+            &start_drive,
+        )
+        .blank()
+        .nest("private void setDriveMode(DcMotor.RunMode mode)", &set_mode)
+        .blank()
+        .nest(
+            "private void setDrivePower(double left, double right)",
+            &set_power,
+        )
+        .blank()
+        .nest(
+            "private boolean driveBusy()",
+            Block::new().line(format!("return {};", busy.join(" && "))),
+        );
+
+    helpers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module_step(subsystem: &str, component: usize) -> Step {
+        Step::Module {
+            subsystem: subsystem.into(),
+            component,
+            name: "Run intake".into(),
+            params: vec![],
+        }
+    }
+
+    fn drive_motor(name: &str, position: &str) -> Hardware {
+        Hardware {
+            name: name.into(),
+            java_type: "DcMotorEx".into(),
+            role: HardwareRole::DriveMotor,
+            drivetrain: "Mecanum".into(),
+            position: position.into(),
+        }
+    }
+
+    fn mecanum() -> Vec<Hardware> {
+        vec![
+            drive_motor("frontLeft", "Front Left"),
+            drive_motor("frontRight", "Front Right"),
+            drive_motor("rearLeft", "Rear Left"),
+            drive_motor("rearRight", "Rear Right"),
+        ]
+    }
+
+    /// The autonomous OpMode generated for `steps` on a robot with `hardware`
+    fn generate(steps: Vec<Step>, hardware: &[Hardware]) -> String {
+        let autonomous = Autonomous {
+            steps,
+            ..Default::default()
+        };
+        let mut opmode = OpMode::autonomous("MyBot");
+        autonomous.generate(&mut opmode, &StepContext::new(vec![], hardware));
+        opmode.render()
+    }
+
+    fn routine() -> Autonomous {
+        Autonomous {
+            steps: vec![
+                module_step("Arm", 0),
+                Step::Wait { seconds: 1.0 },
+                Step::Parallel {
+                    steps: vec![module_step("Arm", 1), module_step("Intake", 1)],
+                },
+                module_step("Arm", 2),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn drops_steps_of_a_removed_component_and_moves_later_ones_up() {
+        let mut autonomous = routine();
+        autonomous.remove_component("Arm", 1);
+
+        assert_eq!(
+            autonomous.steps,
+            vec![
+                module_step("Arm", 0),
+                Step::Wait { seconds: 1.0 },
+                Step::Parallel {
+                    steps: vec![module_step("Intake", 1)],
+                },
+                module_step("Arm", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn follows_a_renamed_subsystem() {
+        let mut autonomous = routine();
+        autonomous.rename_subsystem("Arm", "Lift");
+
+        assert_eq!(
+            autonomous.steps,
+            vec![
+                module_step("Lift", 0),
+                Step::Wait { seconds: 1.0 },
+                Step::Parallel {
+                    steps: vec![module_step("Lift", 1), module_step("Intake", 1)],
+                },
+                module_step("Lift", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_steps_of_a_deleted_subsystem() {
+        let mut autonomous = routine();
+        autonomous.remove_subsystem("Arm");
+
+        assert_eq!(
+            autonomous.steps,
+            vec![
+                Step::Wait { seconds: 1.0 },
+                Step::Parallel {
+                    steps: vec![module_step("Intake", 1)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn only_lets_one_step_in_a_group_drive() {
+        let code = generate(
+            vec![Step::Parallel {
+                steps: vec![
+                    Step::Drive {
+                        inches: 24.0,
+                        power: 0.5,
+                    },
+                    Step::Strafe {
+                        inches: 12.0,
+                        power: 0.5,
+                    },
+                    Step::Wait { seconds: 1.0 },
+                ],
+            }],
+            &mecanum(),
+        );
+
+        assert!(code.contains("startDrive(24, 0, 0.5);"));
+        assert!(!code.contains("startDrive(0, 12, 0.5);"));
+        assert!(code.contains(
+            "// ERROR 'Strafe 12 in at 0.5 power' can't drive while another step in its group does"
+        ));
+        assert!(code
+            .contains("while (opModeIsActive() && ((driveBusy()) || (timer1_3.seconds() < 1)))"));
+    }
+
+    #[test]
+    fn drives_and_strafes_each_mecanum_wheel_the_right_way() {
+        let code = generate(
+            vec![
+                Step::Drive {
+                    inches: 24.0,
+                    power: 0.5,
+                },
+                Step::Strafe {
+                    inches: -12.0,
+                    power: 0.3,
+                },
+            ],
+            &mecanum(),
+        );
+
+        assert!(code.contains(
+            "        // 1. Drive 24 in at 0.5 power\n\
+             \x20       startDrive(24, 0, 0.5);\n\
+             \x20       while (opModeIsActive() && (driveBusy())) {\n"
+        ));
+        assert!(code.contains("startDrive(0, -12, 0.3);"));
+        for line in [
+            "frontLeft.setTargetPosition(frontLeft.getCurrentPosition() + forward + strafe);",
+            "frontRight.setTargetPosition(frontRight.getCurrentPosition() + forward - strafe);",
+            "rearLeft.setTargetPosition(rearLeft.getCurrentPosition() + forward - strafe);",
+            "rearRight.setTargetPosition(rearRight.getCurrentPosition() + forward + strafe);",
+            "rearLeft.setPower(left);",
+            "rearRight.setPower(right);",
+        ] {
+            assert!(code.contains(line), "{}", line);
+        }
+        assert!(!code.contains("IMU"));
+    }
+
+    #[test]
+    fn only_strafes_with_mecanum_wheels() {
+        let mut tank = drive_motor("left", "Left");
+        tank.drivetrain = "Tank".into();
+
+        let code = generate(
+            vec![Step::Strafe {
+                inches: 12.0,
+                power: 0.5,
+            }],
+            &[tank],
+        );
+
+        assert!(code.contains("// ERROR only mecanum drivetrains can strafe"));
+        assert!(!code.contains("startDrive(0, 12, 0.5);"));
+    }
+
+    #[test]
+    fn turns_to_a_heading_with_the_imu() {
+        let code = generate(
+            vec![Step::TurnToHeading {
+                degrees: 90.0,
+                power: 0.4,
+            }],
+            &mecanum(),
+        );
+
+        assert!(code.contains("import com.qualcomm.robotcore.hardware.IMU;"));
+        assert!(code.contains(r#"imu = hardwareMap.get(IMU.class, "imu");"#));
+        assert!(code.contains(
+            "        while (opModeIsActive() && (Math.abs(headingError(90)) > HEADING_TOLERANCE)) {\n\
+             \x20           double turn1 = Range.clip(headingError(90) * TURN_GAIN, -0.4, 0.4);\n\
+             \x20           setDrivePower(-turn1, turn1);\n"
+        ));
+        assert!(code.contains("private double headingError(double heading) {"));
+    }
+
+    #[test]
+    fn runs_a_group_until_all_of_its_steps_finish() {
+        let mut hardware = mecanum();
+        hardware.push(Hardware {
+            name: "arm".into(),
+            java_type: "DcMotorEx".into(),
+            role: HardwareRole::Motor,
+            drivetrain: "".into(),
+            position: "".into(),
+        });
+
+        let code = generate(
+            vec![Step::Parallel {
+                steps: vec![
+                    Step::Drive {
+                        inches: 12.0,
+                        power: 0.5,
+                    },
+                    Step::RunMotorToPosition {
+                        motor: "arm".into(),
+                        position: 300,
+                        velocity: 800.0,
+                    },
+                ],
+            }],
+            &hardware,
+        );
+
+        assert!(code.contains(
+            "        startDrive(12, 0, 0.5);\n\
+             \x20       arm.setTargetPosition(300);\n\
+             \x20       arm.setMode(DcMotor.RunMode.RUN_TO_POSITION);\n\
+             \x20       arm.setVelocity(800);\n\
+             \x20       while (opModeIsActive() && ((driveBusy()) || (arm.isBusy()))) {\n"
+        ));
+    }
+
+    #[test]
+    fn reports_module_steps_whose_component_is_gone() {
+        let code = generate(vec![module_step("Arm", 0)], &mecanum());
+
+        assert!(code.contains("// ERROR Arm no longer has the component for 'Run intake'"));
+        assert!(!code.contains("startDrive"));
+    }
+}
@@ -0,0 +1,337 @@
+use crate::app::generators::autonomous::routine::StepContext;
+use crate::app::generators::hardware::{Hardware, HardwareRole};
+use crate::app::java::block::Block;
+
+/// One step of an autonomous routine
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Step {
+    /// Drive forwards (or backwards for negative distances) using the drive motor encoders
+    Drive {
+        inches: f64,
+        power: f64,
+    },
+    /// Drive sideways; right is positive. Only mecanum drivetrains can strafe.
+    Strafe {
+        inches: f64,
+        power: f64,
+    },
+    /// Turn until the IMU reads `degrees`, counter-clockwise positive, relative to the heading
+    /// the robot started at
+    TurnToHeading {
+        degrees: f64,
+        power: f64,
+    },
+    RunMotorToPosition {
+        motor: String,
+        position: i32,
+        velocity: f64,
+    },
+    SetServo {
+        servo: String,
+        position: f64,
+    },
+    Wait {
+        seconds: f64,
+    },
+    /// Steps started together. The group finishes once all of them have.
+    Parallel {
+        steps: Vec<Step>,
+    },
+    /// A step type offered by a Lua module, generated by the component that offered it
+    Module {
+        subsystem: String,
+        component: usize,
+        name: String,
+        params: Vec<StepParam>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct StepParam {
+    pub name: String,
+    /// Java expression passed to the module, e.g. `0.5`
+    pub value: String,
+}
+
+/// A step type a component offers through its Lua module's optional
+/// `get_autonomous_steps()` function:
+///
+/// ```lua
+/// function get_autonomous_steps()
+///     return { { name = "Run intake", params = { { "power", "1.0" }, { "seconds", "2" } } } }
+/// end
+/// ```
+///
+/// The module's `generate_autonomous_step(name, params)` is called with the step name and a
+/// table of parameter values, and returns the Java to run for it. See [`StepCode`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepType {
+    pub name: String,
+    /// Parameters and their default values
+    pub params: Vec<StepParam>,
+}
+
+/// The Java for one step: `start` runs once, then `update` runs every loop while `busy` is
+/// true, then `finish` runs. Steps without `busy` finish as soon as they've started.
+///
+/// Lua modules return either a string, used as `start`, or a table with any of `start`,
+/// `update`, `busy` and `finish`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepCode {
+    pub start: Block,
+    pub update: Block,
+    pub busy: Option<String>,
+    pub finish: Block,
+}
+
+impl StepCode {
+    fn error(message: &str) -> Self {
+        let mut code = Self::default();
+        code.start.line(format!("// ERROR {}", message));
+        code
+    }
+}
+
+impl Step {
+    /// A step of each built-in type with sensible defaults, offered by "Add step"
+    pub fn templates() -> Vec<Step> {
+        vec![
+            Step::Drive {
+                inches: 24.0,
+                power: 0.5,
+            },
+            Step::Strafe {
+                inches: 12.0,
+                power: 0.5,
+            },
+            Step::TurnToHeading {
+                degrees: 90.0,
+                power: 0.4,
+            },
+            Step::RunMotorToPosition {
+                motor: "".into(),
+                position: 0,
+                velocity: 1000.0,
+            },
+            Step::SetServo {
+                servo: "".into(),
+                position: 0.0,
+            },
+            Step::Wait { seconds: 1.0 },
+            Step::Parallel { steps: vec![] },
+        ]
+    }
+
+    pub fn title(&self) -> &str {
+        match self {
+            Step::Drive { .. } => "Drive",
+            Step::Strafe { .. } => "Strafe",
+            Step::TurnToHeading { .. } => "Turn to heading",
+            Step::RunMotorToPosition { .. } => "Run motor to position",
+            Step::SetServo { .. } => "Set servo",
+            Step::Wait { .. } => "Wait",
+            Step::Parallel { .. } => "Run in parallel",
+            Step::Module { name, .. } => name,
+        }
+    }
+
+    /// One line describing the step, used for comments and telemetry in the generated code
+    pub fn describe(&self) -> String {
+        match self {
+            Step::Drive { inches, power } => format!("Drive {} in at {} power", inches, power),
+            Step::Strafe { inches, power } => format!("Strafe {} in at {} power", inches, power),
+            Step::TurnToHeading { degrees, power } => {
+                format!("Turn to {} degrees at {} power", degrees, power)
+            }
+            Step::RunMotorToPosition {
+                motor, position, ..
+            } => format!("Run {} to {}", motor, position),
+            Step::SetServo { servo, position } => format!("Set {} to {}", servo, position),
+            Step::Wait { seconds } => format!("Wait {} s", seconds),
+            Step::Parallel { steps } => steps
+                .iter()
+                .map(Step::describe)
+                .collect::<Vec<_>>()
+                .join(" + "),
+            Step::Module {
+                subsystem,
+                name,
+                params,
+                ..
+            } => format!(
+                "{}: {}({})",
+                subsystem,
+                name,
+                params
+                    .iter()
+                    .map(|param| param.value.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    pub fn uses_drivetrain(&self) -> bool {
+        match self {
+            Step::Drive { .. } | Step::Strafe { .. } | Step::TurnToHeading { .. } => true,
+            Step::Parallel { steps } => steps.iter().any(Step::uses_drivetrain),
+            _ => false,
+        }
+    }
+
+    pub fn uses_imu(&self) -> bool {
+        match self {
+            Step::TurnToHeading { .. } => true,
+            Step::Parallel { steps } => steps.iter().any(Step::uses_imu),
+            _ => false,
+        }
+    }
+
+    /// Generate the Java for this step. `id` is unique among the routine's steps and is used
+    /// to name any variables the step needs.
+    pub fn code(&self, context: &StepContext<'_>, id: &str) -> StepCode {
+        let drivetrain_ready = !context.drive_motors().is_empty();
+
+        match self {
+            Step::Drive { .. } | Step::Strafe { .. } | Step::TurnToHeading { .. }
+                if !drivetrain_ready =>
+            {
+                StepCode::error("no drivetrain motors are configured")
+            }
+            Step::Drive { inches, power } => drive_code(*inches, 0.0, *power),
+            Step::Strafe { inches, power } => {
+                if context
+                    .drive_motors()
+                    .iter()
+                    .any(|motor| motor.drivetrain != "Mecanum")
+                {
+                    StepCode::error("only mecanum drivetrains can strafe")
+                } else {
+                    drive_code(0.0, *inches, *power)
+                }
+            }
+            Step::TurnToHeading { degrees, power } => {
+                let mut code = StepCode::default();
+                code.start
+                    .line("setDriveMode(DcMotor.RunMode.RUN_USING_ENCODER);");
+                code.update
+                    .line(format!(
+                        "double turn{} = Range.clip(headingError({}) * TURN_GAIN, -{}, {});",
+                        id, degrees, power, power
+                    ))
+                    .line(format!("setDrivePower(-turn{}, turn{});", id, id));
+                code.busy = Some(format!(
+                    "Math.abs(headingError({})) > HEADING_TOLERANCE",
+                    degrees
+                ));
+                code.finish.line("setDrivePower(0, 0);");
+                code
+            }
+            Step::RunMotorToPosition {
+                motor,
+                position,
+                velocity,
+            } => match find(context.hardware, motor, HardwareRole::Motor) {
+                Some(hardware) => {
+                    let mut code = StepCode::default();
+                    code.start
+                        .line(format!("{}.setTargetPosition({});", motor, position))
+                        .line(format!(
+                            "{}.setMode(DcMotor.RunMode.RUN_TO_POSITION);",
+                            motor
+                        ));
+                    if hardware.java_type == "DcMotorEx" {
+                        code.start
+                            .line(format!("{}.setVelocity({});", motor, velocity));
+                    } else {
+                        code.start
+                            .line(format!("{}.setPower({});", motor, velocity));
+                    }
+                    code.busy = Some(format!("{}.isBusy()", motor));
+                    code
+                }
+                None => StepCode::error(&format!("there's no motor named '{}'", motor)),
+            },
+            Step::SetServo { servo, position } => {
+                match find(context.hardware, servo, HardwareRole::Servo) {
+                    Some(hardware) => {
+                        let mut code = StepCode::default();
+                        // continuous rotation servos take a power rather than a position
+                        if hardware.java_type == "CRServo" {
+                            code.start
+                                .line(format!("{}.setPower({});", servo, position));
+                        } else {
+                            code.start
+                                .line(format!("{}.setPosition({});", servo, position));
+                        }
+                        code
+                    }
+                    None => StepCode::error(&format!("there's no servo named '{}'", servo)),
+                }
+            }
+            Step::Wait { seconds } => {
+                let mut code = StepCode::default();
+                code.start
+                    .line(format!("ElapsedTime timer{} = new ElapsedTime();", id));
+                code.busy = Some(format!("timer{}.seconds() < {}", id, seconds));
+                code
+            }
+            Step::Parallel { steps } => {
+                let mut code = StepCode::default();
+                let mut busy = vec![];
+                let mut driving = false;
+
+                for (i, step) in steps.iter().enumerate() {
+                    // the drivetrain can only follow one step at a time
+                    let step_code = if driving && step.uses_drivetrain() {
+                        StepCode::error(&format!(
+                            "'{}' can't drive while another step in its group does",
+                            step.describe()
+                        ))
+                    } else {
+                        step.code(context, &format!("{}_{}", id, i + 1))
+                    };
+                    driving |= step.uses_drivetrain();
+                    code.start.append(&step_code.start);
+                    code.update.section(&step_code.update);
+                    code.finish.section(&step_code.finish);
+                    busy.extend(step_code.busy.map(|busy| format!("({})", busy)));
+                }
+
+                if !busy.is_empty() {
+                    code.busy = Some(busy.join(" || "));
+                }
+                code
+            }
+            Step::Module {
+                subsystem,
+                component,
+                name,
+                params,
+            } => match context.component(subsystem, *component) {
+                Some(generator) => generator
+                    .generate_autonomous_step(name, params)
+                    .unwrap_or_else(|e| StepCode::error(&e.summary())),
+                None => StepCode::error(&format!(
+                    "{} no longer has the component for '{}'",
+                    subsystem, name
+                )),
+            },
+        }
+    }
+}
+
+fn drive_code(forward: f64, strafe: f64, power: f64) -> StepCode {
+    let mut code = StepCode::default();
+    code.start
+        .line(format!("startDrive({}, {}, {});", forward, strafe, power));
+    code.busy = Some("driveBusy()".into());
+    code.finish.line("setDrivePower(0, 0);");
+    code
+}
+
+fn find<'a>(hardware: &'a [Hardware], name: &str, role: HardwareRole) -> Option<&'a Hardware> {
+    hardware
+        .iter()
+        .find(|hardware| hardware.role == role && hardware.name == name)
+}
@@ -0,0 +1,58 @@
+use mlua::{Lua, Table};
+
+/// A boolean a component works out every loop that other components can act on, such as a
+/// limit switch being pressed, as reported by a Lua module's optional `get_conditions()`:
+///
+/// ```lua
+/// function get_conditions()
+///     return { { java = "liftLimit_pressed", description = "liftLimit pressed" } }
+/// end
+/// ```
+///
+/// `java` is a Java expression that can be used anywhere in `generate_loop`, since it's
+/// declared by the component's `generate_loop_one_time_setup`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub java: String,
+    pub description: String,
+}
+
+impl Condition {
+    pub fn from_table(table: &Table<'_>) -> mlua::Result<Self> {
+        let java: String = table.get("java")?;
+
+        Ok(Self {
+            description: table
+                .get::<_, Option<String>>("description")?
+                .unwrap_or_else(|| java.clone()),
+            java,
+        })
+    }
+
+    /// Give a module the conditions on the robot as the `sensorConditions` global, a list of
+    /// `{ java = ..., description = ... }`
+    pub fn set_global(lua: &Lua, conditions: &[Condition]) -> mlua::Result<()> {
+        let list = lua.create_table()?;
+
+        for (i, condition) in conditions.iter().enumerate() {
+            let entry = lua.create_table()?;
+            entry.set("java", condition.java.as_str())?;
+            entry.set("description", condition.description.as_str())?;
+            list.raw_set(i + 1, entry)?;
+        }
+
+        lua.globals().set("sensorConditions", list)
+    }
+
+    /// Read the `sensorConditions` global back
+    pub fn from_global(lua: &Lua) -> Vec<Condition> {
+        lua.globals()
+            .get::<_, Table<'_>>("sensorConditions")
+            .and_then(|list| {
+                list.sequence_values::<Table<'_>>()
+                    .map(|entry| Condition::from_table(&entry?))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -1,5 +1,8 @@
+use crate::app::generators::keybinding::behaviour::ButtonBehaviour;
+use crate::app::generators::keybinding::chord::Modifier;
+use crate::app::generators::keybinding::keybinding::Gamepad;
 use crate::app::generators::ui_elements::{
-    ButtonInput, CheckboxInput, ComboBoxInput, Slider, TextInput,
+    AxisShapingInput, ButtonInput, CheckboxInput, ComboBoxInput, ConditionInput, Slider, TextInput,
 };
 use egui::Ui;
 use mlua::{Lua, UserData, UserDataFields};
@@ -17,6 +20,8 @@ pub enum Control {
     Label(String),
     CheckboxType(CheckboxInput),
     ButtonType(ButtonInput),
+    AxisShapingType(AxisShapingInput),
+    ConditionType(ConditionInput),
     Separator,
     Spacer,
 }
@@ -54,11 +59,99 @@ impl UserData for Control {
                 Control::Label(l) => Ok(l.to_string()),
                 Control::TextInputType(t) => Ok(t.value.to_string()),
                 Control::ComboBoxType(c) => Ok(c.value.to_string()),
+                Control::ConditionType(c) => Ok(c.value.to_string()),
                 Control::SliderType(s) => Ok(s.value.to_string()),
                 _ => Ok("".to_string()),
             };
         });
 
+        fields.add_field_method_get("gamepad", |_, this| match this {
+            Control::ComboBoxType(ComboBoxInput {
+                gamepad: Some(gamepad),
+                ..
+            }) => Ok(format!("{:?}", gamepad)),
+            _ => Ok(format!("{:?}", Gamepad::default())),
+        });
+
+        fields.add_field_method_set("gamepad", |_, this, val: String| {
+            if let Control::ComboBoxType(c) = this {
+                if c.gamepad.is_some() {
+                    c.gamepad = Some(Gamepad::from_name(&val).ok_or_else(|| {
+                        mlua::Error::runtime(format!("'{}' isn't a gamepad", val))
+                    })?);
+                }
+            }
+            Ok(())
+        });
+
+        fields.add_field_method_get("behaviour", |_, this| match this {
+            Control::ComboBoxType(ComboBoxInput {
+                behaviour: Some(behaviour),
+                ..
+            }) => Ok(format!("{:?}", behaviour)),
+            _ => Ok(format!("{:?}", ButtonBehaviour::default())),
+        });
+
+        fields.add_field_method_set("behaviour", |_, this, val: String| {
+            if let Control::ComboBoxType(c) = this {
+                if c.behaviour.is_some() {
+                    let behaviour = ButtonBehaviour::from_name(&val)
+                        .filter(|b| c.behaviours.is_empty() || c.behaviours.contains(b))
+                        .ok_or_else(|| {
+                            mlua::Error::runtime(format!(
+                                "'{}' isn't an offered button behaviour",
+                                val
+                            ))
+                        })?;
+                    c.behaviour = Some(behaviour);
+                }
+            }
+            Ok(())
+        });
+
+        fields.add_field_method_get("modifiers", |_, this| match this {
+            Control::ComboBoxType(c) => Ok(c
+                .modifiers
+                .iter()
+                .map(|modifier| modifier.to_string())
+                .collect::<Vec<String>>()),
+            _ => Ok(vec![]),
+        });
+
+        fields.add_field_method_set("modifiers", |_, this, val: Vec<String>| {
+            if let Control::ComboBoxType(c) = this {
+                if c.behaviour.is_some() {
+                    c.modifiers = parse_modifiers(&val)?;
+                }
+            }
+            Ok(())
+        });
+
+        fields.add_field_method_get("deadband", |_, this| match this {
+            Control::AxisShapingType(a) => Ok(a.shaping.deadband),
+            _ => Ok(0.0),
+        });
+
+        fields.add_field_method_get("expo", |_, this| match this {
+            Control::AxisShapingType(a) => Ok(a.shaping.expo),
+            _ => Ok(0.0),
+        });
+
+        fields.add_field_method_get("scale", |_, this| match this {
+            Control::AxisShapingType(a) => Ok(a.shaping.scale),
+            _ => Ok(1.0),
+        });
+
+        fields.add_field_method_get("inverted", |_, this| match this {
+            Control::AxisShapingType(a) => Ok(a.shaping.inverted),
+            _ => Ok(false),
+        });
+
+        fields.add_field_method_get("slew_rate", |_, this| match this {
+            Control::AxisShapingType(a) => Ok(a.shaping.slew_rate),
+            _ => Ok(0.0),
+        });
+
         fields.add_field_method_set("text", |_, this, val: String| {
             match this {
                 Control::ComboBoxType(c) => {
@@ -67,6 +160,9 @@ impl UserData for Control {
                 Control::TextInputType(t) => {
                     t.value = val;
                 }
+                Control::ConditionType(c) => {
+                    c.value = val;
+                }
                 _ => {}
             }
             Ok(())
@@ -74,6 +170,17 @@ impl UserData for Control {
     }
 }
 
+/// Parse modifier names such as `left_bumper` or `right_trigger > 0.5`
+pub fn parse_modifiers(names: &[String]) -> mlua::Result<Vec<Modifier>> {
+    names
+        .iter()
+        .map(|name| {
+            Modifier::from_name(name)
+                .ok_or_else(|| mlua::Error::runtime(format!("'{}' isn't a modifier", name)))
+        })
+        .collect()
+}
+
 impl Control {
     pub fn render(&mut self, ui: &mut Ui, lua: &Lua) {
         match self {
@@ -85,6 +192,8 @@ impl Control {
             }
             Control::ComboBoxType(c) => c.render(ui, None),
             Control::ButtonType(b) => b.render(ui, Some(lua)),
+            Control::AxisShapingType(a) => a.render(ui, None),
+            Control::ConditionType(c) => c.render(ui, Some(lua)),
             Control::CheckboxType(b) => {
                 ui.checkbox(&mut b.value, &b.label);
             }
@@ -109,6 +218,8 @@ impl Control {
             Control::ComboBoxType(c) => c.name.to_string(),
             Control::CheckboxType(c) => c.name.to_string(),
             Control::ButtonType(b) => b.name.to_string(),
+            Control::AxisShapingType(a) => a.name.to_string(),
+            Control::ConditionType(c) => c.name.to_string(),
             _ => "".to_string(),
         };
     }
@@ -1,3 +1,5 @@
+use crate::app::java::opmode::OpMode;
+
 pub trait Generator {
     fn generate_includes(&self) -> String {
         "".to_string()
@@ -19,6 +21,24 @@ pub trait Generator {
         "".to_string()
     }
 
+    /// Extra methods for the OpMode class
+    fn generate_methods(&self) -> String {
+        "".to_string()
+    }
+
+    /// Add this generator's code to `opmode`. The default re-indents the text from the
+    /// `generate_*` functions into the matching sections.
+    fn generate(&self, opmode: &mut OpMode) {
+        opmode.add_includes(&self.generate_includes());
+        opmode.fields.code(&self.generate_globals());
+        opmode.init.code(&self.generate_init());
+        opmode
+            .loop_one_time_setup
+            .code(&self.generate_loop_one_time_setup());
+        opmode.loop_body.code(&self.generate_loop());
+        opmode.members.code(&self.generate_methods());
+    }
+
     fn render_options(&mut self, ui: &mut egui::Ui, id: usize);
 }
 
@@ -0,0 +1,57 @@
+use mlua::Table;
+
+/// What a piece of configured hardware is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareRole {
+    Motor,
+    /// A motor driving the robot's wheels. Autonomous drive and turn steps use these.
+    DriveMotor,
+    Servo,
+    /// A sensor, such as a touch sensor or limit switch
+    Sensor,
+    Other,
+}
+
+/// A device a component sets up in the generated OpMode, as reported by a Lua module's
+/// optional `get_hardware()` function:
+///
+/// ```lua
+/// function get_hardware()
+///     return { { name = "armMotor", type = "DcMotorEx", role = "motor" } }
+/// end
+/// ```
+///
+/// Drive motors also give `drivetrain` ("Mecanum", "Arcade" or "Tank") and their `position`
+/// on the robot, e.g. "Front Left".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hardware {
+    /// Variable and configuration name
+    pub name: String,
+    /// Java class of the variable, e.g. `DcMotorEx` or `CRServo`
+    pub java_type: String,
+    pub role: HardwareRole,
+    pub drivetrain: String,
+    pub position: String,
+}
+
+impl Hardware {
+    pub fn from_table(table: &Table<'_>) -> mlua::Result<Self> {
+        let string = |key: &str| -> mlua::Result<String> {
+            Ok(table.get::<_, Option<String>>(key)?.unwrap_or_default())
+        };
+
+        Ok(Self {
+            name: table.get("name")?,
+            java_type: string("type")?,
+            role: match string("role")?.as_str() {
+                "motor" => HardwareRole::Motor,
+                "drive_motor" => HardwareRole::DriveMotor,
+                "servo" => HardwareRole::Servo,
+                "sensor" => HardwareRole::Sensor,
+                _ => HardwareRole::Other,
+            },
+            drivetrain: string("drivetrain")?,
+            position: string("position")?,
+        })
+    }
+}
@@ -0,0 +1,555 @@
+use crate::app::generators::control::Control;
+use crate::app::generators::keybinding::chord::Modifier;
+use crate::app::generators::keybinding::keybinding::{BooleanButton, Gamepad};
+use crate::app::generators::keybinding::registry::Input;
+use crate::app::generators::ui_elements::ComboBoxInput;
+use mlua::{AnyUserData, Lua, Table, Value};
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+/// How a button binding reacts to its button. Formats as the name Lua modules use,
+/// e.g. `rising_edge`.
+#[allow(non_camel_case_types)]
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumIter,
+)]
+pub enum ButtonBehaviour {
+    /// Active for as long as the button is held
+    #[default]
+    held,
+    /// Each press switches the binding on or off
+    toggle,
+    /// Active for one loop when the button is pressed
+    rising_edge,
+    /// Active for one loop when the button is released
+    falling_edge,
+    /// Each press fires the next of the bindings sharing the button, like `rising_edge`
+    cycle,
+}
+
+impl ButtonBehaviour {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::iter().find(|behaviour| format!("{:?}", behaviour) == name)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ButtonBehaviour::held => "While held",
+            ButtonBehaviour::toggle => "Toggle",
+            ButtonBehaviour::rising_edge => "On press",
+            ButtonBehaviour::falling_edge => "On release",
+            ButtonBehaviour::cycle => "Cycle",
+        }
+    }
+
+    /// A small combo box for picking the behaviour, out of `offered`, or any if it's empty
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        id: impl std::hash::Hash,
+        offered: &[ButtonBehaviour],
+    ) {
+        egui::ComboBox::from_id_source(id)
+            .selected_text(self.description())
+            .width(90.0)
+            .show_ui(ui, |ui| {
+                for behaviour in Self::iter()
+                    .filter(|behaviour| offered.is_empty() || offered.contains(behaviour))
+                {
+                    ui.selectable_value(self, behaviour, behaviour.description());
+                }
+            });
+    }
+}
+
+/// A binding handed to [`ButtonLogic`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ButtonBinding {
+    pub gamepad: Gamepad,
+    pub button: BooleanButton,
+    pub behaviour: ButtonBehaviour,
+    /// Inputs that have to be held as well, e.g. `left_bumper` for `left_bumper + a`
+    pub modifiers: Vec<Modifier>,
+}
+
+impl ButtonBinding {
+    pub fn new(gamepad: Gamepad, button: BooleanButton, behaviour: ButtonBehaviour) -> Self {
+        Self {
+            gamepad,
+            button,
+            behaviour,
+            modifiers: vec![],
+        }
+    }
+
+    pub fn with_modifiers(mut self, modifiers: &[Modifier]) -> Self {
+        self.modifiers = modifiers.to_vec();
+        self
+    }
+
+    /// The Java for the button alone, e.g. `gamepad1.a`
+    fn button(&self) -> String {
+        format!("{:?}.{:?}", self.gamepad, self.button)
+    }
+
+    /// The modifiers' conditions, each followed by ` && `
+    fn held_modifiers(&self) -> String {
+        self.modifiers
+            .iter()
+            .map(|modifier| format!("{:?}.{} && ", self.gamepad, modifier))
+            .collect()
+    }
+
+    /// Name of the fields remembering the button from the previous loop
+    fn last(&self, prefix: &str) -> String {
+        format!("{}_{:?}_{:?}_last", prefix, self.gamepad, self.button)
+    }
+
+    /// Start of the names of the fields for this chord, e.g. `arm_gamepad1_left_bumper_a`
+    fn state(&self, prefix: &str) -> String {
+        let mut state = format!("{}_{:?}", prefix, self.gamepad);
+        for modifier in &self.modifiers {
+            state += &format!("_{}", modifier.field_name());
+        }
+        state + &format!("_{:?}", self.button)
+    }
+
+    fn same_chord(&self, other: &ButtonBinding) -> bool {
+        self.gamepad == other.gamepad
+            && self.button == other.button
+            && self.modifiers.len() == other.modifiers.len()
+            && self.modifiers.iter().all(|m| other.modifiers.contains(m))
+    }
+
+    /// The modifiers `other` adds on top of this binding's, if it's a longer chord ending in
+    /// the same button
+    fn extra_modifiers<'a>(&self, other: &'a ButtonBinding) -> Option<Vec<&'a Modifier>> {
+        let longer = self.gamepad == other.gamepad
+            && self.button == other.button
+            && other.modifiers.len() > self.modifiers.len()
+            && self.modifiers.iter().all(|m| other.modifiers.contains(m));
+
+        longer.then(|| {
+            other
+                .modifiers
+                .iter()
+                .filter(|m| !self.modifiers.contains(m))
+                .collect()
+        })
+    }
+}
+
+/// The Java for a set of button bindings with behaviours and modifiers. Everything but `held`
+/// needs to remember the button from the previous loop, so the OpMode gets fields for that
+/// state, code before the bindings are checked to update toggles and cycles, and code after to
+/// remember this loop's buttons.
+///
+/// A binding only fires while none of the longer chords ending in its button are held, so
+/// `a` stays quiet while `left_bumper + a` is pressed, whichever component binds the longer one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ButtonLogic {
+    /// Field declarations for the OpMode class
+    pub fields: String,
+    /// Loop code to run before checking `conditions`
+    pub before: String,
+    /// The Java condition for each binding, in the order they were given
+    pub conditions: Vec<String>,
+    /// Loop code to run after checking `conditions`
+    pub after: String,
+}
+
+impl ButtonLogic {
+    /// `prefix` starts the names of the state fields, so give each component its own,
+    /// e.g. the name of its hardware. Bindings of the same chord with the `cycle` behaviour
+    /// are cycled through in the order given. `others` are the chords the rest of the robot
+    /// binds, which take precedence over shorter ones here just like this component's own.
+    pub fn new(prefix: &str, bindings: &[ButtonBinding], others: &[ButtonBinding]) -> Self {
+        let mut logic = Self::default();
+
+        // the buttons whose state is tracked, in the order first seen
+        let mut tracked: Vec<&ButtonBinding> = vec![];
+        let mut toggles: Vec<(&ButtonBinding, String)> = vec![];
+        let mut cycles: Vec<(&ButtonBinding, String, usize)> = vec![];
+
+        for binding in bindings {
+            // conditions for the chord being held, and for it being pressed this loop
+            let mut held = binding.held_modifiers() + &binding.button();
+            let mut pressed = format!("{} && !{}", held, binding.last(prefix));
+            let mut released = format!(
+                "{}!{} && {}",
+                binding.held_modifiers(),
+                binding.button(),
+                binding.last(prefix)
+            );
+
+            let mut shadows: Vec<String> = vec![];
+            for other in bindings.iter().chain(others) {
+                if let Some(extra) = binding.extra_modifiers(other) {
+                    let held: Vec<String> = extra
+                        .iter()
+                        .map(|m| format!("{:?}.{}", binding.gamepad, m))
+                        .collect();
+                    let shadow = match extra.as_slice() {
+                        [Modifier::Button(_)] => format!("!{}", held[0]),
+                        _ => format!("!({})", held.join(" && ")),
+                    };
+                    if !shadows.contains(&shadow) {
+                        shadows.push(shadow);
+                    }
+                }
+            }
+            for shadow in &shadows {
+                for condition in [&mut held, &mut pressed, &mut released] {
+                    *condition += &format!(" && {}", shadow);
+                }
+            }
+
+            if binding.behaviour != ButtonBehaviour::held
+                && !tracked
+                    .iter()
+                    .any(|t| t.gamepad == binding.gamepad && t.button == binding.button)
+            {
+                tracked.push(binding);
+            }
+
+            let state = binding.state(prefix);
+
+            logic.conditions.push(match binding.behaviour {
+                ButtonBehaviour::held => held,
+                ButtonBehaviour::toggle => {
+                    if !toggles.iter().any(|(t, _)| t.same_chord(binding)) {
+                        toggles.push((binding, pressed));
+                    }
+                    format!("{}_toggled", state)
+                }
+                ButtonBehaviour::rising_edge => pressed,
+                ButtonBehaviour::falling_edge => released,
+                ButtonBehaviour::cycle => {
+                    let index = match cycles.iter_mut().find(|(c, _, _)| c.same_chord(binding)) {
+                        Some((_, _, count)) => {
+                            *count += 1;
+                            *count - 1
+                        }
+                        None => {
+                            cycles.push((binding, pressed.clone(), 1));
+                            0
+                        }
+                    };
+                    format!("{} && {}_cycle == {}", pressed, state, index)
+                }
+            });
+        }
+
+        for binding in &tracked {
+            logic.fields += &format!("private boolean {} = false;\n", binding.last(prefix));
+            logic.after += &format!("{} = {};\n", binding.last(prefix), binding.button());
+        }
+
+        for (binding, pressed) in &toggles {
+            let state = binding.state(prefix);
+
+            logic.fields += &format!("private boolean {}_toggled = false;\n", state);
+            logic.before += &format!(
+                "if ({}) {{\n\t{}_toggled = !{}_toggled;\n}}\n",
+                pressed, state, state
+            );
+        }
+
+        for (binding, pressed, count) in &cycles {
+            let state = binding.state(prefix);
+
+            // -1 until the first press, so nothing is selected when the OpMode starts
+            logic.fields += &format!("private int {}_cycle = -1;\n", state);
+            logic.before += &format!(
+                "if ({}) {{\n\t{}_cycle = ({}_cycle + 1) % {};\n}}\n",
+                pressed, state, state, count
+            );
+        }
+
+        if !logic.fields.is_empty() {
+            logic.fields += "\n";
+        }
+
+        logic
+    }
+}
+
+/// The chords the rest of the robot binds, kept with a component's Lua VM for `buttonLogic`.
+/// Set with [`LuaGenerator::share_chords`](crate::app::generators::lua_generator::LuaGenerator::share_chords)
+/// before generating.
+#[derive(Debug, Clone, Default)]
+pub struct OtherChords(pub Vec<ButtonBinding>);
+
+/// Give modules `buttonLogic(prefix, keybinds)`, the Lua side of [`ButtonLogic::new`].
+/// `keybinds` is a list of keybinding controls, which may have gaps; the result has the
+/// `fields`, `before` and `after` code, and `conditions` holding the condition for each keybind
+/// bound to a button, at the keybind's index.
+pub fn register(lua: &Lua) -> mlua::Result<()> {
+    let button_logic = lua.create_function(|lua, (prefix, keybinds): (String, Table<'_>)| {
+        let mut keybinds = keybinds
+            .pairs::<i64, Value<'_>>()
+            .collect::<mlua::Result<Vec<_>>>()?;
+        keybinds.sort_by_key(|(i, _)| *i);
+
+        let mut indices = vec![];
+        let mut bindings = vec![];
+
+        for (i, keybind) in keybinds {
+            let Value::UserData(keybind) = keybind else {
+                continue;
+            };
+
+            if let Some(binding) = button_binding(&keybind) {
+                indices.push(i);
+                bindings.push(binding);
+            }
+        }
+
+        let logic = match lua.app_data_ref::<OtherChords>() {
+            Some(others) => ButtonLogic::new(&prefix, &bindings, &others.0),
+            None => ButtonLogic::new(&prefix, &bindings, &[]),
+        };
+
+        let conditions = lua.create_table()?;
+        for (i, condition) in indices.into_iter().zip(logic.conditions) {
+            conditions.raw_set(i, condition)?;
+        }
+
+        let result = lua.create_table()?;
+        result.set("fields", logic.fields)?;
+        result.set("before", logic.before)?;
+        result.set("after", logic.after)?;
+        result.set("conditions", conditions)?;
+        Ok(result)
+    })?;
+
+    lua.globals().set("buttonLogic", button_logic)
+}
+
+/// The button a keybinding control is set to, if it's set to one
+fn button_binding(keybind: &AnyUserData<'_>) -> Option<ButtonBinding> {
+    let control = keybind.borrow::<Control>().ok()?;
+
+    let Control::ComboBoxType(ComboBoxInput {
+        value,
+        gamepad: Some(gamepad),
+        behaviour,
+        modifiers,
+        ..
+    }) = &*control
+    else {
+        return None;
+    };
+
+    match Input::from_name(value)? {
+        Input::Button(button) => Some(
+            ButtonBinding::new(*gamepad, button, behaviour.unwrap_or_default())
+                .with_modifiers(modifiers),
+        ),
+        Input::Axis(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::generators::keybinding::keybinding::Axis;
+
+    fn binding(button: BooleanButton, behaviour: ButtonBehaviour) -> ButtonBinding {
+        ButtonBinding::new(Gamepad::gamepad1, button, behaviour)
+    }
+
+    #[test]
+    fn held_needs_no_state() {
+        let logic = ButtonLogic::new(
+            "arm",
+            &[binding(BooleanButton::a, ButtonBehaviour::held)],
+            &[],
+        );
+
+        assert_eq!(
+            logic,
+            ButtonLogic {
+                conditions: vec!["gamepad1.a".into()],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn toggles_on_each_press() {
+        let logic = ButtonLogic::new(
+            "arm",
+            &[binding(BooleanButton::a, ButtonBehaviour::toggle)],
+            &[],
+        );
+
+        assert_eq!(
+            logic.fields,
+            "private boolean arm_gamepad1_a_last = false;\n\
+             private boolean arm_gamepad1_a_toggled = false;\n\n"
+        );
+        assert_eq!(
+            logic.before,
+            "if (gamepad1.a && !arm_gamepad1_a_last) {\n\
+             \tarm_gamepad1_a_toggled = !arm_gamepad1_a_toggled;\n}\n"
+        );
+        assert_eq!(logic.conditions, vec!["arm_gamepad1_a_toggled"]);
+        assert_eq!(logic.after, "arm_gamepad1_a_last = gamepad1.a;\n");
+    }
+
+    #[test]
+    fn fires_edges_once() {
+        let logic = ButtonLogic::new(
+            "arm",
+            &[
+                binding(BooleanButton::a, ButtonBehaviour::rising_edge),
+                binding(BooleanButton::b, ButtonBehaviour::falling_edge),
+            ],
+            &[],
+        );
+
+        assert_eq!(
+            logic.conditions,
+            vec![
+                "gamepad1.a && !arm_gamepad1_a_last",
+                "!gamepad1.b && arm_gamepad1_b_last",
+            ]
+        );
+        assert_eq!(
+            logic.after,
+            "arm_gamepad1_a_last = gamepad1.a;\narm_gamepad1_b_last = gamepad1.b;\n"
+        );
+        assert_eq!(logic.before, "");
+    }
+
+    #[test]
+    fn cycles_through_bindings_of_one_button() {
+        let logic = ButtonLogic::new(
+            "arm",
+            &[
+                binding(BooleanButton::x, ButtonBehaviour::cycle),
+                binding(BooleanButton::x, ButtonBehaviour::cycle),
+                binding(BooleanButton::x, ButtonBehaviour::cycle),
+            ],
+            &[],
+        );
+
+        assert_eq!(
+            logic.fields,
+            "private boolean arm_gamepad1_x_last = false;\n\
+             private int arm_gamepad1_x_cycle = -1;\n\n"
+        );
+        assert_eq!(
+            logic.before,
+            "if (gamepad1.x && !arm_gamepad1_x_last) {\n\
+             \tarm_gamepad1_x_cycle = (arm_gamepad1_x_cycle + 1) % 3;\n}\n"
+        );
+        assert_eq!(
+            logic.conditions,
+            (0..3)
+                .map(|i| format!(
+                    "gamepad1.x && !arm_gamepad1_x_last && arm_gamepad1_x_cycle == {}",
+                    i
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn gives_longer_chords_precedence() {
+        let logic = ButtonLogic::new(
+            "arm",
+            &[
+                binding(BooleanButton::a, ButtonBehaviour::held),
+                binding(BooleanButton::a, ButtonBehaviour::held)
+                    .with_modifiers(&[Modifier::Button(BooleanButton::left_bumper)]),
+                binding(BooleanButton::a, ButtonBehaviour::held).with_modifiers(&[
+                    Modifier::Button(BooleanButton::left_bumper),
+                    Modifier::Axis(Axis::right_trigger, 0.5),
+                ]),
+            ],
+            &[],
+        );
+
+        assert_eq!(
+            logic.conditions,
+            vec![
+                "gamepad1.a && !gamepad1.left_bumper \
+                 && !(gamepad1.left_bumper && gamepad1.right_trigger > 0.5)",
+                "gamepad1.left_bumper && gamepad1.a && !(gamepad1.right_trigger > 0.5)",
+                "gamepad1.left_bumper && gamepad1.right_trigger > 0.5 && gamepad1.a",
+            ]
+        );
+    }
+
+    #[test]
+    fn gives_way_to_chords_of_other_components() {
+        let others = [
+            binding(BooleanButton::a, ButtonBehaviour::toggle)
+                .with_modifiers(&[Modifier::Button(BooleanButton::left_bumper)]),
+            // other buttons and gamepads don't matter
+            binding(BooleanButton::b, ButtonBehaviour::held)
+                .with_modifiers(&[Modifier::Button(BooleanButton::right_bumper)]),
+            ButtonBinding::new(Gamepad::gamepad2, BooleanButton::a, ButtonBehaviour::held)
+                .with_modifiers(&[Modifier::Button(BooleanButton::right_bumper)]),
+        ];
+
+        let logic = ButtonLogic::new(
+            "arm",
+            &[
+                binding(BooleanButton::a, ButtonBehaviour::rising_edge),
+                binding(BooleanButton::a, ButtonBehaviour::held)
+                    .with_modifiers(&[Modifier::Button(BooleanButton::left_bumper)]),
+            ],
+            &others,
+        );
+
+        assert_eq!(
+            logic.conditions,
+            vec![
+                "gamepad1.a && !arm_gamepad1_a_last && !gamepad1.left_bumper",
+                "gamepad1.left_bumper && gamepad1.a",
+            ]
+        );
+    }
+
+    #[test]
+    fn names_toggles_on_opposite_axis_directions_apart() {
+        let logic = ButtonLogic::new(
+            "arm",
+            &[
+                binding(BooleanButton::a, ButtonBehaviour::toggle)
+                    .with_modifiers(&[Modifier::Axis(Axis::left_stick_y, 0.5)]),
+                binding(BooleanButton::a, ButtonBehaviour::toggle)
+                    .with_modifiers(&[Modifier::Axis(Axis::left_stick_y, -0.5)]),
+            ],
+            &[],
+        );
+
+        assert_eq!(
+            logic.conditions,
+            vec![
+                "arm_gamepad1_left_stick_y_above_0_5_a_toggled",
+                "arm_gamepad1_left_stick_y_below_neg0_5_a_toggled",
+            ]
+        );
+        assert_eq!(
+            logic.fields,
+            "private boolean arm_gamepad1_a_last = false;\n\
+             private boolean arm_gamepad1_left_stick_y_above_0_5_a_toggled = false;\n\
+             private boolean arm_gamepad1_left_stick_y_below_neg0_5_a_toggled = false;\n\n"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use crate::app::generators::keybinding::keybinding::{Axis, BooleanButton};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use strum::IntoEnumIterator;
+
+/// An input that has to be held as well for a binding to fire, like `left_bumper` in
+/// `left_bumper + a`. Reads the same gamepad as the binding. Displays as the Java condition
+/// without the gamepad, e.g. `right_trigger > 0.5`, which is also how Lua modules name it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Modifier {
+    Button(BooleanButton),
+    /// An axis pushed past the threshold: above it if it's positive, below it if it's
+    /// negative, so `left_stick_y` at -0.5 means pushed halfway up
+    Axis(Axis, f32),
+}
+
+impl Default for Modifier {
+    fn default() -> Self {
+        Modifier::Button(BooleanButton::left_bumper)
+    }
+}
+
+impl Modifier {
+    /// Parse `left_bumper`, `right_trigger > 0.5` or `left_stick_y < -0.5`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut parts = name.split_whitespace();
+        let input = parts.next()?;
+
+        let modifier = match (parts.next(), parts.next()) {
+            (None, None) => BooleanButton::iter()
+                .filter(|button| *button != BooleanButton::default)
+                .find(|button| format!("{:?}", button) == input)
+                .map(Modifier::Button)?,
+            (Some(comparison @ (">" | "<")), Some(threshold)) => {
+                let threshold: f32 = threshold.parse().ok()?;
+                if (comparison == ">") != (threshold > 0.0) {
+                    return None;
+                }
+
+                Axis::iter()
+                    .filter(|axis| *axis != Axis::default)
+                    .find(|axis| format!("{:?}", axis) == input)
+                    .map(|axis| Modifier::Axis(axis, threshold))?
+            }
+            _ => return None,
+        };
+
+        match parts.next() {
+            Some(_) => None,
+            None => Some(modifier),
+        }
+    }
+
+    /// The input the modifier reads, e.g. `left_bumper` or `left_stick_y`
+    pub fn input_name(&self) -> String {
+        match self {
+            Modifier::Button(button) => format!("{:?}", button),
+            Modifier::Axis(axis, _) => format!("{:?}", axis),
+        }
+    }
+
+    /// The part of a state field name for this modifier. Axes add which way and how far
+    /// they're pushed, e.g. `left_stick_y_below_neg0_5`, so opposite directions get their own.
+    pub fn field_name(&self) -> String {
+        match self {
+            Modifier::Button(button) => format!("{:?}", button),
+            Modifier::Axis(axis, threshold) => {
+                let past = format!("{}", threshold.abs()).replace('.', "_");
+                if *threshold < 0.0 {
+                    format!("{:?}_below_neg{}", axis, past)
+                } else {
+                    format!("{:?}_above_{}", axis, past)
+                }
+            }
+        }
+    }
+
+    /// Edit the modifiers of a binding: one row each, plus a button to add another
+    pub fn render_list(modifiers: &mut Vec<Modifier>, ui: &mut egui::Ui, id: impl fmt::Display) {
+        let mut removed = None;
+
+        for (i, modifier) in modifiers.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label("+");
+                modifier.render(ui, format!("{}modifier{}", id, i));
+
+                if ui
+                    .small_button("🗙")
+                    .on_hover_text("Remove modifier")
+                    .clicked()
+                {
+                    removed = Some(i);
+                }
+            });
+        }
+
+        if let Some(i) = removed {
+            modifiers.remove(i);
+        }
+
+        if ui
+            .small_button("+ Modifier")
+            .on_hover_text("Only fire while another input is held too")
+            .clicked()
+        {
+            modifiers.push(Modifier::default());
+        }
+    }
+
+    fn render(&mut self, ui: &mut egui::Ui, id: String) {
+        egui::ComboBox::from_id_source(&id)
+            .selected_text(self.input_name())
+            .width(110.0)
+            .show_ui(ui, |ui| {
+                for button in
+                    BooleanButton::iter().filter(|button| *button != BooleanButton::default)
+                {
+                    ui.selectable_value(self, Modifier::Button(button), format!("{:?}", button));
+                }
+                for axis in Axis::iter().filter(|axis| *axis != Axis::default) {
+                    let threshold = match self {
+                        Modifier::Axis(_, threshold) => *threshold,
+                        Modifier::Button(_) => 0.5,
+                    };
+                    ui.selectable_value(
+                        self,
+                        Modifier::Axis(axis, threshold),
+                        format!("{:?}", axis),
+                    );
+                }
+            });
+
+        if let Modifier::Axis(_, threshold) = self {
+            ui.add(
+                egui::Slider::new(threshold, -1.0..=1.0)
+                    .text("past")
+                    .step_by(0.05)
+                    .max_decimals(2),
+            );
+
+            // zero would mean "never" for one direction, so nudge it to the smallest step
+            if *threshold == 0.0 {
+                *threshold = 0.05;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Modifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Modifier::Button(button) => write!(f, "{:?}", button),
+            Modifier::Axis(axis, threshold) if *threshold < 0.0 => {
+                write!(f, "{:?} < {}", axis, threshold)
+            }
+            Modifier::Axis(axis, threshold) => write!(f, "{:?} > {}", axis, threshold),
+        }
+    }
+}
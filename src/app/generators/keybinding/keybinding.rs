@@ -1,17 +1,31 @@
+use crate::app::generators::keybinding::behaviour::{ButtonBehaviour, ButtonBinding};
+use crate::app::generators::keybinding::chord::Modifier;
+use crate::app::generators::keybinding::shaping::AxisShaping;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use strum::EnumIter;
+use strum::{EnumIter, IntoEnumIterator};
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, PartialOrd)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
 pub struct Keybinding<T> {
     pub value: T,
     pub button: Option<BooleanButton>,
+    #[serde(default)]
+    pub gamepad: Gamepad,
+    #[serde(default)]
+    pub behaviour: ButtonBehaviour,
+    /// Inputs that have to be held along with `button`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<Modifier>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, PartialOrd)]
 pub struct AxisKeybinding {
     pub reversed: bool,
     pub axis: Option<Axis>,
+    #[serde(default)]
+    pub gamepad: Gamepad,
+    #[serde(default)]
+    pub shaping: AxisShaping,
 }
 
 impl<T> Keybinding<T> {
@@ -19,33 +33,43 @@ impl<T> Keybinding<T> {
         Keybinding {
             value: value,
             button: None,
+            gamepad: Gamepad::default(),
+            behaviour: ButtonBehaviour::default(),
+            modifiers: vec![],
         }
     }
+
+    /// The binding as [`ButtonLogic`](super::behaviour::ButtonLogic) takes it, if it's bound
+    pub fn button_binding(&self) -> Option<ButtonBinding> {
+        self.button.map(|button| {
+            ButtonBinding::new(self.gamepad, button, self.behaviour).with_modifiers(&self.modifiers)
+        })
+    }
 }
 
 impl Eq for Keybinding<i32> {}
 
 impl Ord for Keybinding<i32> {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.button {
+        self.gamepad.cmp(&other.gamepad).then(match self.button {
             None => Ordering::Less,
             Some(button) => match other.button {
                 None => Ordering::Greater,
                 Some(other) => button.partial_cmp(&other).unwrap(),
             },
-        }
+        })
     }
 }
 
 impl Ord for Keybinding<f32> {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.button {
+        self.gamepad.cmp(&other.gamepad).then(match self.button {
             None => Ordering::Less,
             Some(button) => match other.button {
                 None => Ordering::Greater,
                 Some(other) => button.partial_cmp(&other).unwrap(),
             },
-        }
+        })
     }
 }
 
@@ -57,12 +81,57 @@ impl AxisKeybinding {
         AxisKeybinding {
             reversed: false,
             axis: None,
+            gamepad: Gamepad::default(),
+            shaping: AxisShaping::default(),
         }
     }
 }
 
+/// Which driver's gamepad a binding reads. Formats as the name of the field on `OpMode`,
+/// so `format!("{:?}.{:?}", gamepad, button)` is the Java for the input.
+#[allow(non_camel_case_types)]
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumIter,
+)]
+pub enum Gamepad {
+    #[default]
+    gamepad1,
+    gamepad2,
+}
+
+impl Gamepad {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::iter().find(|gamepad| format!("{:?}", gamepad) == name)
+    }
+
+    /// A small combo box for picking the gamepad
+    pub fn render(&mut self, ui: &mut egui::Ui, id: impl std::hash::Hash) {
+        egui::ComboBox::from_id_source(id)
+            .selected_text(format!("{:?}", self))
+            .width(90.0)
+            .show_ui(ui, |ui| {
+                for gamepad in Self::iter() {
+                    ui.selectable_value(self, gamepad, format!("{:?}", gamepad));
+                }
+            });
+    }
+}
+
 #[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd, Copy, EnumIter)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, EnumIter,
+)]
 pub enum BooleanButton {
     default = 0,
     a,
@@ -82,7 +151,9 @@ pub enum BooleanButton {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd, Copy, EnumIter)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, EnumIter,
+)]
 pub enum Axis {
     default,
     left_trigger,
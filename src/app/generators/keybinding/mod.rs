@@ -1 +1,5 @@
+pub mod behaviour;
+pub mod chord;
 pub mod keybinding;
+pub mod registry;
+pub mod shaping;
@@ -0,0 +1,438 @@
+use crate::app::generators::autonomous::routine::Component;
+use crate::app::generators::keybinding::behaviour::{ButtonBehaviour, ButtonBinding};
+use crate::app::generators::keybinding::chord::Modifier;
+use crate::app::generators::keybinding::keybinding::{Axis, BooleanButton, Gamepad};
+use std::collections::BTreeMap;
+use std::fmt;
+use strum::IntoEnumIterator;
+
+/// A button or axis on a gamepad, as named by the fields of the SDK's `Gamepad` class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Input {
+    Button(BooleanButton),
+    Axis(Axis),
+}
+
+impl Input {
+    /// Parse an input name such as `a` or `left_stick_y`. The `default` entries, which mean
+    /// "when nothing else is pressed" rather than a real input, give `None`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        BooleanButton::iter()
+            .filter(|button| *button != BooleanButton::default)
+            .find(|button| format!("{:?}", button) == name)
+            .map(Input::Button)
+            .or_else(|| {
+                Axis::iter()
+                    .filter(|axis| *axis != Axis::default)
+                    .find(|axis| format!("{:?}", axis) == name)
+                    .map(Input::Axis)
+            })
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Button(button) => write!(f, "{:?}", button),
+            Input::Axis(axis) => write!(f, "{:?}", axis),
+        }
+    }
+}
+
+/// An input on a particular gamepad. Displays as the Java that reads it, e.g. `gamepad2.a`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GamepadInput {
+    pub gamepad: Gamepad,
+    pub input: Input,
+}
+
+impl GamepadInput {
+    pub fn new(gamepad: Gamepad, input: Input) -> Self {
+        Self { gamepad, input }
+    }
+}
+
+impl fmt::Display for GamepadInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}.{}", self.gamepad, self.input)
+    }
+}
+
+/// An input a component reads, as reported by
+/// [`LuaGenerator::keybindings`](crate::app::generators::lua_generator::LuaGenerator::keybindings)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentBinding {
+    /// `None` for a keybinding control that isn't bound to anything
+    pub input: Option<GamepadInput>,
+    pub description: String,
+    /// The keybinding control the input was picked with, if the user can change it
+    pub control: Option<String>,
+    pub behaviour: ButtonBehaviour,
+    /// Inputs that have to be held along with `input`
+    pub modifiers: Vec<Modifier>,
+    /// Whether the component generates the binding with `buttonLogic`, so it stays quiet
+    /// while a longer chord ending in the same button is held
+    pub yields_to_chords: bool,
+}
+
+/// One use of an input by a component
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub input: GamepadInput,
+    pub subsystem: String,
+    /// Index of the component in its subsystem
+    pub component: usize,
+    /// Name of the hardware the component sets up, or of its module if it has none
+    pub component_name: String,
+    /// What the component uses the input for, e.g. the label of the keybinding control
+    pub description: String,
+    /// The keybinding control the input was picked with, if the user can change it
+    pub control: Option<String>,
+    pub behaviour: ButtonBehaviour,
+    /// Inputs that have to be held along with `input`
+    pub modifiers: Vec<Modifier>,
+    /// Whether the binding stays quiet while a longer chord ending in the same button is held
+    pub yields_to_chords: bool,
+}
+
+impl Binding {
+    fn same_component(&self, other: &Binding) -> bool {
+        self.subsystem == other.subsystem && self.component == other.component
+    }
+
+    /// Whether this binding fires whenever `other` does, because it needs no more modifiers
+    fn fires_with(&self, other: &Binding) -> bool {
+        self.modifiers.iter().all(|m| other.modifiers.contains(m))
+    }
+
+    /// Whether both bindings act on the same press. Within a component the generated code
+    /// gives longer chords precedence, across components it does for the bindings that yield
+    /// to them, and bindings of one component cycling through a button take turns.
+    fn clashes_with(&self, other: &Binding) -> bool {
+        let same_chord = self.fires_with(other) && other.fires_with(self);
+
+        if same_chord {
+            let cycle = self.behaviour == ButtonBehaviour::cycle
+                && other.behaviour == ButtonBehaviour::cycle;
+            !(self.same_component(other) && cycle)
+        } else if self.same_component(other) {
+            false
+        } else if self.fires_with(other) {
+            !self.yields_to_chords
+        } else if other.fires_with(self) {
+            !other.yields_to_chords
+        } else {
+            false
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} › {}: {}",
+            self.subsystem, self.component_name, self.description
+        )?;
+
+        for modifier in &self.modifiers {
+            write!(f, " (with {})", modifier)?;
+        }
+        Ok(())
+    }
+}
+
+/// A keybinding control the user can point at any input
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeybindingControl {
+    pub subsystem: String,
+    pub component: usize,
+    pub component_name: String,
+    /// Name of the control, e.g. `DCM_Keybind1`
+    pub control: String,
+    pub label: String,
+    pub input: Option<GamepadInput>,
+}
+
+impl fmt::Display for KeybindingControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} › {}: {}",
+            self.subsystem, self.component_name, self.label
+        )
+    }
+}
+
+/// Every gamepad input used anywhere on the robot, so inputs bound by more than one component
+/// can be found. Two bindings of the same input make the generated OpMode fight itself every
+/// loop, with whichever runs last winning.
+#[derive(Debug, Clone, Default)]
+pub struct KeybindingRegistry {
+    bindings: Vec<Binding>,
+    controls: Vec<KeybindingControl>,
+}
+
+impl KeybindingRegistry {
+    /// Collect the keybindings of `components`: their keybinding controls, and anything they
+    /// list with their module's optional `get_keybindings()`
+    pub fn new(components: &[Component<'_>]) -> Self {
+        let mut registry = Self::default();
+
+        for component in components {
+            let component_name = component_name(component);
+
+            for keybinding in component.generator.keybindings() {
+                let binding = |input| Binding {
+                    input,
+                    subsystem: component.subsystem.to_string(),
+                    component: component.index,
+                    component_name: component_name.clone(),
+                    description: keybinding.description.clone(),
+                    control: keybinding.control.clone(),
+                    behaviour: keybinding.behaviour,
+                    modifiers: keybinding.modifiers.clone(),
+                    yields_to_chords: keybinding.yields_to_chords,
+                };
+
+                if let Some(input) = keybinding.input {
+                    registry.bindings.push(binding(input));
+                }
+                if let Some(control) = &keybinding.control {
+                    registry.controls.push(KeybindingControl {
+                        subsystem: component.subsystem.to_string(),
+                        component: component.index,
+                        component_name: component_name.clone(),
+                        control: control.clone(),
+                        label: keybinding.description.clone(),
+                        input: keybinding.input,
+                    });
+                }
+            }
+        }
+
+        registry
+    }
+
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// Every keybinding control on the robot, bound or not
+    pub fn controls(&self) -> &[KeybindingControl] {
+        &self.controls
+    }
+
+    /// The chords ending in a button that components other than the given one bind, for its
+    /// `buttonLogic` to give way to
+    pub fn chords_outside(&self, subsystem: &str, component: usize) -> Vec<ButtonBinding> {
+        self.bindings
+            .iter()
+            .filter(|binding| !(binding.subsystem == subsystem && binding.component == component))
+            .filter(|binding| !binding.modifiers.is_empty())
+            .filter_map(|binding| match binding.input.input {
+                Input::Button(button) => Some(
+                    ButtonBinding::new(binding.input.gamepad, button, binding.behaviour)
+                        .with_modifiers(&binding.modifiers),
+                ),
+                Input::Axis(_) => None,
+            })
+            .collect()
+    }
+
+    /// Every input in use, in gamepad then input order, with the bindings that use it
+    pub fn by_input(&self) -> BTreeMap<GamepadInput, Vec<&Binding>> {
+        let mut inputs: BTreeMap<GamepadInput, Vec<&Binding>> = BTreeMap::new();
+        for binding in &self.bindings {
+            inputs.entry(binding.input).or_default().push(binding);
+        }
+        inputs
+    }
+
+    /// Inputs used by more than one binding. Bindings of one component that cycle through
+    /// the same button are meant to share it, so they don't count.
+    pub fn conflicts(&self) -> BTreeMap<GamepadInput, Vec<&Binding>> {
+        let mut inputs = self.by_input();
+        inputs.retain(|_, bindings| is_conflict(bindings));
+        inputs
+    }
+
+    /// A line for each input the given component shares with something else, naming the
+    /// other users
+    pub fn conflicts_for(&self, subsystem: &str, component: usize) -> Vec<String> {
+        self.conflicts()
+            .into_iter()
+            .filter_map(|(input, bindings)| {
+                let (mine, others): (Vec<&Binding>, Vec<&Binding>) =
+                    bindings.into_iter().partition(|binding| {
+                        binding.subsystem == subsystem && binding.component == component
+                    });
+
+                let mut users: Vec<String> = others
+                    .iter()
+                    .filter(|other| mine.iter().any(|binding| binding.clashes_with(other)))
+                    .map(|other| other.to_string())
+                    .collect();
+                let own = mine
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, binding)| {
+                        mine.iter()
+                            .enumerate()
+                            .any(|(j, other)| *i != j && binding.clashes_with(other))
+                    })
+                    .count();
+                if own > 1 {
+                    users.push(format!("{} of this component's own bindings", own));
+                }
+
+                if users.is_empty() {
+                    return None;
+                }
+
+                Some(format!("{} is also used by {}", input, users.join(", ")))
+            })
+            .collect()
+    }
+
+    /// List every input and what uses it, with conflicts highlighted
+    pub fn render(&self, ui: &mut egui::Ui) {
+        let inputs = self.by_input();
+
+        if inputs.is_empty() {
+            ui.label("No keybindings yet");
+            return;
+        }
+
+        egui::Grid::new("Keybinding registry")
+            .striped(true)
+            .show(ui, |ui| {
+                for (input, bindings) in inputs {
+                    let text = input.to_string();
+                    if is_conflict(&bindings) {
+                        ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", text));
+                    } else {
+                        ui.label(text);
+                    }
+
+                    ui.vertical(|ui| {
+                        for binding in bindings {
+                            ui.label(binding.to_string());
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// Whether the bindings of one input fight each other
+fn is_conflict(bindings: &[&Binding]) -> bool {
+    bindings.iter().enumerate().any(|(i, binding)| {
+        bindings[i + 1..]
+            .iter()
+            .any(|other| binding.clashes_with(other))
+    })
+}
+
+/// `armMotor` for a DC motor component, or `Drivetrain #1` for a component without hardware
+pub fn component_name(component: &Component<'_>) -> String {
+    match component.generator.hardware().first() {
+        Some(hardware) => hardware.name.clone(),
+        None => {
+            let script = &component.generator.script;
+            let module = std::path::Path::new(script)
+                .file_stem()
+                .map_or(script.to_string(), |stem| {
+                    stem.to_string_lossy().into_owned()
+                });
+            format!("{} #{}", module, component.index + 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(subsystem: &str, component: usize, modifiers: &[Modifier]) -> Binding {
+        Binding {
+            input: GamepadInput::new(Gamepad::gamepad1, Input::Button(BooleanButton::a)),
+            subsystem: subsystem.into(),
+            component,
+            component_name: format!("{} motor", subsystem),
+            description: "Keybinding".into(),
+            control: Some("DCM_Keybind".into()),
+            behaviour: ButtonBehaviour::held,
+            modifiers: modifiers.to_vec(),
+            yields_to_chords: true,
+        }
+    }
+
+    fn registry(bindings: Vec<Binding>) -> KeybindingRegistry {
+        KeybindingRegistry {
+            bindings,
+            controls: vec![],
+        }
+    }
+
+    const LEFT_BUMPER: Modifier = Modifier::Button(BooleanButton::left_bumper);
+
+    #[test]
+    fn lets_a_longer_chord_elsewhere_shadow_a_binding() {
+        let registry = registry(vec![
+            binding("Arm", 0, &[]),
+            binding("Lift", 0, &[LEFT_BUMPER]),
+        ]);
+
+        assert!(registry.conflicts().is_empty());
+        assert_eq!(
+            registry.chords_outside("Arm", 0),
+            vec![
+                ButtonBinding::new(Gamepad::gamepad1, BooleanButton::a, ButtonBehaviour::held)
+                    .with_modifiers(&[LEFT_BUMPER])
+            ]
+        );
+        assert!(registry.chords_outside("Lift", 0).is_empty());
+    }
+
+    #[test]
+    fn flags_a_binding_that_doesnt_yield_to_chords_elsewhere() {
+        let mut listed = binding("Drivetrain", 0, &[]);
+        listed.control = None;
+        listed.yields_to_chords = false;
+
+        let registry = registry(vec![listed, binding("Lift", 0, &[LEFT_BUMPER])]);
+
+        assert_eq!(
+            registry.conflicts_for("Drivetrain", 0),
+            vec!["gamepad1.a is also used by Lift › Lift motor: Keybinding (with left_bumper)"]
+        );
+    }
+
+    #[test]
+    fn flags_the_same_chord_in_two_components() {
+        let registry = registry(vec![
+            binding("Arm", 0, &[LEFT_BUMPER]),
+            binding("Lift", 0, &[LEFT_BUMPER]),
+        ]);
+
+        assert_eq!(registry.conflicts().len(), 1);
+        assert_eq!(registry.conflicts_for("Arm", 0).len(), 1);
+    }
+
+    #[test]
+    fn lets_one_component_cycle_through_a_button() {
+        let mut first = binding("Arm", 0, &[]);
+        first.behaviour = ButtonBehaviour::cycle;
+        let mut second = first.clone();
+        second.description = "Another position".into();
+
+        assert!(registry(vec![first.clone(), second.clone()])
+            .conflicts()
+            .is_empty());
+
+        second.subsystem = "Lift".into();
+        assert_eq!(registry(vec![first, second]).conflicts().len(), 1);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::app::generators::control::Control;
+use mlua::{AnyUserData, Lua};
+use serde::{Deserialize, Serialize};
+
+/// How an analog axis is turned into an output: a deadband around the centre, a curve
+/// between a linear and a cubic response, a scale, inversion and a limit on how fast the
+/// output may change. The default passes the axis through untouched.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct AxisShaping {
+    /// Stick travel either side of the centre that reads as zero
+    pub deadband: f32,
+    /// How far the response bends from linear (0) towards cubic (1)
+    pub expo: f32,
+    pub scale: f32,
+    pub inverted: bool,
+    /// Most the output may change per second, or 0 for no limit
+    pub slew_rate: f32,
+}
+
+impl Default for AxisShaping {
+    fn default() -> Self {
+        Self {
+            deadband: 0.0,
+            expo: 0.0,
+            scale: 1.0,
+            inverted: false,
+            slew_rate: 0.0,
+        }
+    }
+}
+
+/// The Java for a shaped axis, see [`AxisShaping::code`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AxisCode {
+    /// Field declarations for the OpMode class
+    pub fields: String,
+    /// Loop code to run before `value` is read
+    pub before: String,
+    /// An expression for the shaped axis
+    pub value: String,
+}
+
+impl AxisShaping {
+    /// The Java for `input`, e.g. `gamepad1.left_stick_y`, shaped like this. `prefix` starts
+    /// the names of the fields a slew limit needs, so give each axis its own.
+    ///
+    /// The generated code calls the OpMode's `shapeAxis` and `slewLimit` helpers, which are
+    /// added to the class when used.
+    pub fn code(&self, prefix: &str, input: &str) -> AxisCode {
+        let scale = if self.inverted {
+            -self.scale
+        } else {
+            self.scale
+        };
+
+        let value = if self.deadband > 0.0 || self.expo > 0.0 {
+            format!(
+                "shapeAxis({}, {:?}, {:?}, {:?})",
+                input, self.deadband, self.expo, scale
+            )
+        } else if scale == 1.0 {
+            input.to_string()
+        } else if scale == -1.0 {
+            format!("-{}", input)
+        } else {
+            format!("{} * {:?}", input, scale)
+        };
+
+        if self.slew_rate <= 0.0 {
+            return AxisCode {
+                value,
+                ..Default::default()
+            };
+        }
+
+        let slewed = format!("{}_slewed", prefix);
+        let timer = format!("{}_slew_timer", prefix);
+
+        AxisCode {
+            fields: format!(
+                "private double {} = 0;\nprivate ElapsedTime {} = new ElapsedTime();\n\n",
+                slewed, timer
+            ),
+            before: format!(
+                "{} = slewLimit({}, {}, {:?}, {});\n",
+                slewed, slewed, value, self.slew_rate, timer
+            ),
+            value: slewed,
+        }
+    }
+
+    /// Edit every setting
+    pub fn render(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.inverted, "Invert");
+        self.render_response(ui);
+    }
+
+    /// Edit everything but inversion, for bindings that already have a reverse option
+    pub fn render_response(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.deadband, 0.0..=0.5)
+                .text("Deadband")
+                .step_by(0.01)
+                .max_decimals(2),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.expo, 0.0..=1.0)
+                .text("Expo")
+                .step_by(0.05)
+                .max_decimals(2),
+        )
+        .on_hover_text("0 is linear, 1 is cubic: higher gives finer control near the centre");
+        ui.add(
+            egui::Slider::new(&mut self.scale, 0.0..=1.0)
+                .text("Scale")
+                .step_by(0.05)
+                .max_decimals(2),
+        );
+
+        ui.horizontal(|ui| {
+            let mut limited = self.slew_rate > 0.0;
+            if ui
+                .checkbox(&mut limited, "Limit acceleration")
+                .on_hover_text("Ramp the output instead of jumping straight to the stick")
+                .changed()
+            {
+                self.slew_rate = if limited { 4.0 } else { 0.0 };
+            }
+
+            if limited {
+                ui.add(
+                    egui::Slider::new(&mut self.slew_rate, 0.5..=20.0)
+                        .text("per second")
+                        .step_by(0.5)
+                        .max_decimals(1),
+                );
+            }
+        });
+    }
+}
+
+/// Give modules `axisCode(prefix, shaping, input)`, the Lua side of [`AxisShaping::code`].
+/// `shaping` is an `axisShaping` control and `input` the Java for the axis; the result has the
+/// `fields`, `before` and `value` code.
+pub fn register(lua: &Lua) -> mlua::Result<()> {
+    let axis_code = lua.create_function(
+        |lua, (prefix, shaping, input): (String, AnyUserData<'_>, String)| {
+            let shaping = match &*shaping.borrow::<Control>()? {
+                Control::AxisShapingType(control) => control.shaping,
+                _ => {
+                    return Err(mlua::Error::runtime(
+                        "axisCode needs an axisShaping control",
+                    ))
+                }
+            };
+
+            let code = shaping.code(&prefix, &input);
+
+            let result = lua.create_table()?;
+            result.set("fields", code.fields)?;
+            result.set("before", code.before)?;
+            result.set("value", code.value)?;
+            Ok(result)
+        },
+    )?;
+
+    lua.globals().set("axisCode", axis_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "gamepad1.left_stick_y";
+
+    fn value(shaping: AxisShaping) -> String {
+        let code = shaping.code("arm", INPUT);
+        assert_eq!((code.fields.as_str(), code.before.as_str()), ("", ""));
+        code.value
+    }
+
+    #[test]
+    fn passes_the_axis_through_by_default() {
+        assert_eq!(value(AxisShaping::default()), INPUT);
+    }
+
+    #[test]
+    fn scales_and_inverts_without_a_helper() {
+        let inverted = AxisShaping {
+            inverted: true,
+            ..Default::default()
+        };
+        assert_eq!(value(inverted), "-gamepad1.left_stick_y");
+
+        let scaled = AxisShaping {
+            scale: 0.5,
+            inverted: true,
+            ..Default::default()
+        };
+        assert_eq!(value(scaled), "gamepad1.left_stick_y * -0.5");
+    }
+
+    #[test]
+    fn shapes_a_deadband_or_curve_with_shape_axis() {
+        let shaping = AxisShaping {
+            deadband: 0.05,
+            expo: 0.3,
+            scale: 0.8,
+            inverted: true,
+            slew_rate: 0.0,
+        };
+
+        assert_eq!(
+            value(shaping),
+            "shapeAxis(gamepad1.left_stick_y, 0.05, 0.3, -0.8)"
+        );
+    }
+
+    #[test]
+    fn keeps_the_slew_limited_value_in_a_field() {
+        let shaping = AxisShaping {
+            expo: 0.5,
+            slew_rate: 4.0,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            shaping.code("arm", INPUT),
+            AxisCode {
+                fields: "private double arm_slewed = 0;\n\
+                         private ElapsedTime arm_slew_timer = new ElapsedTime();\n\n"
+                    .into(),
+                before: "arm_slewed = slewLimit(arm_slewed, \
+                         shapeAxis(gamepad1.left_stick_y, 0.0, 0.5, 1.0), 4.0, arm_slew_timer);\n"
+                    .into(),
+                value: "arm_slewed".into(),
+            }
+        );
+    }
+}
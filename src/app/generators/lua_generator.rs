@@ -1,17 +1,44 @@
-use crate::app::generators::control::Control;
+use crate::app::generators::autonomous::step::{StepCode, StepParam, StepType};
+use crate::app::generators::condition::Condition;
+use crate::app::generators::control::{parse_modifiers, Control};
+use crate::app::generators::hardware::Hardware;
+use crate::app::generators::keybinding::behaviour::{
+    self, ButtonBehaviour, ButtonBinding, OtherChords,
+};
+use crate::app::generators::keybinding::keybinding::Gamepad;
+use crate::app::generators::keybinding::registry::{ComponentBinding, GamepadInput, Input};
+use crate::app::generators::keybinding::shaping::{self, AxisShaping};
+use crate::app::generators::lua_sandbox;
+use crate::app::generators::module_info::ModuleInfo;
+use crate::app::generators::module_library;
+use crate::app::generators::telemetry::TelemetryItem;
 use crate::app::generators::ui_elements::{
-    ButtonInput, CheckboxInput, ComboBoxInput, Slider, TextInput,
+    AxisShapingInput, ButtonInput, CheckboxInput, ComboBoxInput, ConditionInput, Slider, TextInput,
 };
+use crate::app::hardware_config::HardwareConfig;
+use crate::app::java::opmode::OpMode;
 use egui::Ui;
 use mlua::prelude::LuaError;
-use mlua::{Function, Lua, Table, UserData, UserDataMethods};
-use std::fs::File;
-use std::io::Read;
+use mlua::{ChunkMode, FromLua, Function, Lua, Table, UserData, UserDataMethods, Value};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
 
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
 pub struct ControlHandler {
     pub scripts: Vec<String>,
     pub generators: Vec<LuaGenerator>,
+    /// Manifests of `scripts`, read the first time they're needed
+    #[serde(skip)]
+    module_infos: BTreeMap<String, ModuleInfo>,
+    /// The hardware configuration read from the Robot Controller, offered for every
+    /// component's device names
+    #[serde(skip)]
+    pub robot_config: Option<HardwareConfig>,
+    /// Positions of the components removed from the list, in the order they went, until
+    /// [`ControlHandler::take_removed`] is called
+    #[serde(skip)]
+    removed: Vec<usize>,
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -23,6 +50,48 @@ pub struct LuaGenerator {
     pub controls: Vec<Control>,
     #[serde(skip)]
     pub loaded: bool,
+    /// The most recent error from each Lua function, keyed by function name.
+    /// Cleared when that function next succeeds.
+    #[serde(skip)]
+    pub errors: RefCell<BTreeMap<String, GeneratorError>>,
+    /// Inputs this component shares with other components, from the robot's
+    /// [`KeybindingRegistry`](crate::app::generators::keybinding::registry::KeybindingRegistry)
+    #[serde(skip)]
+    pub keybinding_conflicts: Vec<String>,
+    /// Conditions offered by every component on the robot, handed to the script as
+    /// `sensorConditions`
+    #[serde(skip)]
+    pub sensor_conditions: Vec<Condition>,
+}
+
+/// An error raised while loading a Lua module or calling one of its functions
+#[derive(Debug, Clone)]
+pub struct GeneratorError {
+    pub script: String,
+    pub function: String,
+    pub message: String,
+}
+
+/// Upper bound on tick/get_controls rounds when restoring saved controls, in case a script
+/// never reports its controls as settled
+const MAX_RESTORE_PASSES: usize = 8;
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.script, self.function, self.message)
+    }
+}
+
+impl GeneratorError {
+    /// The error without its Lua traceback, for places that only have room for one line
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: {}: {}",
+            self.script,
+            self.function,
+            self.message.lines().next().unwrap_or_default()
+        )
+    }
 }
 
 impl Clone for LuaGenerator {
@@ -31,164 +100,93 @@ impl Clone for LuaGenerator {
             lua: Lua::new(),
             script: self.script.clone(),
             script_data: self.script_data.clone(),
-            controls: vec![],
+            controls: self.controls.clone(),
             loaded: false,
+            errors: Default::default(),
+            keybinding_conflicts: vec![],
+            sensor_conditions: vec![],
         };
     }
 }
 
 impl ControlHandler {
     pub fn render(&mut self, ui: &mut Ui) {
-        self.tick_lua();
+        // errors are kept on the component that raised them and shown by render_controls
+        let _ = self.tick_lua();
 
-        self.add_controls();
+        let _ = self.add_controls();
 
         self.render_controls(ui);
     }
 
-    pub fn add_controls(&mut self) {
-        let mut i = 0;
+    /// Update every generator's controls. A failing generator doesn't stop the others;
+    /// the first error is returned once all have run.
+    pub fn add_controls(&mut self) -> Result<(), GeneratorError> {
+        let mut result = Ok(());
+
         for generator in &mut self.generators {
-            let mut new_controls = vec![];
+            if let Err(e) = generator.update_controls() {
+                result = result.and(Err(e));
+            }
+        }
 
-            let get_controls: Function<'_> = generator
-                .lua
-                .globals()
-                .get("get_controls")
-                .expect("Lua scripts must contain a 'get_controls' method!");
-            let controls_changed: Function<'_> = generator
-                .lua
-                .globals()
-                .get("controls_changed")
-                .expect("Lua scripts must contain a 'controls_changed' method!");
+        result
+    }
 
-            let changed = controls_changed.call::<_, bool>(()).unwrap();
+    /// Load and tick every generator. A failing generator doesn't stop the others;
+    /// the first error is returned once all have run.
+    pub fn tick_lua(&mut self) -> Result<(), GeneratorError> {
+        let mut result = Ok(());
 
-            if changed == false {
-                // ensure unique IDs for each control. This is needed for repeated ComboBox controls
-                i += 100;
-                continue;
+        for generator in &mut self.generators {
+            if let Err(e) = generator.load().and_then(|_| generator.tick()) {
+                result = result.and(Err(e));
             }
+        }
 
-            generator.controls.clear();
-
-            let table = get_controls.call::<_, Table<'_>>(()).unwrap();
-
-            table
-                .for_each(|k: String, v: Table<'_>| {
-                    i += 1;
-                    match v.raw_get::<i32, String>(1) {
-                        Ok(s) => match s.as_str() {
-                            "Slider" => {
-                                let control = Control::SliderType(Slider {
-                                    name: v.raw_get::<i32, String>(2).unwrap(),
-                                    label: v.raw_get::<i32, String>(3).unwrap(),
-                                    min: v.raw_get::<i32, f32>(4).unwrap(),
-                                    max: v.raw_get::<i32, f32>(5).unwrap(),
-                                    value: v.raw_get::<i32, f32>(6).unwrap(),
-                                    step_by: v.raw_get::<i32, f64>(7).unwrap(),
-                                    deicimals: v.raw_get::<i32, usize>(8).unwrap(),
-                                    keybinding: None,
-                                });
-                                generator.lua.globals().set(k, control.clone()).unwrap();
-                                new_controls.push(control);
-                            }
-                            "TextInput" => {
-                                let control = Control::TextInputType(TextInput {
-                                    name: v.raw_get::<i32, String>(2).unwrap(),
-                                    label: v.raw_get::<i32, String>(3).unwrap(),
-                                    value: v.raw_get::<i32, String>(4).unwrap(),
-                                });
-                                generator.lua.globals().set(k, control.clone()).unwrap();
-                                new_controls.push(control);
-                            }
-                            "ComboBox" => {
-                                let mut entries = vec![];
-                                for i in 5..50 {
-                                    match v.raw_get::<i32, String>(i) {
-                                        Ok(s) => {
-                                            entries.push(s);
-                                        }
-                                        Err(_) => {
-                                            break;
-                                        }
-                                    }
-                                }
+        result
+    }
 
-                                let control = Control::ComboBoxType(ComboBoxInput {
-                                    name: v.raw_get::<i32, String>(2).unwrap(),
-                                    label: v.raw_get::<i32, String>(3).unwrap(),
-                                    value: v.raw_get::<i32, String>(4).unwrap(),
-                                    id: i,
-                                    entries: entries,
-                                });
+    /// Re-read every generator's script on the next tick, keeping the configured control values
+    pub fn reload(&mut self) {
+        self.module_infos.clear();
 
-                                generator.lua.globals().set(k, control.clone()).unwrap();
-                                new_controls.push(control);
-                            }
-                            "Checkbox" => {
-                                let control = Control::CheckboxType(CheckboxInput {
-                                    name: v.raw_get::<i32, String>(2).unwrap(),
-                                    label: v.raw_get::<i32, String>(3).unwrap(),
-                                    value: v.raw_get::<i32, i32>(4).unwrap() == 1,
-                                });
-                                new_controls.push(control);
-                            }
-                            "Button" => {
-                                let control = Control::ButtonType(ButtonInput {
-                                    name: v.raw_get::<i32, String>(2).unwrap(),
-                                    callback: v.raw_get::<i32, String>(3).unwrap(),
-                                });
-                                new_controls.push(control);
-                            }
-                            "Label" => {
-                                let control = Control::Label(v.raw_get::<i32, String>(2).unwrap());
-                                new_controls.push(control);
-                            }
-                            "Separator" => {
-                                let control = Control::Separator;
-                                new_controls.push(control);
-                            }
-                            "Spacer" => {
-                                let control = Control::Spacer;
-                                new_controls.push(control);
-                            }
-                            _ => {}
-                        },
-                        Err(_) => {}
-                    }
+        for generator in &mut self.generators {
+            generator.reload();
+        }
+    }
 
-                    Ok(())
-                })
-                .expect("Failed to parse table");
+    /// Offer `scripts` as the available modules and point every component at the module it now
+    /// resolves to, so overriding a built-in module takes effect on existing components too.
+    /// Components are reloaded, keeping their configuration.
+    pub fn set_scripts(&mut self, scripts: &[String]) {
+        self.scripts = scripts.to_vec();
 
-            generator.controls.append(&mut new_controls);
+        for generator in &mut self.generators {
+            generator.script = module_library::resolve_script(&generator.script, scripts);
         }
+
+        self.reload();
     }
 
-    pub fn tick_lua(&mut self) {
-        for generator in &mut self.generators {
-            generator.load();
-            for control in &generator.controls {
-                generator
-                    .lua
-                    .globals()
-                    .set(control.get_name(), control.clone())
-                    .unwrap();
+    /// Manifests of the modules that can be added here, in the same order as `scripts`
+    pub fn modules(&mut self) -> Vec<&ModuleInfo> {
+        for script in &self.scripts {
+            if !self.module_infos.contains_key(script) {
+                self.module_infos
+                    .insert(script.clone(), ModuleInfo::load(script));
             }
-            let _ = generator
-                .lua
-                .globals()
-                .get("tick")
-                .and_then(|tick: Function<'_>| {
-                    tick.call::<_, ()>(()).unwrap();
-                    Ok(())
-                });
         }
+
+        self.scripts
+            .iter()
+            .filter_map(|script| self.module_infos.get(script))
+            .collect()
     }
 
     pub fn render_controls(&mut self, ui: &mut Ui) {
         let mut removed_generators = vec![];
+        let robot_config = &self.robot_config;
 
         egui::Grid::new("Controls Grid").show(ui, |ui| {
             let mut last_generator_type = "".to_string();
@@ -213,90 +211,117 @@ impl ControlHandler {
                         ui.end_row();
                     }
 
-                    ui.vertical(|ui| {
-                        for control in &mut generator.controls {
-                            control.render(ui, &generator.lua);
-                        }
-                        if ui.button("Remove component").clicked() {
-                            removed_generators.push(id);
-                        }
+                    // scope widget IDs per component so repeated controls don't collide
+                    ui.push_id(id, |ui| {
+                        ui.vertical(|ui| {
+                            generator.render_errors(ui);
+                            generator.render_keybinding_conflicts(ui);
+
+                            let hardware = match robot_config {
+                                Some(_) => generator.hardware(),
+                                None => vec![],
+                            };
+
+                            for control in &mut generator.controls {
+                                // device names are the text inputs the hardware is named by
+                                let device = match control {
+                                    Control::TextInputType(t) => {
+                                        hardware.iter().find(|hardware| hardware.name == t.value)
+                                    }
+                                    _ => None,
+                                };
+
+                                control.render(ui, &generator.lua);
+
+                                if let (Some(config), Some(device), Control::TextInputType(t)) =
+                                    (robot_config, device, control)
+                                {
+                                    ui.horizontal(|ui| {
+                                        config.render_name_picker(ui, device.role, &mut t.value)
+                                    });
+                                }
+                            }
+                            if ui.button("Remove component").clicked() {
+                                removed_generators.push(id);
+                            }
+                        });
                     });
 
                     i = i + 1;
                 });
         });
 
-        for generator in removed_generators {
+        // later positions first, so the earlier ones still point at the right component
+        removed_generators.sort_unstable();
+        for generator in removed_generators.into_iter().rev() {
             self.generators.remove(generator);
+            self.removed.push(generator);
         }
     }
 
-    pub fn generate_includes(&self) -> String {
+    /// Positions of the components removed since this was last called, each as it was when
+    /// that component went, so TeleOps can forget or move their changes to them
+    pub fn take_removed(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.removed)
+    }
+
+    /// Call `function` on every generator and concatenate the Java it returns.
+    /// Generators that don't define `function` contribute nothing.
+    pub fn try_generate(&self, function: &str) -> Result<String, GeneratorError> {
         let mut code: String = "".to_string();
 
         for generator in &self.generators {
-            let func: Result<Function<'_>, LuaError> =
-                generator.lua.globals().get("generate_includes");
-
-            if let Ok(f) = func {
-                code += &*f.call::<_, String>(()).unwrap();
-            }
+            code += &generator.generate(function)?;
         }
-        code.to_string()
+        Ok(code)
     }
 
-    pub fn generate_init(&self) -> String {
-        let mut code: String = "".to_string();
+    /// Like `try_generate`, but a broken component only replaces its own output with an
+    /// `// ERROR` comment so the rest of the robot still generates
+    fn generate(&self, function: &str) -> String {
+        self.generators
+            .iter()
+            .map(|generator| match generator.generate(function) {
+                Ok(code) => code,
+                Err(e) => format!("// ERROR {}\n", e.summary()),
+            })
+            .collect()
+    }
 
-        for generator in &self.generators {
-            let func: Result<Function<'_>, LuaError> = generator.lua.globals().get("generate_init");
+    pub fn generate_includes(&self) -> String {
+        self.generate("generate_includes")
+    }
 
-            if let Ok(f) = func {
-                code += &*f.call::<_, String>(()).unwrap();
-            }
-        }
-        code.to_string()
+    pub fn generate_init(&self) -> String {
+        self.generate("generate_init")
     }
 
     pub fn generate_globals(&self) -> String {
-        let mut code: String = "".to_string();
-
-        for generator in &self.generators {
-            let func: Result<Function<'_>, LuaError> =
-                generator.lua.globals().get("generate_globals");
-
-            if let Ok(f) = func {
-                code += &*f.call::<_, String>(()).unwrap();
-            }
-        }
-        code.to_string()
+        self.generate("generate_globals")
     }
 
     pub fn generate_loop_one_time_setup(&self) -> String {
-        let mut code: String = "".to_string();
-
-        for generator in &self.generators {
-            let func: Result<Function<'_>, LuaError> =
-                generator.lua.globals().get("generate_loop_one_time_setup");
-
-            if let Ok(f) = func {
-                code += &*f.call::<_, String>(()).unwrap();
-            }
-        }
-        code.to_string()
+        self.generate("generate_loop_one_time_setup")
     }
 
     pub fn generate_loop(&self) -> String {
-        let mut code: String = "".to_string();
+        self.generate("generate_loop")
+    }
 
-        for generator in &self.generators {
-            let func: Result<Function<'_>, LuaError> = generator.lua.globals().get("generate_loop");
+    pub fn generate_methods(&self) -> String {
+        self.generate("generate_methods")
+    }
 
-            if let Ok(f) = func {
-                code += &*f.call::<_, String>(()).unwrap();
-            }
-        }
-        code.to_string()
+    /// Add every generator's code to `opmode`
+    pub fn generate_into(&self, opmode: &mut OpMode) {
+        opmode.add_includes(&self.generate_includes());
+        opmode.fields.code(&self.generate_globals());
+        opmode.init.code(&self.generate_init());
+        opmode
+            .loop_one_time_setup
+            .code(&self.generate_loop_one_time_setup());
+        opmode.loop_body.code(&self.generate_loop());
+        opmode.members.code(&self.generate_methods());
     }
 }
 
@@ -320,36 +345,857 @@ impl UserData for LuaGenerator {
 
 impl LuaGenerator {
     pub fn new(script_path: &str) -> Self {
-        let mut generator = Self {
+        Self {
             lua: Lua::new(),
             script: script_path.to_string(),
             script_data: "".into(),
             loaded: false,
             controls: vec![],
-        };
-        generator.render();
+            errors: Default::default(),
+            keybinding_conflicts: vec![],
+            sensor_conditions: vec![],
+        }
+    }
+
+    /// Create a generator whose controls start from previously saved values
+    pub fn with_controls(script_path: &str, controls: Vec<Control>) -> Self {
+        let mut generator = Self::new(script_path);
+        generator.controls = controls;
         generator
     }
 
-    pub fn load(&mut self) {
+    pub fn load(&mut self) -> Result<(), GeneratorError> {
         if let true = self.loaded {
-            return;
+            return Ok(());
         }
 
         self.loaded = true;
+        self.errors.borrow_mut().clear();
+
+        self.script_data = module_library::read_module(&self.script)
+            .map_err(|e| self.error("load", LuaError::external(e)))?;
+
+        // modules run sandboxed; `require "lua_private.Helpers"` resolves next to the module
+        // directory no matter where FTCreate was started from
+        let root = module_library::module_root(&self.script);
+        self.lua = lua_sandbox::new_lua(root.as_deref()).map_err(|e| self.error("load", e))?;
+        behaviour::register(&self.lua).map_err(|e| self.error("load", e))?;
+        shaping::register(&self.lua).map_err(|e| self.error("load", e))?;
+
+        lua_sandbox::reset_instruction_budget(&self.lua);
+        self.lua
+            .load(&self.script_data)
+            .set_name(&self.script)
+            .set_mode(ChunkMode::Text)
+            .exec()
+            .map_err(|e| self.error("load", e))?;
+
+        // refuse modules written for a newer FTCreate before they fail in confusing ways
+        if let Some(problem) = ModuleInfo::from_lua(&self.lua, &self.script).incompatibility() {
+            return Err(self.error("load", LuaError::runtime(problem)));
+        }
+
+        if !self.controls.is_empty() {
+            self.restore()?;
+        }
+
+        Ok(())
+    }
+
+    /// Ask the script for its controls if it reports that they changed.
+    /// Returns whether the controls were rebuilt.
+    pub fn update_controls(&mut self) -> Result<bool, GeneratorError> {
+        if self.stopped().is_some() {
+            return Ok(false);
+        }
+        self.clear_error("get_controls");
+        self.clear_error("controls_changed");
+        lua_sandbox::reset_instruction_budget(&self.lua);
+
+        let mut i = 0;
+        let mut new_controls = vec![];
+
+        let get_controls: Function<'_> = self.lua.globals().get("get_controls").map_err(|_| {
+            self.error(
+                "get_controls",
+                LuaError::runtime("Lua scripts must contain a 'get_controls' method!"),
+            )
+        })?;
+        let controls_changed: Function<'_> =
+            self.lua.globals().get("controls_changed").map_err(|_| {
+                self.error(
+                    "controls_changed",
+                    LuaError::runtime("Lua scripts must contain a 'controls_changed' method!"),
+                )
+            })?;
+
+        let changed = controls_changed
+            .call::<_, bool>(())
+            .map_err(|e| self.error("controls_changed", e))?;
+
+        if !changed {
+            return Ok(false);
+        }
+
+        let table = get_controls
+            .call::<_, Table<'_>>(())
+            .map_err(|e| self.error("get_controls", e))?;
+
+        table
+            .for_each(|k: String, v: Table<'_>| {
+                i += 1;
+                if let Ok(s) = v.raw_get::<i32, String>(1) {
+                    match s.as_str() {
+                        "Slider" => {
+                            let control = Control::SliderType(Slider {
+                                name: v.raw_get::<i32, String>(2)?,
+                                label: v.raw_get::<i32, String>(3)?,
+                                min: v.raw_get::<i32, f32>(4)?,
+                                max: v.raw_get::<i32, f32>(5)?,
+                                value: v.raw_get::<i32, f32>(6)?,
+                                step_by: v.raw_get::<i32, f64>(7)?,
+                                deicimals: v.raw_get::<i32, usize>(8)?,
+                                keybinding: None,
+                            });
+                            self.lua.globals().set(k, control.clone())?;
+                            new_controls.push(control);
+                        }
+                        "TextInput" => {
+                            let control = Control::TextInputType(TextInput {
+                                name: v.raw_get::<i32, String>(2)?,
+                                label: v.raw_get::<i32, String>(3)?,
+                                value: v.raw_get::<i32, String>(4)?,
+                            });
+                            self.lua.globals().set(k, control.clone())?;
+                            new_controls.push(control);
+                        }
+                        "ComboBox" => {
+                            let control = Control::ComboBoxType(ComboBoxInput {
+                                name: v.raw_get::<i32, String>(2)?,
+                                label: v.raw_get::<i32, String>(3)?,
+                                value: v.raw_get::<i32, String>(4)?,
+                                id: i,
+                                entries: combo_box_entries(&v, 5),
+                                gamepad: None,
+                                behaviour: None,
+                                behaviours: vec![],
+                                modifiers: vec![],
+                            });
+
+                            self.lua.globals().set(k, control.clone())?;
+                            new_controls.push(control);
+                        }
+                        "Keybinding" => {
+                            let gamepad = v.raw_get::<i32, String>(5)?;
+                            let behaviour = v
+                                .raw_get::<_, Option<String>>("behaviour")?
+                                .map(|behaviour| {
+                                    ButtonBehaviour::from_name(&behaviour).ok_or_else(|| {
+                                        LuaError::runtime(format!(
+                                            "'{}' isn't a button behaviour",
+                                            behaviour
+                                        ))
+                                    })
+                                })
+                                .transpose()?;
+                            let behaviours = v
+                                .raw_get::<_, Option<Vec<String>>>("behaviours")?
+                                .unwrap_or_default()
+                                .iter()
+                                .map(|behaviour| {
+                                    ButtonBehaviour::from_name(behaviour).ok_or_else(|| {
+                                        LuaError::runtime(format!(
+                                            "'{}' isn't a button behaviour",
+                                            behaviour
+                                        ))
+                                    })
+                                })
+                                .collect::<Result<Vec<_>, _>>()?;
+                            // a binding saved before the module narrowed its behaviours falls
+                            // back to the first one it still offers
+                            let behaviour = behaviour.map(|behaviour| match behaviours.first() {
+                                Some(first) if !behaviours.contains(&behaviour) => *first,
+                                _ => behaviour,
+                            });
+                            let modifiers = v
+                                .raw_get::<_, Option<Vec<String>>>("modifiers")?
+                                .unwrap_or_default();
+
+                            let control = Control::ComboBoxType(ComboBoxInput {
+                                name: v.raw_get::<i32, String>(2)?,
+                                label: v.raw_get::<i32, String>(3)?,
+                                value: v.raw_get::<i32, String>(4)?,
+                                id: i,
+                                entries: combo_box_entries(&v, 6),
+                                gamepad: Some(Gamepad::from_name(&gamepad).ok_or_else(|| {
+                                    LuaError::runtime(format!("'{}' isn't a gamepad", gamepad))
+                                })?),
+                                behaviour,
+                                behaviours,
+                                modifiers: parse_modifiers(&modifiers)?,
+                            });
+
+                            self.lua.globals().set(k, control.clone())?;
+                            new_controls.push(control);
+                        }
+                        "AxisShaping" => {
+                            let control = Control::AxisShapingType(AxisShapingInput {
+                                name: v.raw_get::<i32, String>(2)?,
+                                label: v.raw_get::<i32, String>(3)?,
+                                shaping: AxisShaping {
+                                    deadband: v.raw_get::<i32, f32>(4)?,
+                                    expo: v.raw_get::<i32, f32>(5)?,
+                                    scale: v.raw_get::<i32, f32>(6)?,
+                                    inverted: v.raw_get::<i32, bool>(7)?,
+                                    slew_rate: v.raw_get::<i32, f32>(8)?,
+                                },
+                            });
+
+                            self.lua.globals().set(k, control.clone())?;
+                            new_controls.push(control);
+                        }
+                        "Condition" => {
+                            let control = Control::ConditionType(ConditionInput {
+                                name: v.raw_get::<i32, String>(2)?,
+                                label: v.raw_get::<i32, String>(3)?,
+                                value: v.raw_get::<i32, String>(4)?,
+                            });
+
+                            self.lua.globals().set(k, control.clone())?;
+                            new_controls.push(control);
+                        }
+                        "Checkbox" => {
+                            let control = Control::CheckboxType(CheckboxInput {
+                                name: v.raw_get::<i32, String>(2)?,
+                                label: v.raw_get::<i32, String>(3)?,
+                                value: v.raw_get::<i32, i32>(4)? == 1,
+                            });
+                            new_controls.push(control);
+                        }
+                        "Button" => {
+                            let control = Control::ButtonType(ButtonInput {
+                                name: v.raw_get::<i32, String>(2)?,
+                                callback: v.raw_get::<i32, String>(3)?,
+                                error: None,
+                            });
+                            new_controls.push(control);
+                        }
+                        "Label" => {
+                            let control = Control::Label(v.raw_get::<i32, String>(2)?);
+                            new_controls.push(control);
+                        }
+                        "Separator" => {
+                            let control = Control::Separator;
+                            new_controls.push(control);
+                        }
+                        "Spacer" => {
+                            let control = Control::Spacer;
+                            new_controls.push(control);
+                        }
+                        _ => {}
+                    }
+                }
+
+                Ok(())
+            })
+            .map_err(|e| self.error("get_controls", e))?;
+
+        self.controls.clear();
+        self.controls.append(&mut new_controls);
+
+        Ok(true)
+    }
+
+    /// Push the current control values into the Lua VM and call the script's `tick`
+    pub fn tick(&mut self) -> Result<(), GeneratorError> {
+        if self.stopped().is_some() {
+            return Ok(());
+        }
+        self.clear_error("tick");
+        lua_sandbox::reset_instruction_budget(&self.lua);
+
+        for control in &self.controls {
+            self.lua
+                .globals()
+                .set(control.get_name(), control.clone())
+                .map_err(|e| self.error("tick", e))?;
+        }
+        Condition::set_global(&self.lua, &self.sensor_conditions)
+            .map_err(|e| self.error("tick", e))?;
+        if let Ok(tick) = self.lua.globals().get::<_, Function<'_>>("tick") {
+            tick.call::<_, ()>(()).map_err(|e| self.error("tick", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Bring a freshly loaded Lua VM back to the state described by `self.controls`.
+    ///
+    /// The saved values are handed to the script's optional `restore_controls(values)` hook
+    /// (a table of control name to value), then the script is ticked until its controls stop
+    /// changing so anything it derives from them, such as the number of positions, is rebuilt.
+    /// The controls helpers read existing globals, so scripts without the hook are restored too.
+    fn restore(&mut self) -> Result<(), GeneratorError> {
+        // scoped because the values table borrows the VM until it's dropped
+        {
+            let values = self
+                .lua
+                .create_table()
+                .map_err(|e| self.error("restore_controls", e))?;
+
+            for control in &self.controls {
+                let result = match control {
+                    Control::SliderType(s) => values.set(s.name.as_str(), s.value),
+                    Control::CheckboxType(c) => values.set(c.name.as_str(), c.value as i32),
+                    Control::TextInputType(t) => values.set(t.name.as_str(), t.value.as_str()),
+                    Control::ComboBoxType(c) => values.set(c.name.as_str(), c.value.as_str()),
+                    Control::ConditionType(c) => values.set(c.name.as_str(), c.value.as_str()),
+                    _ => Ok(()),
+                };
+                result.map_err(|e| self.error("restore_controls", e))?;
+            }
+
+            if let Ok(hook) = self
+                .lua
+                .globals()
+                .get::<_, Function<'_>>("restore_controls")
+            {
+                lua_sandbox::reset_instruction_budget(&self.lua);
+                hook.call::<_, ()>(values)
+                    .map_err(|e| self.error("restore_controls", e))?;
+            }
+        }
+
+        for _ in 0..MAX_RESTORE_PASSES {
+            self.tick()?;
+            if !self.update_controls()? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Throw away the Lua VM so the script is read again on the next tick.
+    /// Control values are kept and restored into the new VM.
+    pub fn reload(&mut self) {
+        self.loaded = false;
+    }
+
+    /// Call one of the script's `generate_*` functions. Scripts that don't define `function`
+    /// generate nothing for it.
+    pub fn generate(&self, function: &str) -> Result<String, GeneratorError> {
+        if let Some(e) = self.stopped() {
+            return Err(e);
+        }
+        self.clear_error(function);
+        lua_sandbox::reset_instruction_budget(&self.lua);
+
+        let f = match self.lua.globals().get::<_, Function<'_>>(function) {
+            Ok(f) => f,
+            Err(_) => return Ok("".to_string()),
+        };
+
+        // functions may return the code as a string, or as a list of lines. A list from
+        // `generate_includes` holds class names, e.g. { "com.qualcomm.robotcore.hardware.Servo" }
+        match f
+            .call::<_, Value<'_>>(())
+            .map_err(|e| self.error(function, e))?
+        {
+            Value::Table(lines) => lines
+                .sequence_values::<String>()
+                .map(|line| match function {
+                    "generate_includes" => line.map(|name| format!("import {};\n", name)),
+                    _ => line.map(|line| line + "\n"),
+                })
+                .collect::<mlua::Result<String>>()
+                .map_err(|e| self.error(function, e)),
+            Value::Nil => Ok("".to_string()),
+            value => String::from_lua(value, &self.lua).map_err(|e| self.error(function, e)),
+        }
+    }
+
+    /// The gamepad inputs this component reads: every keybinding control, including those
+    /// set to "none", plus anything listed by the script's optional `get_keybindings()`:
+    ///
+    /// ```lua
+    /// function get_keybindings()
+    ///     return { { gamepad = "gamepad1", input = "left_stick_y", description = "Drive" } }
+    /// end
+    /// ```
+    pub fn keybindings(&self) -> Vec<ComponentBinding> {
+        let mut keybindings: Vec<ComponentBinding> = self
+            .controls
+            .iter()
+            .filter_map(|control| match control {
+                Control::ComboBoxType(ComboBoxInput {
+                    gamepad: Some(gamepad),
+                    name,
+                    value,
+                    label,
+                    behaviour,
+                    modifiers,
+                    ..
+                }) => Some(ComponentBinding {
+                    input: Input::from_name(value).map(|input| GamepadInput::new(*gamepad, input)),
+                    description: label.clone(),
+                    control: Some(name.clone()),
+                    behaviour: behaviour.unwrap_or_default(),
+                    modifiers: modifiers.clone(),
+                    yields_to_chords: behaviour.is_some(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        keybindings.extend(self.optional_list("get_keybindings", |table| {
+            let gamepad = table
+                .get::<_, Option<String>>("gamepad")?
+                .unwrap_or_else(|| format!("{:?}", Gamepad::default()));
+            let input = table.get::<_, String>("input")?;
+
+            let gamepad = Gamepad::from_name(&gamepad)
+                .ok_or_else(|| LuaError::runtime(format!("'{}' isn't a gamepad", gamepad)))?;
+            let input = Input::from_name(&input).ok_or_else(|| {
+                LuaError::runtime(format!("'{}' isn't a gamepad button or axis", input))
+            })?;
+
+            Ok(ComponentBinding {
+                input: Some(GamepadInput::new(gamepad, input)),
+                description: table
+                    .get::<_, Option<String>>("description")?
+                    .unwrap_or_else(|| input.to_string()),
+                control: None,
+                behaviour: ButtonBehaviour::default(),
+                modifiers: vec![],
+                yields_to_chords: false,
+            })
+        }));
+
+        keybindings
+    }
+
+    /// Hand `buttonLogic` the chords the rest of the robot binds, so this component's bindings
+    /// give way to longer ones elsewhere. Kept until the VM is reloaded.
+    pub fn share_chords(&self, others: Vec<ButtonBinding>) {
+        self.lua.set_app_data(OtherChords(others));
+    }
+
+    /// Point the keybinding control named `control` at `input`, or at nothing.
+    /// Returns whether there was such a control.
+    pub fn set_keybinding(&mut self, control: &str, input: Option<GamepadInput>) -> bool {
+        for c in &mut self.controls {
+            if let Control::ComboBoxType(combo_box) = c {
+                if combo_box.name == control && combo_box.gamepad.is_some() {
+                    match input {
+                        Some(input) => {
+                            combo_box.value = input.input.to_string();
+                            combo_box.gamepad = Some(input.gamepad);
+                        }
+                        None => combo_box.value = "none".into(),
+                    }
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// The conditions this component offers other components, from the script's optional
+    /// `get_conditions()`
+    pub fn conditions(&self) -> Vec<Condition> {
+        self.optional_list("get_conditions", Condition::from_table)
+    }
+
+    /// The values this component can show on the Driver Station, from the script's optional
+    /// `get_telemetry()`
+    pub fn telemetry(&self) -> Vec<TelemetryItem> {
+        self.optional_list("get_telemetry", TelemetryItem::from_table)
+    }
+
+    /// The hardware this component sets up, from the script's optional `get_hardware()`
+    pub fn hardware(&self) -> Vec<Hardware> {
+        self.optional_list("get_hardware", Hardware::from_table)
+    }
+
+    /// Autonomous step types this component offers, from the script's optional
+    /// `get_autonomous_steps()`
+    pub fn autonomous_steps(&self) -> Vec<StepType> {
+        self.optional_list("get_autonomous_steps", |table| {
+            let params = match table.get::<_, Option<Table<'_>>>("params")? {
+                Some(params) => params
+                    .sequence_values::<Table<'_>>()
+                    .map(|param| {
+                        let param = param?;
+                        Ok(StepParam {
+                            name: param.get(1)?,
+                            value: param.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                        })
+                    })
+                    .collect::<mlua::Result<_>>()?,
+                None => vec![],
+            };
+
+            Ok(StepType {
+                name: table.get("name")?,
+                params,
+            })
+        })
+    }
+
+    /// Generate one of this component's autonomous steps with the script's
+    /// `generate_autonomous_step(name, params)`
+    pub fn generate_autonomous_step(
+        &self,
+        name: &str,
+        params: &[StepParam],
+    ) -> Result<StepCode, GeneratorError> {
+        const FUNCTION: &str = "generate_autonomous_step";
+
+        if let Some(e) = self.stopped() {
+            return Err(e);
+        }
+        self.clear_error(FUNCTION);
+        lua_sandbox::reset_instruction_budget(&self.lua);
+
+        let f: Function<'_> = self.lua.globals().get(FUNCTION).map_err(|_| {
+            self.error(
+                FUNCTION,
+                LuaError::runtime(format!("the module has no '{}' function", FUNCTION)),
+            )
+        })?;
+
+        let values = self
+            .lua
+            .create_table()
+            .map_err(|e| self.error(FUNCTION, e))?;
+        for param in params {
+            values
+                .set(param.name.as_str(), param.value.as_str())
+                .map_err(|e| self.error(FUNCTION, e))?;
+        }
+
+        let mut code = StepCode::default();
 
-        let mut script_data: String = "".to_string();
+        match f
+            .call::<_, Value<'_>>((name, values))
+            .map_err(|e| self.error(FUNCTION, e))?
+        {
+            Value::Table(table) => {
+                let part = |key: &str| {
+                    table
+                        .get::<_, Option<String>>(key)
+                        .map_err(|e| self.error(FUNCTION, e))
+                };
+                code.start.code(&part("start")?.unwrap_or_default());
+                code.update.code(&part("update")?.unwrap_or_default());
+                code.finish.code(&part("finish")?.unwrap_or_default());
+                code.busy = part("busy")?.filter(|busy| !busy.trim().is_empty());
+            }
+            Value::Nil => {}
+            value => {
+                let start =
+                    String::from_lua(value, &self.lua).map_err(|e| self.error(FUNCTION, e))?;
+                code.start.code(&start);
+            }
+        }
+
+        Ok(code)
+    }
+
+    /// Call an optional script function returning a list of tables, converting each with
+    /// `convert`. Errors are shown on the component and give an empty list.
+    fn optional_list<T>(
+        &self,
+        function: &str,
+        convert: impl Fn(&Table<'_>) -> mlua::Result<T>,
+    ) -> Vec<T> {
+        if !self.loaded || self.stopped().is_some() {
+            return vec![];
+        }
+        lua_sandbox::reset_instruction_budget(&self.lua);
+
+        let f = match self.lua.globals().get::<_, Function<'_>>(function) {
+            Ok(f) => f,
+            Err(_) => return vec![],
+        };
+
+        let result = f.call::<_, Table<'_>>(()).and_then(|list| {
+            list.sequence_values::<Table<'_>>()
+                .map(|entry| convert(&entry?))
+                .collect::<mlua::Result<Vec<T>>>()
+        });
+
+        match result {
+            Ok(list) => {
+                self.clear_error(function);
+                list
+            }
+            Err(e) => {
+                self.error(function, e);
+                vec![]
+            }
+        }
+    }
+
+    /// The error that stopped this component: its script failed to load, or a call ran out of
+    /// instructions. Nothing in the script is called again until it's reloaded, so a runaway
+    /// `tick` doesn't spend the whole instruction budget every frame.
+    fn stopped(&self) -> Option<GeneratorError> {
+        let errors = self.errors.borrow();
 
-        let file = File::open(self.script.clone());
+        errors.get("load").cloned().or_else(|| {
+            errors
+                .values()
+                .find(|error| lua_sandbox::hit_instruction_limit(&error.message))
+                .cloned()
+        })
+    }
 
-        file.unwrap().read_to_string(&mut script_data).unwrap();
+    fn clear_error(&self, function: &str) {
+        self.errors.borrow_mut().remove(function);
+    }
 
-        self.script_data = script_data.to_string();
+    /// Build an error for `function` and remember it so it's shown on the component
+    fn error(&self, function: &str, e: LuaError) -> GeneratorError {
+        let error = GeneratorError {
+            script: self.script.clone(),
+            function: function.to_string(),
+            message: e.to_string(),
+        };
+        self.errors
+            .borrow_mut()
+            .insert(function.to_string(), error.clone());
+        error
+    }
 
-        self.lua.load(&self.script_data).exec().unwrap();
+    /// Show any errors this component's script raised, above its controls
+    pub fn render_errors(&self, ui: &mut Ui) {
+        for error in self.errors.borrow().values() {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("Error in {}", error.function),
+            );
+            ui.label(error.message.lines().next().unwrap_or_default());
+            if lua_sandbox::hit_instruction_limit(&error.message) {
+                ui.label("The component is stopped until the Lua modules are reloaded");
+            }
+            ui.collapsing("Lua traceback", |ui| {
+                ui.label(format!("{}\n\n{}", error.script, error.message));
+            });
+            ui.separator();
+        }
     }
-    pub fn render(&mut self) {
-        self.load();
-        return;
+
+    /// Warn about inputs this component shares with others, above its controls
+    pub fn render_keybinding_conflicts(&self, ui: &mut Ui) {
+        if self.keybinding_conflicts.is_empty() {
+            return;
+        }
+
+        ui.colored_label(ui.visuals().warn_fg_color, "Keybinding conflict");
+        for conflict in &self.keybinding_conflicts {
+            ui.label(conflict);
+        }
+        ui.separator();
+    }
+}
+
+/// Read the entries of a packed combo box control, starting at index `first`
+fn combo_box_entries(control: &Table<'_>, first: i32) -> Vec<String> {
+    (first..)
+        .map_while(|i| control.raw_get::<i32, String>(i).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// A generator running `source`, kept in a temporary module file
+    fn generator(source: &str) -> (LuaGenerator, tempfile::NamedTempFile) {
+        let mut module = tempfile::Builder::new().suffix(".lua").tempfile().unwrap();
+        module.write_all(source.as_bytes()).unwrap();
+
+        let generator = LuaGenerator::new(module.path().to_str().unwrap());
+        (generator, module)
+    }
+
+    /// A module whose position sliders follow its count slider, the way DC_Motor's do
+    const POSITIONS: &str = r#"
+        require "lua_private.Helpers"
+
+        controlsChanged = true
+        positions = 0
+        ticks = 0
+
+        function get_controls()
+            local controls = {}
+            controls[1] = textInput("T_Name", "Name", "arm")
+            controls[2] = slider("T_Count", "Positions", 0, 5, 1, 1, 0)
+            for i = 1, positions do
+                controls[#controls + 1] = slider("T_Pos" .. i, "Position", -100, 100, 0, 1, 0)
+            end
+            controlsChanged = false
+            return controls
+        end
+
+        function tick()
+            ticks = ticks + 1
+            if exists(T_Count) and T_Count.value ~= positions then
+                positions = T_Count.value
+                controlsChanged = true
+            end
+        end
+
+        function controls_changed()
+            return controlsChanged
+        end
+
+        function restore_controls(values)
+            restoredName = values.T_Name
+        end
+
+        function generate_loop()
+            local code = T_Name.text
+            for i = 1, positions do
+                code = code .. " " .. _G["T_Pos" .. i].value
+            end
+            return code
+        end
+    "#;
+
+    fn set_slider(generator: &mut LuaGenerator, name: &str, value: f32) {
+        for control in &mut generator.controls {
+            if let Control::SliderType(slider) = control {
+                if slider.name == name {
+                    slider.value = value;
+                }
+            }
+        }
+        generator.tick().unwrap();
+        generator.update_controls().unwrap();
+    }
+
+    fn saved(generator: &LuaGenerator) -> String {
+        serde_json::to_string(&generator.controls).unwrap()
+    }
+
+    #[test]
+    fn restores_controls_the_script_builds_from_other_controls() {
+        let (mut generator, module) = generator(POSITIONS);
+        generator.load().unwrap();
+        generator.tick().unwrap();
+        generator.update_controls().unwrap();
+        set_slider(&mut generator, "T_Count", 3.0);
+        set_slider(&mut generator, "T_Pos2", 40.0);
+        set_slider(&mut generator, "T_Pos3", -20.0);
+        assert_eq!(
+            generator.generate("generate_loop").unwrap(),
+            "arm 0.0 40.0 -20.0"
+        );
+
+        let controls = serde_json::from_str(&saved(&generator)).unwrap();
+        let mut restored = LuaGenerator::with_controls(module.path().to_str().unwrap(), controls);
+        restored.load().unwrap();
+
+        assert_eq!(saved(&restored), saved(&generator));
+        assert_eq!(
+            restored.generate("generate_loop").unwrap(),
+            "arm 0.0 40.0 -20.0"
+        );
+        assert_eq!(
+            restored
+                .lua
+                .globals()
+                .get::<_, String>("restoredName")
+                .unwrap(),
+            "arm"
+        );
+    }
+
+    #[test]
+    fn gives_up_restoring_a_script_whose_controls_never_settle() {
+        let (generator, module) = generator(
+            r#"
+            ticks = 0
+            function get_controls() return {} end
+            function tick() ticks = ticks + 1 end
+            function controls_changed() return true end
+            "#,
+        );
+        drop(generator);
+
+        let controls = vec![Control::Label("saved".into())];
+        let mut restored = LuaGenerator::with_controls(module.path().to_str().unwrap(), controls);
+        restored.load().unwrap();
+
+        assert_eq!(
+            restored.lua.globals().get::<_, usize>("ticks").unwrap(),
+            MAX_RESTORE_PASSES
+        );
+    }
+
+    #[test]
+    fn stops_calling_a_script_that_ran_out_of_instructions() {
+        let (mut generator, _module) = generator(
+            r#"
+            ticks = 0
+            function tick()
+                ticks = ticks + 1
+                while true do end
+            end
+            function generate_loop() return "" end
+            "#,
+        );
+        generator.load().unwrap();
+
+        assert!(generator.tick().is_err());
+        assert!(generator.tick().is_ok());
+        assert!(generator.generate("generate_loop").is_err());
+        assert_eq!(generator.lua.globals().get::<_, i32>("ticks").unwrap(), 1);
+
+        generator.reload();
+        generator.load().unwrap();
+        assert!(generator.tick().is_err());
+    }
+
+    #[test]
+    fn narrows_a_restored_behaviour_to_the_ones_the_module_offers() {
+        let (generator, module) = generator(
+            r#"
+            require "lua_private.Helpers"
+            changed = true
+            function get_controls()
+                changed = false
+                return { keybindingComboBox("K", "Key", "a", nil, "held", { "held", "toggle" }) }
+            end
+            function controls_changed() return changed end
+            function generate_loop() return K.behaviour end
+            "#,
+        );
+        drop(generator);
+
+        let controls = vec![Control::ComboBoxType(ComboBoxInput {
+            name: "K".into(),
+            value: "a".into(),
+            label: "Key".into(),
+            entries: vec![],
+            id: 0,
+            gamepad: Some(Gamepad::gamepad1),
+            behaviour: Some(ButtonBehaviour::cycle),
+            behaviours: vec![],
+            modifiers: vec![],
+        })];
+        let mut restored = LuaGenerator::with_controls(module.path().to_str().unwrap(), controls);
+        restored.load().unwrap();
+
+        assert_eq!(restored.generate("generate_loop").unwrap(), "held");
+        assert!(restored
+            .lua
+            .load(r#"K.behaviour = "cycle""#)
+            .exec()
+            .is_err());
     }
 }
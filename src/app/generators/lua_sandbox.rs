@@ -0,0 +1,212 @@
+use crate::app::generators::module_library;
+use mlua::prelude::LuaError;
+use mlua::{ChunkMode, HookTriggers, Lua, LuaOptions, StdLib, Table, Value};
+use std::path::Path;
+
+/// Memory a single module's Lua VM may allocate
+pub const MEMORY_LIMIT: usize = 32 * 1024 * 1024;
+
+/// Lua instructions a single call into a module may run before it's stopped
+pub const INSTRUCTION_LIMIT: u32 = 10_000_000;
+
+/// Registry key of the table caching helpers loaded with `require`
+const LOADED_KEY: &str = "ftcreate.loaded";
+
+/// Helpers modules are allowed to `require`, e.g. `lua_private.Helpers`
+const PRIVATE_PREFIX: &str = "lua_private.";
+
+/// Create the restricted VM a module runs in.
+///
+/// Modules get the table, string, math, utf8 and coroutine libraries and the clock/date parts
+/// of `os`. There's no `io`, no `os.execute`, no `dofile`/`loadfile`, `load` only accepts source
+/// text, and `require` can only load `lua_private` helpers: from the `lua_private` directory
+/// under `root` if it has one, otherwise the copy built into FTCreate.
+pub fn new_lua(root: Option<&Path>) -> mlua::Result<Lua> {
+    let lua = Lua::new_with(
+        StdLib::TABLE
+            | StdLib::STRING
+            | StdLib::MATH
+            | StdLib::UTF8
+            | StdLib::COROUTINE
+            | StdLib::OS,
+        LuaOptions::default(),
+    )?;
+
+    lua.set_memory_limit(MEMORY_LIMIT)?;
+
+    // scoped so the borrows of `lua` end before it's returned
+    {
+        let globals = lua.globals();
+
+        let os: Table<'_> = globals.get("os")?;
+        let safe_os = lua.create_table()?;
+        for name in ["clock", "date", "difftime", "time"] {
+            safe_os.set(name, os.get::<_, Value<'_>>(name)?)?;
+        }
+        globals.set("os", safe_os)?;
+
+        globals.set("dofile", Value::Nil)?;
+        globals.set("loadfile", Value::Nil)?;
+
+        lua.load(
+            r##"
+            local load = load
+            _G.load = function(chunk, name, _, ...)
+                -- an env given as nil is still an env, so only pass one on if it was given
+                if select("#", ...) > 0 then
+                    return load(chunk, name, "t", ...)
+                end
+                return load(chunk, name, "t")
+            end
+            string.dump = nil
+            "##,
+        )
+        .set_name("sandbox")
+        .exec()?;
+
+        lua.set_named_registry_value(LOADED_KEY, lua.create_table()?)?;
+
+        let root = root.map(Path::to_path_buf);
+        let require = lua.create_function(move |lua, name: String| {
+            let loaded: Table<'_> = lua.named_registry_value(LOADED_KEY)?;
+
+            if let Some(module) = loaded.get::<_, Option<Value<'_>>>(name.as_str())? {
+                return Ok(module);
+            }
+
+            let helper = helper_name(&name).ok_or_else(|| {
+                LuaError::runtime(format!(
+                    "module '{}' not found: modules can only require helpers from lua_private",
+                    name
+                ))
+            })?;
+
+            let path = root
+                .as_ref()
+                .map(|root| root.join("lua_private").join(format!("{}.lua", helper)))
+                .filter(|path| path.is_file());
+
+            let source = match path {
+                Some(path) => std::fs::read_to_string(&path)
+                    .map_err(|e| LuaError::runtime(format!("module '{}': {}", name, e)))?,
+                None => module_library::builtin_helper(helper)
+                    .ok_or_else(|| LuaError::runtime(format!("module '{}' not found", name)))?
+                    .to_string(),
+            };
+
+            let module = match lua
+                .load(&source)
+                .set_name(&name)
+                .set_mode(ChunkMode::Text)
+                .call::<_, Value<'_>>(())?
+            {
+                Value::Nil => Value::Boolean(true),
+                module => module,
+            };
+
+            loaded.set(name.as_str(), module.clone())?;
+
+            Ok(module)
+        })?;
+        globals.set("require", require)?;
+    }
+
+    Ok(lua)
+}
+
+/// Start of the error a module gets when it runs out of instructions
+const INSTRUCTION_LIMIT_ERROR: &str = "stopped after";
+
+/// Give the VM a fresh instruction budget. Called before every call into a module so a runaway
+/// loop is stopped with an error on its component instead of freezing the UI.
+pub fn reset_instruction_budget(lua: &Lua) {
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(INSTRUCTION_LIMIT),
+        |_, _| {
+            Err(LuaError::runtime(format!(
+                "{} {} instructions. Is there an infinite loop?",
+                INSTRUCTION_LIMIT_ERROR, INSTRUCTION_LIMIT
+            )))
+        },
+    );
+}
+
+/// Whether an error message is the one a module gets for running out of instructions
+pub fn hit_instruction_limit(message: &str) -> bool {
+    message.contains(&format!(
+        "{} {} instructions",
+        INSTRUCTION_LIMIT_ERROR, INSTRUCTION_LIMIT
+    ))
+}
+
+/// Map `lua_private.Name` to `Name`, refusing anything else
+fn helper_name(name: &str) -> Option<&str> {
+    let helper = name.strip_prefix(PRIVATE_PREFIX)?;
+
+    if helper.is_empty()
+        || !helper
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+
+    Some(helper)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_runs_in_the_globals_without_an_env() {
+        let lua = new_lua(None).unwrap();
+
+        let pi: f64 = lua
+            .load(r#"return load("return math.pi")()"#)
+            .eval()
+            .unwrap();
+
+        assert_eq!(pi, std::f64::consts::PI);
+    }
+
+    #[test]
+    fn load_runs_in_a_given_env() {
+        let lua = new_lua(None).unwrap();
+
+        let x: i32 = lua
+            .load(r#"return load("return x", "chunk", "b", { x = 3 })()"#)
+            .eval()
+            .unwrap();
+
+        assert_eq!(x, 3);
+    }
+
+    #[test]
+    fn load_refuses_bytecode() {
+        let lua = new_lua(None).unwrap();
+
+        let loaded: Option<Value<'_>> = lua.load(r#"return load("\27Lua")"#).eval().unwrap();
+
+        assert!(loaded.is_none());
+    }
+
+    #[test]
+    fn require_only_loads_private_helpers() {
+        let lua = new_lua(None).unwrap();
+
+        assert!(lua.load(r#"require "lua_private.Helpers""#).exec().is_ok());
+        assert!(lua.load(r#"require "os""#).exec().is_err());
+        assert!(lua.load(r#"require "lua_private.../x""#).exec().is_err());
+    }
+
+    #[test]
+    fn stops_a_runaway_loop() {
+        let lua = new_lua(None).unwrap();
+        reset_instruction_budget(&lua);
+
+        let error = lua.load("while true do end").exec().unwrap_err();
+
+        assert!(hit_instruction_limit(&error.to_string()));
+    }
+}
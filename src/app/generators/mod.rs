@@ -1,10 +1,15 @@
 //pub mod drivetrain;
 pub mod keybinding;
-pub mod motors;
-pub mod servos;
 pub mod subsystem;
 
+pub mod autonomous;
+pub mod condition;
 pub mod control;
 pub mod generator;
+pub mod hardware;
 pub mod lua_generator;
+pub mod lua_sandbox;
+pub mod module_info;
+pub mod module_library;
+pub mod telemetry;
 pub mod ui_elements;
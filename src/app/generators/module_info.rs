@@ -0,0 +1,311 @@
+use crate::app::generators::lua_generator::LuaGenerator;
+use mlua::{Lua, Table};
+use std::path::Path;
+use strum_macros::EnumIter;
+
+/// Version of the Lua module API this build of FTCreate provides.
+/// Modules that declare a newer `api_version` in their manifest are refused.
+///
+/// 2: `generate_*` functions may return a list of lines, `generate_includes` a list of class
+///    names, and modules can add methods to the OpMode with `generate_methods`.
+/// 3: `keybindingComboBox` controls carry the gamepad they read, available as `.gamepad`.
+/// 4: `keybindingComboBox` takes a button behaviour, available as `.behaviour`, and
+///    `buttonLogic` generates the code for it.
+/// 5: `axisShaping` controls shape analog axes, and `axisCode` generates the code for them.
+/// 6: modules offer conditions with `get_conditions()`, and `conditionComboBox` controls
+///    pick one.
+/// 7: modules offer values for the telemetry designer with `get_telemetry()`.
+pub const LUA_API_VERSION: u32 = 7;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Deserialize,
+    serde::Serialize,
+    EnumIter,
+)]
+pub enum ModuleCategory {
+    Drivetrain,
+    Motors,
+    Servos,
+    Sensors,
+    Other,
+}
+
+/// Metadata a Lua module declares about itself, either from a `module_info()` function in the
+/// script or from a `<module>.json` file next to it. Modules without a manifest are listed under
+/// their file name in the "Other" category.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ModuleInfo {
+    #[serde(skip)]
+    pub script: String,
+    pub name: String,
+    pub description: String,
+    pub author: String,
+    pub version: String,
+    pub category: ModuleCategory,
+    pub api_version: u32,
+    pub icon: String,
+    /// Why this module can't be added to a robot, if it can't
+    #[serde(skip)]
+    pub problem: Option<String>,
+}
+
+impl Default for ModuleInfo {
+    fn default() -> Self {
+        Self {
+            script: "".into(),
+            name: "".into(),
+            description: "".into(),
+            author: "".into(),
+            version: "".into(),
+            category: ModuleCategory::Other,
+            api_version: LUA_API_VERSION,
+            icon: "".into(),
+            problem: None,
+        }
+    }
+}
+
+impl ModuleInfo {
+    /// Read the manifest of the module at `script`. Never fails; a module that can't be read
+    /// is returned with `problem` set.
+    pub fn load(script: &str) -> Self {
+        if let Some(info) = Self::read_sidecar(script) {
+            return info.finish(script);
+        }
+
+        let mut generator = LuaGenerator::new(script);
+
+        match generator.load() {
+            Ok(_) => Self::from_lua(&generator.lua, script),
+            Err(e) => Self {
+                problem: Some(e.summary()),
+                ..Default::default()
+            }
+            .finish(script),
+        }
+    }
+
+    /// Read the manifest of a script that's already been run in `lua`
+    pub fn from_lua(lua: &Lua, script: &str) -> Self {
+        let info = Self::read_sidecar(script).unwrap_or_else(|| {
+            match lua.globals().get::<_, mlua::Function<'_>>("module_info") {
+                Ok(module_info) => match module_info.call::<_, Table<'_>>(()) {
+                    Ok(table) => Self::from_table(&table),
+                    Err(e) => Self {
+                        problem: Some(format!("module_info: {}", e)),
+                        ..Default::default()
+                    },
+                },
+                Err(_) => Default::default(),
+            }
+        });
+
+        info.finish(script)
+    }
+
+    fn read_sidecar(script: &str) -> Option<Self> {
+        let sidecar = Path::new(script).with_extension("json");
+
+        if !sidecar.exists() {
+            return None;
+        }
+
+        Some(
+            std::fs::read_to_string(&sidecar)
+                .map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| Self {
+                    problem: Some(format!("{}: {}", sidecar.display(), e)),
+                    ..Default::default()
+                }),
+        )
+    }
+
+    fn finish(mut self, script: &str) -> Self {
+        self.script = script.to_string();
+
+        if self.name.is_empty() {
+            self.name = script_stem(script);
+        }
+
+        if self.problem.is_none() {
+            self.problem = self.incompatibility();
+        }
+
+        self
+    }
+
+    /// Read a manifest from the table returned by a script's `module_info()`
+    pub fn from_table(table: &Table<'_>) -> Self {
+        let string = |key: &str| {
+            table
+                .get::<_, Option<String>>(key)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        };
+
+        let category = string("category");
+
+        Self {
+            name: string("name"),
+            description: string("description"),
+            author: string("author"),
+            version: string("version"),
+            category: serde_json::from_value(serde_json::Value::String(category))
+                .unwrap_or(ModuleCategory::Other),
+            api_version: table
+                .get::<_, Option<u32>>("api_version")
+                .ok()
+                .flatten()
+                .unwrap_or(LUA_API_VERSION),
+            icon: string("icon"),
+            ..Default::default()
+        }
+    }
+
+    /// Explain why this module can't run on this build of FTCreate, if it can't
+    pub fn incompatibility(&self) -> Option<String> {
+        if self.api_version > LUA_API_VERSION {
+            Some(format!(
+                "{} needs FTCreate Lua API version {}, but this version of FTCreate provides version {}. Update FTCreate to use it.",
+                self.name, self.api_version, LUA_API_VERSION
+            ))
+        } else {
+            None
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        if self.icon.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} {}", self.icon, self.name)
+        }
+    }
+
+    /// Text shown when hovering the module in the picker
+    pub fn details(&self) -> String {
+        let mut details = self.description.clone();
+
+        if !self.author.is_empty() || !self.version.is_empty() {
+            details += &format!("\n\nVersion {} by {}", self.version, self.author);
+        }
+
+        if let Some(problem) = &self.problem {
+            details += &format!("\n\n{}", problem);
+        }
+
+        details.trim().to_string()
+    }
+
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+
+        filter.is_empty()
+            || self.name.to_lowercase().contains(&filter)
+            || self.description.to_lowercase().contains(&filter)
+            || self.author.to_lowercase().contains(&filter)
+    }
+}
+
+fn script_stem(script: &str) -> String {
+    Path::new(script)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| script.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `source` to `<name>.lua` in `dir`, returning its path
+    fn module(dir: &Path, name: &str, source: &str) -> String {
+        let path = dir.join(format!("{}.lua", name));
+        std::fs::write(&path, source).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn reads_module_info_from_the_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = module(
+            dir.path(),
+            "Intake",
+            r#"
+            function module_info()
+                return { name = "Intake", description = "Spins a roller", author = "Team 1",
+                         version = "1.2", category = "Motors", icon = "🌀" }
+            end
+            "#,
+        );
+
+        let info = ModuleInfo::load(&script);
+
+        assert_eq!(info.script, script);
+        assert_eq!(info.display_name(), "🌀 Intake");
+        assert_eq!(info.category, ModuleCategory::Motors);
+        assert_eq!(info.api_version, LUA_API_VERSION);
+        assert_eq!(info.details(), "Spins a roller\n\nVersion 1.2 by Team 1");
+        assert!(info.problem.is_none());
+        assert!(info.matches("roller"));
+    }
+
+    #[test]
+    fn lists_modules_without_module_info_under_their_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = module(dir.path(), "My_Motor", "controlsChanged = true");
+
+        let info = ModuleInfo::load(&script);
+
+        assert_eq!(info.name, "My_Motor");
+        assert_eq!(info.category, ModuleCategory::Other);
+        assert!(info.problem.is_none());
+    }
+
+    #[test]
+    fn prefers_a_json_manifest_next_to_the_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = module(
+            dir.path(),
+            "Lift",
+            r#"function module_info() return { name = "From Lua" } end"#,
+        );
+        std::fs::write(
+            dir.path().join("Lift.json"),
+            r#"{ "name": "From JSON", "category": "Servos" }"#,
+        )
+        .unwrap();
+
+        let info = ModuleInfo::load(&script);
+
+        assert_eq!(info.name, "From JSON");
+        assert_eq!(info.category, ModuleCategory::Servos);
+    }
+
+    #[test]
+    fn refuses_modules_for_a_newer_api() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = module(
+            dir.path(),
+            "Future",
+            &format!(
+                "function module_info() return {{ api_version = {} }} end",
+                LUA_API_VERSION + 1
+            ),
+        );
+
+        let info = ModuleInfo::load(&script);
+
+        assert!(info.problem.unwrap().contains("Update FTCreate"));
+    }
+}
@@ -0,0 +1,215 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Directory name given to the modules compiled into FTCreate. Built-in scripts are named
+/// `<built-in>/DC_Motor.lua` so they can be matched by file name like any other module.
+pub const BUILTIN_DIR: &str = "<built-in>";
+
+/// The modules shipped with FTCreate, available no matter where it's started from
+const BUILTIN_MODULES: &[(&str, &str)] = &[
+    (
+        "DC_Motor.lua",
+        include_str!("../../../lua_modules/DC_Motor.lua"),
+    ),
+    (
+        "Drivetrain.lua",
+        include_str!("../../../lua_modules/Drivetrain.lua"),
+    ),
+    (
+        "PIDF_Arm.lua",
+        include_str!("../../../lua_modules/PIDF_Arm.lua"),
+    ),
+    ("Servo.lua", include_str!("../../../lua_modules/Servo.lua")),
+    (
+        "Touch_Sensor.lua",
+        include_str!("../../../lua_modules/Touch_Sensor.lua"),
+    ),
+    (
+        "Limit_Switch.lua",
+        include_str!("../../../lua_modules/Limit_Switch.lua"),
+    ),
+    (
+        "Distance_Sensor.lua",
+        include_str!("../../../lua_modules/Distance_Sensor.lua"),
+    ),
+    (
+        "Color_Sensor.lua",
+        include_str!("../../../lua_modules/Color_Sensor.lua"),
+    ),
+];
+
+/// The helpers modules can `require` as `lua_private.<name>`
+const BUILTIN_HELPERS: &[(&str, &str)] =
+    &[("Helpers", include_str!("../../../lua_private/Helpers.lua"))];
+
+/// The per-user folder searched for modules, e.g. `~/.local/share/FTCreate/lua_modules`.
+/// Modules here override built-in modules with the same file name.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn user_modules_dir() -> Option<PathBuf> {
+    eframe::storage_dir("FTCreate").map(|dir| dir.join("lua_modules"))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn user_modules_dir() -> Option<PathBuf> {
+    None
+}
+
+/// List every available module: the built-in ones, then the user modules folder, then each of
+/// `extra_dirs`. A module overrides any earlier module with the same file name.
+///
+/// A missing user modules folder is ignored, but every folder in `extra_dirs` must be readable.
+pub fn find_modules(extra_dirs: &[PathBuf]) -> io::Result<Vec<String>> {
+    let mut scripts: Vec<String> = BUILTIN_MODULES
+        .iter()
+        .map(|(file_name, _)| format!("{}/{}", BUILTIN_DIR, file_name))
+        .collect();
+
+    if let Some(dir) = user_modules_dir().filter(|dir| dir.is_dir()) {
+        add_modules(&mut scripts, find_lua_scripts(&dir)?);
+    }
+
+    for dir in extra_dirs {
+        let found = find_lua_scripts(dir)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", dir.display(), e)))?;
+        add_modules(&mut scripts, found);
+    }
+
+    scripts.sort_by_key(|script| file_name(script));
+    Ok(scripts)
+}
+
+fn add_modules(scripts: &mut Vec<String>, overrides: Vec<String>) {
+    for script in overrides {
+        scripts.retain(|s| file_name(s) != file_name(&script));
+        scripts.push(script);
+    }
+}
+
+/// List the Lua modules in `dir`, sorted so they're always offered in the same order
+fn find_lua_scripts(dir: &Path) -> io::Result<Vec<String>> {
+    let mut scripts = vec![];
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "lua") {
+            scripts.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    scripts.sort();
+    Ok(scripts)
+}
+
+/// Read the source of a module listed by [`find_modules`]
+pub fn read_module(script: &str) -> io::Result<String> {
+    match builtin_module(script) {
+        Some(source) => Ok(source.to_string()),
+        None => std::fs::read_to_string(script),
+    }
+}
+
+fn builtin_module(script: &str) -> Option<&'static str> {
+    let file_name = script.strip_prefix(BUILTIN_DIR)?.strip_prefix('/')?;
+
+    BUILTIN_MODULES
+        .iter()
+        .find(|(name, _)| *name == file_name)
+        .map(|(_, source)| *source)
+}
+
+pub fn is_builtin(script: &str) -> bool {
+    builtin_module(script).is_some()
+}
+
+/// The folder whose `lua_private` directory a module's helpers are loaded from, i.e. the
+/// parent of the folder the module is in. Built-in modules only use the built-in helpers.
+pub fn module_root(script: &str) -> Option<PathBuf> {
+    if is_builtin(script) {
+        return None;
+    }
+
+    match Path::new(script).parent().and_then(Path::parent) {
+        Some(root) if !root.as_os_str().is_empty() => Some(root.to_path_buf()),
+        _ => Some(PathBuf::from(".")),
+    }
+}
+
+/// The built-in copy of `lua_private.<name>`
+pub fn builtin_helper(name: &str) -> Option<&'static str> {
+    BUILTIN_HELPERS
+        .iter()
+        .find(|(helper, _)| *helper == name)
+        .map(|(_, source)| *source)
+}
+
+/// Find the module a saved component was created from among the available modules. Projects
+/// and saved app state store the path the module had at the time, so a component is matched
+/// up by file name when that path is no longer offered (another computer, another working
+/// directory, or a built-in module overridden by a user module).
+pub fn resolve_script(script: &str, scripts: &[String]) -> String {
+    if scripts.iter().any(|s| s == script) {
+        return script.to_string();
+    }
+
+    let file_name = file_name(script);
+
+    scripts
+        .iter()
+        .find(|s| self::file_name(s) == file_name)
+        .cloned()
+        .unwrap_or_else(|| script.to_string())
+}
+
+fn file_name(script: &str) -> String {
+    Path::new(script)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| script.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offers_every_built_in_module() {
+        let scripts = find_modules(&[]).unwrap();
+
+        for (file_name, source) in BUILTIN_MODULES {
+            let script = format!("{}/{}", BUILTIN_DIR, file_name);
+            assert!(scripts.contains(&script));
+            assert_eq!(read_module(&script).unwrap(), *source);
+            assert_eq!(module_root(&script), None);
+        }
+    }
+
+    #[test]
+    fn user_modules_override_built_in_modules_by_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let user_module = dir.path().join("DC_Motor.lua");
+        std::fs::write(&user_module, "-- my own DC motor").unwrap();
+        let user_module = user_module.to_string_lossy().to_string();
+
+        let scripts = find_modules(&[dir.path().to_path_buf()]).unwrap();
+
+        assert!(scripts.contains(&user_module));
+        assert!(!scripts
+            .iter()
+            .any(|script| is_builtin(script) && file_name(script) == "DC_Motor.lua"));
+        assert_eq!(read_module(&user_module).unwrap(), "-- my own DC motor");
+        // projects saved against the built-in module pick up the override
+        assert_eq!(
+            resolve_script("<built-in>/DC_Motor.lua", &scripts),
+            user_module
+        );
+        assert_eq!(
+            resolve_script("./lua_modules/DC_Motor.lua", &scripts),
+            user_module
+        );
+    }
+
+    #[test]
+    fn fails_on_a_missing_module_folder() {
+        assert!(find_modules(&[PathBuf::from("/nonexistent/lua_modules")]).is_err());
+    }
+}
@@ -1,2 +0,0 @@
-pub mod dc_motor;
-pub mod motor;
@@ -1,2 +0,0 @@
-pub mod rev_servo;
-pub mod servo;
@@ -1,21 +1,21 @@
 use crate::app::generators::generator::{self, SubsystemGenerator};
 use crate::app::generators::lua_generator::{ControlHandler, LuaGenerator};
-use strum_macros::EnumIter;
-
-#[derive(
-    Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, EnumIter, PartialOrd, Copy,
-)]
-pub enum DrivetrainType {
-    Mecanum,
-    Arcade,
-    Tank,
-}
+use crate::app::generators::module_info::ModuleCategory;
+use crate::app::java::block::Block;
+use crate::app::java::opmode::OpMode;
+use strum::IntoEnumIterator;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Subsystem {
     pub name: String,
     pub control_handler: ControlHandler,
     pub selected_control: String,
+    #[serde(skip)]
+    module_filter: String,
+    #[serde(skip)]
+    category_filter: Option<ModuleCategory>,
+    #[serde(skip)]
+    add_error: Option<String>,
 }
 
 impl generator::Generator for Subsystem {
@@ -62,31 +62,113 @@ impl generator::Generator for Subsystem {
         code
     }
 
+    fn generate_methods(&self) -> String {
+        self.control_handler.generate_methods()
+    }
+
+    fn generate(&self, opmode: &mut OpMode) {
+        self.control_handler.generate_into(opmode);
+        opmode
+            .loop_body
+            .section(Block::new().user_code(&Subsystem::loop_region(&self.name)))
+            .blank();
+    }
+
     fn render_options(&mut self, ui: &mut egui::Ui, _id: usize) {
         egui::scroll_area::ScrollArea::vertical()
             .auto_shrink([false; 2])
             .show(ui, |ui| {
                 ui.add_space(20.0);
 
+                ui.horizontal(|ui| {
+                    ui.label("Search");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.module_filter).desired_width(120.0),
+                    );
+
+                    egui::ComboBox::new(format!("{}.{}", self.name, "Category"), "Category")
+                        .selected_text(match self.category_filter {
+                            Some(category) => format!("{:?}", category),
+                            None => "All".to_string(),
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.category_filter, None, "All");
+                            for category in ModuleCategory::iter() {
+                                ui.selectable_value(
+                                    &mut self.category_filter,
+                                    Some(category),
+                                    format!("{:?}", category),
+                                );
+                            }
+                        });
+                });
+
+                let modules = self.control_handler.modules();
+                let selected = modules
+                    .iter()
+                    .find(|module| module.script == self.selected_control)
+                    .map(|module| (*module).clone());
+
                 egui::ComboBox::new(format!("{}.{}", self.name, "Controls"), "Controls List")
-                    .selected_text(format!("{}", &mut self.selected_control))
+                    .selected_text(selected.as_ref().map_or("".into(), |m| m.display_name()))
                     .width(170.0)
                     .show_ui(ui, |ui| {
-                        for script in &self.control_handler.scripts {
-                            let script_name =
-                                script.split("/").last().unwrap().split(".").nth(0).unwrap();
-                            ui.selectable_value(
-                                &mut self.selected_control,
-                                script.clone(),
-                                format!("{:?}", script_name),
-                            );
+                        for category in ModuleCategory::iter() {
+                            if self.category_filter.is_some_and(|c| c != category) {
+                                continue;
+                            }
+
+                            let entries = modules
+                                .iter()
+                                .filter(|module| {
+                                    module.category == category
+                                        && module.matches(&self.module_filter)
+                                })
+                                .collect::<Vec<_>>();
+
+                            if entries.is_empty() {
+                                continue;
+                            }
+
+                            ui.label(egui::RichText::new(format!("{:?}", category)).strong());
+
+                            for module in entries {
+                                let details = module.details();
+                                ui.add_enabled_ui(module.problem.is_none(), |ui| {
+                                    ui.selectable_value(
+                                        &mut self.selected_control,
+                                        module.script.clone(),
+                                        module.display_name(),
+                                    )
+                                })
+                                .response
+                                .on_hover_text(&details)
+                                .on_disabled_hover_text(&details);
+                            }
+
+                            ui.separator();
                         }
                     });
 
+                if let Some(module) = &selected {
+                    if !module.description.is_empty() {
+                        ui.label(&module.description);
+                    }
+                }
+
                 if ui.button("Add selected control").clicked() {
-                    let mut new_generator = LuaGenerator::new(&self.selected_control);
-                    new_generator.load();
-                    self.control_handler.generators.push(new_generator);
+                    match selected.and_then(|module| module.problem) {
+                        Some(problem) => self.add_error = Some(problem),
+                        None => {
+                            self.add_error = None;
+                            let new_generator = LuaGenerator::new(&self.selected_control);
+                            self.control_handler.generators.push(new_generator);
+                        }
+                    }
+                }
+
+                if let Some(error) = &self.add_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
                 }
 
                 self.control_handler.render(ui);
@@ -106,11 +188,16 @@ impl Subsystem {
     pub fn new(name: String) -> Self {
         Subsystem {
             name: name.to_string(),
-            control_handler: ControlHandler {
-                scripts: vec![],
-                generators: vec![],
-            },
+            control_handler: ControlHandler::default(),
             selected_control: "".to_string(),
+            module_filter: "".to_string(),
+            category_filter: None,
+            add_error: None,
         }
     }
+
+    /// The user code region in the TeleOp loop for the subsystem called `name`
+    pub fn loop_region(name: &str) -> String {
+        format!("loop {}", name)
+    }
 }
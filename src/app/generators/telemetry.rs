@@ -0,0 +1,475 @@
+use crate::app::generators::autonomous::routine::Component;
+use crate::app::generators::keybinding::registry::{self, Input, KeybindingRegistry};
+use crate::app::java::opmode::OpMode;
+use mlua::Table;
+
+/// Id of the built-in item listing the gamepad inputs being used
+const ACTIVE_CONTROLS: &str = "active_controls";
+
+/// A value that can be shown on the Driver Station, as offered by a Lua module's optional
+/// `get_telemetry()`:
+///
+/// ```lua
+/// function get_telemetry()
+///     return {
+///         { id = "power", java = "armMotor.getPower()", caption = "Arm power", format = "%.2f" },
+///     }
+/// end
+/// ```
+///
+/// `java` is a Java expression that can be read at the end of the loop. `format` is a Java
+/// format string for it and can be left out. `id` tells the component's values apart and
+/// defaults to the value's place in the list; it should stay the same when the component's
+/// device is renamed, so the lines showing it are kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryItem {
+    /// Unique among every item. Those offered by components are `<subsystem>/<component>/<id>`.
+    pub id: String,
+    pub java: String,
+    pub caption: String,
+    pub format: String,
+    /// Where the value comes from, e.g. "Arm › armMotor"
+    pub source: String,
+}
+
+impl TelemetryItem {
+    pub fn from_table(table: &Table<'_>) -> mlua::Result<Self> {
+        let java: String = table.get("java")?;
+
+        Ok(Self {
+            id: table.get::<_, Option<String>>("id")?.unwrap_or_default(),
+            caption: table
+                .get::<_, Option<String>>("caption")?
+                .unwrap_or_else(|| java.clone()),
+            format: table
+                .get::<_, Option<String>>("format")?
+                .unwrap_or_default(),
+            source: "".into(),
+            java,
+        })
+    }
+
+    /// Every value the robot can show: the run time, the gamepad inputs being used and what
+    /// each component offers
+    pub fn collect(components: &[Component<'_>], keybindings: &KeybindingRegistry) -> Vec<Self> {
+        let mut items = vec![
+            Self {
+                id: "runtime".into(),
+                java: "runtime.seconds()".into(),
+                caption: "Run time".into(),
+                format: "%.1f s".into(),
+                source: "OpMode".into(),
+            },
+            Self {
+                id: ACTIVE_CONTROLS.into(),
+                java: active_controls(keybindings),
+                caption: "Controls".into(),
+                format: "".into(),
+                source: "Keybindings".into(),
+            },
+        ];
+
+        for component in components {
+            let source = format!(
+                "{} › {}",
+                component.subsystem,
+                registry::component_name(component)
+            );
+
+            items.extend(component.generator.telemetry().into_iter().enumerate().map(
+                |(i, item)| {
+                    let id = match item.id.is_empty() {
+                        true => (i + 1).to_string(),
+                        false => item.id,
+                    };
+                    Self {
+                        id: component_id(component.subsystem, component.index, &id),
+                        source: source.clone(),
+                        ..item
+                    }
+                },
+            ));
+        }
+
+        items
+    }
+}
+
+/// A Java string naming what every bound input being pressed is for, e.g.
+/// `"armMotor: Raise; "` while `a` is held
+fn active_controls(keybindings: &KeybindingRegistry) -> String {
+    let parts: Vec<String> = keybindings
+        .bindings()
+        .iter()
+        .map(|binding| {
+            let gamepad = format!("{:?}", binding.input.gamepad);
+            let mut condition = match binding.input.input {
+                Input::Button(button) => format!("{}.{:?}", gamepad, button),
+                Input::Axis(axis) => format!("Math.abs({}.{:?}) > 0.1", gamepad, axis),
+            };
+            for modifier in &binding.modifiers {
+                condition += &format!(" && {}.{}", gamepad, modifier);
+            }
+
+            format!(
+                "({} ? {} : \"\")",
+                condition,
+                java_string(&format!(
+                    "{}: {}; ",
+                    binding.component_name, binding.description
+                ))
+            )
+        })
+        .collect();
+
+    if parts.is_empty() {
+        java_string("none bound")
+    } else {
+        parts.join(" + ")
+    }
+}
+
+/// Id of the value a component offers as `id`, which tells it apart from every other value
+fn component_id(subsystem: &str, component: usize, id: &str) -> String {
+    format!("{}/{}/{}", subsystem, component, id)
+}
+
+/// The component and the component's own id of a value offered by `subsystem`, if `id` is one
+fn component_value<'a>(id: &'a str, subsystem: &str) -> Option<(usize, &'a str)> {
+    let (index, id) = id
+        .strip_prefix(subsystem)?
+        .strip_prefix('/')?
+        .split_once('/')?;
+
+    Some((index.parse().ok()?, id))
+}
+
+fn java_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A value shown on the Driver Station, with the caption and format the team picked
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TelemetryLine {
+    /// [`TelemetryItem::id`] of the value shown
+    pub id: String,
+    pub caption: String,
+    pub format: String,
+}
+
+/// What the TeleOp shows on the Driver Station every loop, in order
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Telemetry {
+    pub lines: Vec<TelemetryLine>,
+}
+
+impl Telemetry {
+    /// Keep showing the values of components after the one at `component` in `subsystem` is
+    /// removed, and stop showing its own
+    pub fn remove_component(&mut self, subsystem: &str, component: usize) {
+        self.lines
+            .retain(|line| match component_value(&line.id, subsystem) {
+                Some((index, _)) => index != component,
+                None => true,
+            });
+
+        for line in &mut self.lines {
+            if let Some((index, id)) = component_value(&line.id, subsystem) {
+                if index > component {
+                    line.id = component_id(subsystem, index - 1, id);
+                }
+            }
+        }
+    }
+
+    /// Keep showing the values of a subsystem when it's renamed
+    pub fn rename_subsystem(&mut self, old: &str, new: &str) {
+        for line in &mut self.lines {
+            if let Some((index, id)) = component_value(&line.id, old) {
+                line.id = component_id(new, index, id);
+            }
+        }
+    }
+
+    /// Stop showing the values of a deleted subsystem, so they don't carry over to a new one
+    /// given the same name
+    pub fn remove_subsystem(&mut self, subsystem: &str) {
+        self.lines
+            .retain(|line| component_value(&line.id, subsystem).is_none());
+    }
+
+    /// Add a `telemetry.addData` line to `opmode` for each line whose value is still offered
+    /// by `items`
+    pub fn generate(&self, opmode: &mut OpMode, items: &[TelemetryItem]) {
+        let shown = self.lines.iter().filter_map(|line| {
+            items
+                .iter()
+                .find(|item| item.id == line.id)
+                .map(|item| (line, item))
+        });
+
+        for (line, item) in shown {
+            let caption = java_string(&line.caption);
+            opmode.telemetry.line(if line.format.trim().is_empty() {
+                format!("telemetry.addData({}, {});", caption, item.java)
+            } else {
+                format!(
+                    "telemetry.addData({}, {}, {});",
+                    caption,
+                    java_string(&line.format),
+                    item.java
+                )
+            });
+        }
+    }
+
+    /// Edit the lines shown, and add any of `items` not shown yet
+    pub fn render(&mut self, ui: &mut egui::Ui, items: &[TelemetryItem]) {
+        ui.label(r#"Shown under "Status: Initialized" while the TeleOp runs"#);
+
+        let mut moved: Option<(usize, usize)> = None;
+        let mut removed: Option<usize> = None;
+        let count = self.lines.len();
+
+        egui::Grid::new("telemetry_lines")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, line) in self.lines.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                            moved = Some((i, i - 1));
+                        }
+                        if ui
+                            .add_enabled(i + 1 < count, egui::Button::new("⬇"))
+                            .clicked()
+                        {
+                            moved = Some((i, i + 1));
+                        }
+                    });
+
+                    ui.add(egui::TextEdit::singleline(&mut line.caption).desired_width(140.0));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut line.format)
+                            .desired_width(60.0)
+                            .hint_text("as is"),
+                    )
+                    .on_hover_text(
+                        "Java format string, e.g. %.2f for two decimal places or %d for whole numbers. Leave empty to show the value as it is.",
+                    );
+
+                    match items.iter().find(|item| item.id == line.id) {
+                        Some(item) => ui.weak(&item.source).on_hover_text(&item.java),
+                        None => ui
+                            .colored_label(ui.visuals().warn_fg_color, "⚠ missing")
+                            .on_hover_text(format!(
+                                "Nothing on the robot offers {} any more, so it isn't shown",
+                                line.id
+                            )),
+                    };
+
+                    if ui.button("✖").on_hover_text("Stop showing").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some((from, to)) = moved {
+            self.lines.swap(from, to);
+        }
+        if let Some(i) = removed {
+            self.lines.remove(i);
+        }
+
+        let unused: Vec<&TelemetryItem> = items
+            .iter()
+            .filter(|item| self.lines.iter().all(|line| line.id != item.id))
+            .collect();
+
+        egui::ComboBox::from_id_source("telemetry_add")
+            .selected_text("Add value...")
+            .width(260.0)
+            .show_ui(ui, |ui| {
+                if unused.is_empty() {
+                    ui.weak("Everything is shown");
+                }
+                for item in unused {
+                    if ui
+                        .selectable_label(false, format!("{}: {}", item.source, item.caption))
+                        .clicked()
+                    {
+                        self.lines.push(TelemetryLine {
+                            id: item.id.clone(),
+                            caption: item.caption.clone(),
+                            format: item.format.clone(),
+                        });
+                    }
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::generators::lua_generator::LuaGenerator;
+
+    /// A module offering a motor's power, with an id, and its position, without one
+    const MOTOR: &str = r#"
+        name = "armMotor"
+
+        function get_telemetry()
+            return {
+                { id = "power", java = name .. ".getPower()", caption = name .. " power", format = "%.2f" },
+                { java = name .. ".getCurrentPosition()" },
+            }
+        end
+    "#;
+
+    fn line(id: &str, caption: &str, format: &str) -> TelemetryLine {
+        TelemetryLine {
+            id: id.into(),
+            caption: caption.into(),
+            format: format.into(),
+        }
+    }
+
+    fn telemetry() -> Telemetry {
+        Telemetry {
+            lines: vec![
+                line("runtime", "Run time", "%.1f s"),
+                line("Arm/0/power", "Arm power", ""),
+                line("Arm/1/power", "Wrist power", ""),
+                line("Intake/1/power", "Intake power", ""),
+            ],
+        }
+    }
+
+    fn ids(telemetry: &Telemetry) -> Vec<&str> {
+        telemetry
+            .lines
+            .iter()
+            .map(|line| line.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn reads_values_from_get_telemetry() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("Motor.lua");
+        std::fs::write(&script, MOTOR).unwrap();
+        let mut generator = LuaGenerator::new(script.to_str().unwrap());
+        generator.load().unwrap();
+
+        let items = generator.telemetry();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id, "power");
+        assert_eq!(items[0].java, "armMotor.getPower()");
+        assert_eq!(items[0].caption, "armMotor power");
+        assert_eq!(items[0].format, "%.2f");
+        // the caption and format are optional
+        assert_eq!(items[1].id, "");
+        assert_eq!(items[1].caption, "armMotor.getCurrentPosition()");
+        assert_eq!(items[1].format, "");
+    }
+
+    #[test]
+    fn keeps_ids_when_the_device_is_renamed() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("Motor.lua");
+        std::fs::write(&script, MOTOR).unwrap();
+        let mut generator = LuaGenerator::new(script.to_str().unwrap());
+        generator.load().unwrap();
+        let components = |generator| {
+            vec![Component {
+                subsystem: "Arm",
+                index: 1,
+                generator,
+            }]
+        };
+
+        let items = TelemetryItem::collect(&components(&generator), &Default::default());
+        generator.lua.globals().set("name", "liftMotor").unwrap();
+        let renamed = TelemetryItem::collect(&components(&generator), &Default::default());
+
+        let ids = |items: &[TelemetryItem]| -> Vec<String> {
+            items.iter().map(|item| item.id.clone()).collect()
+        };
+        assert_eq!(
+            ids(&items),
+            ["runtime", ACTIVE_CONTROLS, "Arm/1/power", "Arm/1/2"]
+        );
+        assert_eq!(ids(&renamed), ids(&items));
+        assert_eq!(renamed[2].java, "liftMotor.getPower()");
+    }
+
+    #[test]
+    fn shows_each_line_still_offered_in_the_teleop() {
+        let items = vec![
+            TelemetryItem {
+                id: "Arm/0/power".into(),
+                java: "armMotor.getPower()".into(),
+                caption: "armMotor power".into(),
+                format: "%.2f".into(),
+                source: "Arm › armMotor".into(),
+            },
+            TelemetryItem {
+                id: "runtime".into(),
+                java: "runtime.seconds()".into(),
+                caption: "Run time".into(),
+                format: "%.1f s".into(),
+                source: "OpMode".into(),
+            },
+        ];
+        let telemetry = Telemetry {
+            lines: vec![
+                line("Arm/0/power", "Arm \"power\"", "%.2f"),
+                line("Arm/0/position", "Arm position", "%d"),
+                line("runtime", "Run time", " "),
+            ],
+        };
+        let mut opmode = OpMode::new("MyBot");
+
+        telemetry.generate(&mut opmode, &items);
+
+        let code = opmode.render();
+        assert!(code.contains(
+            "            telemetry.addData(\"Arm \\\"power\\\"\", \"%.2f\", armMotor.getPower());\n\
+             \x20           telemetry.addData(\"Run time\", runtime.seconds());\n"
+        ));
+        assert!(!code.contains("Arm position"));
+    }
+
+    #[test]
+    fn moves_lines_along_when_a_component_is_removed() {
+        let mut telemetry = telemetry();
+        telemetry.remove_component("Arm", 0);
+
+        assert_eq!(
+            ids(&telemetry),
+            ["runtime", "Arm/0/power", "Intake/1/power"]
+        );
+    }
+
+    #[test]
+    fn follows_a_renamed_subsystem() {
+        let mut telemetry = telemetry();
+        telemetry.rename_subsystem("Arm", "Lift");
+
+        assert_eq!(
+            ids(&telemetry),
+            ["runtime", "Lift/0/power", "Lift/1/power", "Intake/1/power"]
+        );
+    }
+
+    #[test]
+    fn forgets_lines_of_a_deleted_subsystem() {
+        let mut telemetry = telemetry();
+        telemetry.remove_subsystem("Arm");
+
+        assert_eq!(ids(&telemetry), ["runtime", "Intake/1/power"]);
+    }
+}
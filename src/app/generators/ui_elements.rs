@@ -1,5 +1,10 @@
+use crate::app::generators::condition::Condition;
 use crate::app::generators::control::UiElement;
-use crate::app::generators::keybinding::keybinding::Keybinding;
+use crate::app::generators::keybinding::behaviour::ButtonBehaviour;
+use crate::app::generators::keybinding::chord::Modifier;
+use crate::app::generators::keybinding::keybinding::{Gamepad, Keybinding};
+use crate::app::generators::keybinding::shaping::AxisShaping;
+use crate::app::generators::lua_sandbox;
 use egui::Ui;
 use mlua::{Function, Lua};
 use std::ops::RangeInclusive;
@@ -30,6 +35,35 @@ pub struct ComboBoxInput {
     pub label: String,
     pub entries: Vec<String>,
     pub id: i32,
+    /// Set for keybinding combo boxes, which also pick the gamepad the binding reads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gamepad: Option<Gamepad>,
+    /// Set for keybinding combo boxes whose module handles button behaviours
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub behaviour: Option<ButtonBehaviour>,
+    /// The behaviours the module handles for this binding, or all of them if it's empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub behaviours: Vec<ButtonBehaviour>,
+    /// Inputs that have to be held as well, for keybinding combo boxes with a `behaviour`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<Modifier>,
+}
+
+/// Settings for shaping an analog axis, collapsed under its label
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct AxisShapingInput {
+    pub name: String,
+    pub label: String,
+    pub shaping: AxisShaping,
+}
+
+/// Picks one of the conditions other components offer, such as a limit switch being pressed.
+/// The value is the condition's Java, or `none`.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ConditionInput {
+    pub name: String,
+    pub label: String,
+    pub value: String,
 }
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -43,6 +77,9 @@ pub struct CheckboxInput {
 pub struct ButtonInput {
     pub name: String,
     pub callback: String,
+    /// Error raised by the last click, shown under the button
+    #[serde(skip)]
+    pub error: Option<String>,
 }
 
 impl UiElement for Slider {
@@ -64,29 +101,90 @@ impl UiElement for TextInput {
 
 impl UiElement for ComboBoxInput {
     fn render(&mut self, ui: &mut Ui, _lua: Option<&Lua>) {
-        egui::ComboBox::new(
-            format!("{}{}{}", &self.name, &self.label, &self.id),
-            &self.label,
-        )
-        .selected_text(format!("{:?}", &mut self.value))
-        .width(170.0)
-        .show_ui(ui, |ui| {
-            for entry in &self.entries {
-                ui.selectable_value(&mut self.value, entry.to_string(), format!("{:?}", entry));
+        let id = format!("{}{}{}", &self.name, &self.label, &self.id);
+
+        ui.horizontal(|ui| {
+            if let Some(gamepad) = &mut self.gamepad {
+                gamepad.render(ui, format!("{}gamepad", id));
+            }
+
+            egui::ComboBox::new(&id, &self.label)
+                .selected_text(format!("{:?}", &mut self.value))
+                .width(if self.gamepad.is_some() { 110.0 } else { 170.0 })
+                .show_ui(ui, |ui| {
+                    for entry in &self.entries {
+                        ui.selectable_value(
+                            &mut self.value,
+                            entry.to_string(),
+                            format!("{:?}", entry),
+                        );
+                    }
+                });
+
+            if let Some(behaviour) = &mut self.behaviour {
+                behaviour.render(ui, format!("{}behaviour", id), &self.behaviours);
             }
         });
+
+        if self.behaviour.is_some() {
+            ui.indent(format!("{}modifiers", id), |ui| {
+                Modifier::render_list(&mut self.modifiers, ui, &id);
+            });
+        }
+    }
+}
+
+impl UiElement for AxisShapingInput {
+    fn render(&mut self, ui: &mut Ui, _lua: Option<&Lua>) {
+        egui::CollapsingHeader::new(&self.label)
+            .id_source(&self.name)
+            .show(ui, |ui| self.shaping.render(ui));
+    }
+}
+
+impl UiElement for ConditionInput {
+    fn render(&mut self, ui: &mut Ui, lua: Option<&Lua>) {
+        let conditions = lua.map(Condition::from_global).unwrap_or_default();
+
+        let selected = match conditions.iter().find(|c| c.java == self.value) {
+            Some(condition) => condition.description.clone(),
+            None if self.value == "none" => "none".to_string(),
+            // the component offering it was removed or changed
+            None => format!("⚠ {} (missing)", self.value),
+        };
+
+        egui::ComboBox::from_id_source(&self.name)
+            .selected_text(selected)
+            .width(170.0)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.value, "none".to_string(), "none");
+                for condition in &conditions {
+                    ui.selectable_value(
+                        &mut self.value,
+                        condition.java.clone(),
+                        &condition.description,
+                    );
+                }
+            });
+        ui.label(&self.label);
     }
 }
 
 impl UiElement for ButtonInput {
     fn render(&mut self, ui: &mut Ui, lua: Option<&Lua>) {
         if ui.button(&self.name).clicked() {
-            let callback: Function<'_> = lua
-                .unwrap()
-                .globals()
-                .get(self.callback.to_owned())
-                .expect("Button callback not found");
-            callback.call::<_, ()>(()).unwrap();
+            self.error = lua.and_then(|lua| {
+                lua_sandbox::reset_instruction_budget(lua);
+                lua.globals()
+                    .get::<_, Function<'_>>(self.callback.to_owned())
+                    .map_err(|_| format!("Button callback '{}' not found", self.callback))
+                    .and_then(|callback| callback.call::<_, ()>(()).map_err(|e| e.to_string()))
+                    .err()
+            });
+        }
+
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
         }
     }
 }
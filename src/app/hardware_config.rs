@@ -0,0 +1,536 @@
+use crate::app::generators::hardware::HardwareRole;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Address of the Robot Controller's web server, the one ftc_http uploads code to
+pub const ROBOT_CONTROLLER_ADDRESS: &str = "192.168.43.1:8080";
+
+/// Where the Robot Controller serves the XML of its active hardware configuration
+pub const ACTIVE_CONFIG_PATH: &str = "/js/activeConfiguration.xml";
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The devices in the Robot Controller's active hardware configuration. OpModes can only find
+/// devices in the hardware map by these names.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct HardwareConfig {
+    pub devices: Vec<ConfiguredDevice>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ConfiguredDevice {
+    pub name: String,
+    /// The type picked in the configuration, e.g. `goBILDA5202SeriesMotor`
+    pub device_type: String,
+    pub kind: DeviceKind,
+    /// The hub it's plugged into, e.g. "Control Hub"
+    pub hub: String,
+    pub port: Option<u32>,
+    /// The I2C bus of an I2C sensor
+    pub bus: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum DeviceKind {
+    Motor,
+    Servo,
+    Sensor,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    /// The Robot Controller answered with something other than `200 OK`
+    Status(String),
+    Format(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "{}", e),
+            ConfigError::Status(status) => {
+                write!(f, "The Robot Controller answered \"{}\"", status)
+            }
+            ConfigError::Format(e) => write!(f, "Not a robot configuration: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl DeviceKind {
+    /// The kind of configured device a component's hardware has to be, if it matters
+    pub fn for_role(role: HardwareRole) -> Option<Self> {
+        match role {
+            HardwareRole::Motor | HardwareRole::DriveMotor => Some(DeviceKind::Motor),
+            HardwareRole::Servo => Some(DeviceKind::Servo),
+            HardwareRole::Sensor => Some(DeviceKind::Sensor),
+            HardwareRole::Other => None,
+        }
+    }
+
+    fn from_type(device_type: &str) -> Self {
+        let device_type = device_type.to_lowercase();
+        if device_type.contains("motor") {
+            DeviceKind::Motor
+        } else if device_type.contains("servo") {
+            DeviceKind::Servo
+        } else {
+            DeviceKind::Sensor
+        }
+    }
+}
+
+impl ConfiguredDevice {
+    /// Where the device is plugged in, e.g. "Control Hub motor port 2"
+    pub fn location(&self) -> String {
+        match (self.kind, self.port, self.bus) {
+            (_, _, Some(bus)) => format!("{} I2C bus {}", self.hub, bus),
+            (DeviceKind::Motor, Some(port), _) => format!("{} motor port {}", self.hub, port),
+            (DeviceKind::Servo, Some(port), _) => format!("{} servo port {}", self.hub, port),
+            (DeviceKind::Sensor, Some(port), _) => format!("{} port {}", self.hub, port),
+            (_, None, None) => self.hub.clone(),
+        }
+    }
+}
+
+impl HardwareConfig {
+    /// Read a configuration as the Robot Controller saves it:
+    ///
+    /// ```xml
+    /// <Robot type="FirstInspires-FTC">
+    ///     <LynxUsbDevice name="Control Hub Portal" serialNumber="(embedded)" parentModuleAddress="173">
+    ///         <LynxModule name="Control Hub" port="173">
+    ///             <goBILDA5202SeriesMotor name="leftDrive" port="0" />
+    ///             <Servo name="claw" port="0" />
+    ///             <RevColorSensorV3 name="color" port="0" bus="1" />
+    ///         </LynxModule>
+    ///     </LynxUsbDevice>
+    ///     <Webcam name="Webcam 1" serialNumber="..." />
+    /// </Robot>
+    /// ```
+    ///
+    /// Every element without children is a device; its hub is the closest named element
+    /// around it.
+    pub fn parse(xml: &str) -> Result<Self, ConfigError> {
+        let mut devices = vec![];
+        // the names of the elements the parser is inside
+        let mut open: Vec<Option<String>> = vec![];
+        let mut found_robot = false;
+        let mut rest = xml;
+
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start..];
+
+            // declarations, comments and the like hold no devices
+            if rest.starts_with("<?") || rest.starts_with("<!") {
+                let end = match rest.starts_with("<!--") {
+                    true => rest.find("-->").map(|i| i + 3),
+                    false => rest.find('>').map(|i| i + 1),
+                };
+                rest = &rest[end.unwrap_or(rest.len())..];
+                continue;
+            }
+
+            let end = rest
+                .find('>')
+                .ok_or_else(|| ConfigError::Format("an element isn't closed".into()))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            if tag.starts_with('/') {
+                open.pop();
+                continue;
+            }
+
+            let self_closing = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let element = tag.split_whitespace().next().unwrap_or_default();
+            let name = attribute(tag, "name");
+
+            if element == "Robot" {
+                found_robot = true;
+            }
+
+            if !self_closing {
+                open.push(name);
+                continue;
+            }
+
+            if let Some(name) = name {
+                devices.push(ConfiguredDevice {
+                    name,
+                    device_type: element.to_string(),
+                    kind: DeviceKind::from_type(element),
+                    hub: open
+                        .iter()
+                        .rev()
+                        .flatten()
+                        .next()
+                        .cloned()
+                        .unwrap_or_default(),
+                    port: attribute(tag, "port").and_then(|port| port.parse().ok()),
+                    bus: attribute(tag, "bus").and_then(|bus| bus.parse().ok()),
+                });
+            }
+        }
+
+        if !found_robot {
+            return Err(ConfigError::Format("no <Robot> element".into()));
+        }
+        Ok(Self { devices })
+    }
+
+    pub fn device(&self, name: &str) -> Option<&ConfiguredDevice> {
+        self.devices.iter().find(|device| device.name == name)
+    }
+
+    /// The devices of `kind`, or all of them for no kind
+    pub fn devices_of(&self, kind: Option<DeviceKind>) -> impl Iterator<Item = &ConfiguredDevice> {
+        self.devices
+            .iter()
+            .filter(move |device| kind.is_none_or(|kind| device.kind == kind))
+    }
+
+    /// Pick the name of a device for hardware used as `role`, and warn if `name` isn't in the
+    /// configuration or is a different kind of device
+    pub fn render_name_picker(&self, ui: &mut egui::Ui, role: HardwareRole, name: &mut String) {
+        let kind = DeviceKind::for_role(role);
+
+        egui::ComboBox::from_id_source("configured device name")
+            .selected_text("From robot")
+            .width(170.0)
+            .show_ui(ui, |ui| {
+                for device in self.devices_of(kind) {
+                    let label = format!("{} ({})", device.name, device.location());
+                    if ui.selectable_label(device.name == *name, label).clicked() {
+                        *name = device.name.clone();
+                    }
+                }
+            });
+
+        match self.device(name) {
+            None => {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "⚠ Not in the robot's configuration",
+                );
+            }
+            Some(device) if kind.is_some_and(|kind| kind != device.kind) => {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("⚠ Configured as a {}", device.device_type),
+                );
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// List the devices by hub and port
+    pub fn render(&self, ui: &mut egui::Ui) {
+        let mut devices: Vec<&ConfiguredDevice> = self.devices.iter().collect();
+        devices.sort_by_key(|device| {
+            (
+                device.hub.clone(),
+                device.kind as u8,
+                device.bus.or(device.port),
+            )
+        });
+
+        egui::Grid::new("robot configuration")
+            .striped(true)
+            .show(ui, |ui| {
+                for device in devices {
+                    ui.label(device.location());
+                    ui.label(&device.name);
+                    ui.label(&device.device_type);
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// The value of `name="..."` in the inside of a tag, with XML escapes undone
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+
+    while let Some(i) = rest.find(name) {
+        let before = rest[..i].chars().last();
+        let after = rest[i + name.len()..].trim_start();
+        rest = &rest[i + name.len()..];
+
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let value = match after.strip_prefix('=') {
+            Some(value) => value.trim_start(),
+            None => continue,
+        };
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let end = value[1..].find(quote)?;
+
+        return Some(
+            value[1..end + 1]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+    }
+
+    None
+}
+
+/// The `host:port` to fetch from for an ftc_http host like `http://192.168.43.1:8080/`,
+/// falling back to [`ROBOT_CONTROLLER_ADDRESS`] if it's not set
+pub fn address(host: &str) -> String {
+    let host = host.trim();
+    let host = host
+        .strip_prefix("http://")
+        .or_else(|| host.strip_prefix("https://"))
+        .unwrap_or(host);
+    let host = host.split('/').next().unwrap_or_default();
+
+    if host.is_empty() {
+        ROBOT_CONTROLLER_ADDRESS.to_string()
+    } else if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    }
+}
+
+/// Read the active hardware configuration from the Robot Controller at `address`, e.g.
+/// [`ROBOT_CONTROLLER_ADDRESS`]
+pub fn fetch(address: &str) -> Result<HardwareConfig, ConfigError> {
+    let socket = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address for the robot"))?;
+    let mut stream = TcpStream::connect_timeout(&socket, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: application/xml, text/xml\r\nConnection: close\r\n\r\n",
+        ACTIVE_CONFIG_PATH, address
+    )?;
+
+    let mut response = vec![];
+    stream.read_to_end(&mut response)?;
+    let response = String::from_utf8_lossy(&response);
+
+    HardwareConfig::parse(&body(&response)?)
+}
+
+/// The body of an HTTP response, if it's `200 OK`
+fn body(response: &str) -> Result<String, ConfigError> {
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| ConfigError::Format("the response was cut short".into()))?;
+    let mut lines = head.lines();
+
+    let status = lines.next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(ConfigError::Status(status.to_string()));
+    }
+
+    let chunked = lines.any(|line| {
+        let line = line.to_lowercase();
+        line.starts_with("transfer-encoding:") && line.contains("chunked")
+    });
+    match chunked {
+        true => unchunk(body),
+        false => Ok(body.to_string()),
+    }
+}
+
+/// Join the chunks of a `Transfer-Encoding: chunked` body
+fn unchunk(mut body: &str) -> Result<String, ConfigError> {
+    let mut joined = String::new();
+
+    loop {
+        let (size, rest) = body
+            .split_once("\r\n")
+            .ok_or_else(|| ConfigError::Format("a chunk was cut short".into()))?;
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or_default().trim(), 16)
+            .map_err(|_| ConfigError::Format(format!("bad chunk size \"{}\"", size)))?;
+        if size == 0 {
+            return Ok(joined);
+        }
+        let chunk = rest
+            .get(..size)
+            .ok_or_else(|| ConfigError::Format("a chunk was cut short".into()))?;
+        joined += chunk;
+        body = rest[size..].trim_start_matches("\r\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    const CONFIG: &str = r#"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?>
+<Robot type="FirstInspires-FTC">
+    <LynxUsbDevice name="Control Hub Portal" serialNumber="(embedded)" parentModuleAddress="173">
+        <LynxModule name="Expansion Hub 2" port="2">
+            <Servo name="claw" port="0" />
+            <ContinuousRotationServo name="intake&amp;spin" port="1" />
+        </LynxModule>
+        <LynxModule name="Control Hub" port="173">
+            <goBILDA5202SeriesMotor name="leftDrive" port="0" />
+            <Motor name="arm" port="1" />
+            <!-- <Motor name="commented" port="2" /> -->
+            <RevColorSensorV3 name="color" port="0" bus="1" />
+            <RevTouchSensor name="touch" port="1" />
+        </LynxModule>
+    </LynxUsbDevice>
+    <Webcam name="Webcam 1" serialNumber="VendorProduct:1234" />
+</Robot>
+"#;
+
+    /// A Robot Controller answering one request with `response`, and the request it got
+    fn mock_controller(response: String) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        (address, handle)
+    }
+
+    fn ok(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/xml\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    #[test]
+    fn fetches_the_active_configuration() {
+        let (address, controller) = mock_controller(ok(CONFIG));
+
+        let config = fetch(&address).unwrap();
+        let request = controller.join().unwrap();
+
+        assert!(request.starts_with(&format!("GET {} HTTP/1.1\r\n", ACTIVE_CONFIG_PATH)));
+        assert_eq!(config.devices.len(), 7);
+        assert_eq!(
+            config.device("leftDrive").unwrap().location(),
+            "Control Hub motor port 0"
+        );
+        assert_eq!(
+            config.device("color").unwrap().location(),
+            "Control Hub I2C bus 1"
+        );
+        assert_eq!(config.device("Webcam 1").unwrap().kind, DeviceKind::Sensor);
+        assert!(config.device("commented").is_none());
+    }
+
+    #[test]
+    fn sorts_devices_by_kind() {
+        let config = HardwareConfig::parse(CONFIG).unwrap();
+        let names = |kind| -> Vec<&str> {
+            config
+                .devices_of(Some(kind))
+                .map(|device| device.name.as_str())
+                .collect()
+        };
+
+        assert_eq!(names(DeviceKind::Motor), ["leftDrive", "arm"]);
+        assert_eq!(names(DeviceKind::Servo), ["claw", "intake&spin"]);
+        assert_eq!(names(DeviceKind::Sensor), ["color", "touch", "Webcam 1"]);
+        assert_eq!(
+            config.device("claw").unwrap().location(),
+            "Expansion Hub 2 servo port 0"
+        );
+    }
+
+    #[test]
+    fn reads_a_chunked_response() {
+        let (first, second) = CONFIG.split_at(100);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+            first.len(),
+            first,
+            second.len(),
+            second
+        );
+        let (address, controller) = mock_controller(response);
+
+        let config = fetch(&address).unwrap();
+        controller.join().unwrap();
+
+        assert_eq!(config, HardwareConfig::parse(CONFIG).unwrap());
+    }
+
+    #[test]
+    fn reports_an_error_status() {
+        let (address, controller) =
+            mock_controller("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".into());
+
+        let error = fetch(&address).unwrap_err();
+        controller.join().unwrap();
+
+        assert!(matches!(error, ConfigError::Status(status) if status.contains("404")));
+    }
+
+    #[test]
+    fn rejects_a_page_that_isnt_a_configuration() {
+        let (address, controller) =
+            mock_controller(ok("<html><body>Robot Controller</body></html>"));
+
+        let error = fetch(&address).unwrap_err();
+        controller.join().unwrap();
+
+        assert!(matches!(error, ConfigError::Format(_)));
+    }
+
+    #[test]
+    fn reports_a_controller_that_isnt_there() {
+        // bind and drop a listener to find a port nothing is listening on
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+
+        assert!(matches!(fetch(&address), Err(ConfigError::Io(_))));
+    }
+
+    #[test]
+    fn takes_the_address_from_the_ftc_http_host() {
+        assert_eq!(address("http://192.168.49.1:8080/"), "192.168.49.1:8080");
+        assert_eq!(address("https://robot.local:8443"), "robot.local:8443");
+        assert_eq!(address("192.168.43.1"), "192.168.43.1:80");
+        assert_eq!(address(""), ROBOT_CONTROLLER_ADDRESS);
+    }
+}
@@ -0,0 +1,1910 @@
+use crate::app::generators::control::Control;
+use crate::app::generators::keybinding::keybinding::{Axis, BooleanButton, Gamepad};
+use crate::app::generators::keybinding::shaping::AxisShaping;
+use crate::app::generators::ui_elements::{
+    AxisShapingInput, CheckboxInput, ComboBoxInput, Slider, TextInput,
+};
+use crate::app::project::{ComponentFile, Project, SubsystemFile, PROJECT_VERSION};
+use crate::app::teleop_profile::TeleOpProfile;
+use std::collections::BTreeMap;
+use std::fmt;
+use strum::IntoEnumIterator;
+
+/// Ticks per second a run-to-position motor is given when the OpMode only sets its power, about
+/// full speed for the common 312 RPM motors
+const GUESSED_MAX_SPEED: f32 = 2800.0;
+
+/// Classes the generated OpMode imports itself, so their imports aren't kept as user code
+const GENERATED_IMPORTS: &[&str] = &[
+    "CRServo",
+    "DcMotor",
+    "DcMotorEx",
+    "DcMotorSimple",
+    "ElapsedTime",
+    "HardwareMap",
+    "LinearOpMode",
+    "OpMode",
+    "PwmControl",
+    "Range",
+    "Servo",
+    "TeleOp",
+    "Telemetry",
+];
+
+/// Drive motors driven by code the importer can't map are put in the drivetrain subsystem when
+/// their names contain one of these
+const DRIVE_MOTOR_WORDS: &[&str] = &["drive", "front", "back", "rear", "left", "right"];
+
+/// A project rebuilt from a hand-written TeleOp
+#[derive(Debug, Clone)]
+pub struct Imported {
+    pub project: Project,
+    /// Code that couldn't be turned into components, by the user code region of the TeleOp
+    /// it's kept in
+    pub regions: BTreeMap<String, Vec<String>>,
+    pub report: ImportReport,
+}
+
+/// What an import made of the OpMode
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    pub class_name: String,
+    pub components: usize,
+    /// Lines of the OpMode kept as user code
+    pub kept_lines: usize,
+    pub warnings: Vec<String>,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} components, {} lines kept as your own code",
+            self.class_name, self.components, self.kept_lines
+        )?;
+        for warning in &self.warnings {
+            write!(f, "\n⚠ {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rebuild a project from the Java source of a TeleOp, either a `LinearOpMode` or an iterative
+/// `OpMode`.
+///
+/// Motors and servos found in the hardware map become DC Motor and Servo components, with the
+/// direction and run mode they're set up with. Gamepad buttons moving them between fixed
+/// positions, a button running a motor and a stick driving one become their keybindings. Every
+/// other statement is kept in the TeleOp's user code region for the part of the OpMode it came
+/// from.
+pub fn import(source: &str) -> Result<Imported, String> {
+    let statements = Parser::new(source).block();
+
+    let class = statements
+        .iter()
+        .find(|statement| statement.body.is_some() && words(&statement.code).contains(&"class"))
+        .ok_or("No class found in the file")?;
+    let class_name = word_after(&class.code, "class").ok_or("The class has no name")?;
+
+    let mut importer = Importer::default();
+
+    for statement in &statements {
+        if let Some(name) = statement.code.strip_prefix("import ") {
+            let simple_name = name.rsplit('.').next().unwrap_or(name);
+            if !GENERATED_IMPORTS.contains(&simple_name) {
+                importer.keep("imports", statement);
+            }
+        }
+    }
+
+    importer.class(class.body.as_deref().unwrap_or_default())?;
+
+    Ok(importer.finish(class_name))
+}
+
+/// A statement of the source, or a block such as a method or an `if` with its braces
+#[derive(Debug, Clone)]
+struct Statement {
+    /// The source, comments included, with its lines indented as though it started the line
+    text: String,
+    /// The code without comments, the `;` or the body, and with spaces only where Java needs
+    /// them, e.g. `if(gamepad1.a)`
+    code: String,
+    /// The statements between the braces of a block
+    body: Option<Vec<Statement>>,
+}
+
+/// Splits Java source into statements and blocks
+struct Parser<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            bytes: source.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    /// The statements up to the `}` closing the current block, or the end of the source
+    fn block(&mut self) -> Vec<Statement> {
+        let mut statements = vec![];
+
+        loop {
+            while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            }
+            if self.pos >= self.bytes.len() {
+                return statements;
+            }
+            if self.bytes[self.pos] == b'}' {
+                self.pos += 1;
+                return statements;
+            }
+
+            let start = self.pos;
+            // parentheses, brackets and the braces of array initialisers and lambdas
+            let mut depth = 0usize;
+            let mut last = b' ';
+
+            loop {
+                if self.pos >= self.bytes.len() {
+                    if let Some(statement) = self.simple(start, self.pos) {
+                        statements.push(statement);
+                    }
+                    return statements;
+                }
+
+                match self.bytes[self.pos] {
+                    b'/' if self.bytes.get(self.pos + 1) == Some(&b'/')
+                        || self.bytes.get(self.pos + 1) == Some(&b'*') =>
+                    {
+                        self.pos = skip_comment(self.bytes, self.pos);
+                        continue;
+                    }
+                    b'"' | b'\'' => {
+                        self.pos = skip_literal(self.bytes, self.pos);
+                        last = b'"';
+                        continue;
+                    }
+                    b'(' | b'[' => depth += 1,
+                    b')' | b']' => depth = depth.saturating_sub(1),
+                    b'{' if depth > 0 || last == b'=' || last == b']' => depth += 1,
+                    b'}' if depth > 0 => depth -= 1,
+                    b';' if depth == 0 => {
+                        self.pos += 1;
+                        self.trailing_comment();
+                        if let Some(statement) = self.simple(start, self.pos) {
+                            statements.push(statement);
+                        }
+                        break;
+                    }
+                    b'{' => {
+                        let code = normalize(&self.source[start..self.pos]);
+                        self.pos += 1;
+                        let body = self.block();
+                        self.trailing_comment();
+                        statements.push(Statement {
+                            text: self.text(start, self.pos),
+                            code,
+                            body: Some(body),
+                        });
+                        break;
+                    }
+                    // only comments are left before the end of the block
+                    b'}' => {
+                        if let Some(statement) = self.simple(start, self.pos) {
+                            statements.push(statement);
+                        }
+                        break;
+                    }
+                    _ => {}
+                }
+
+                if !self.bytes[self.pos].is_ascii_whitespace() {
+                    last = self.bytes[self.pos];
+                }
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Take a `//` comment on the rest of the line as part of the statement just ended
+    fn trailing_comment(&mut self) {
+        let mut pos = self.pos;
+        while pos < self.bytes.len() && matches!(self.bytes[pos], b' ' | b'\t') {
+            pos += 1;
+        }
+        if self.bytes[pos..].starts_with(b"//") {
+            self.pos = skip_comment(self.bytes, pos);
+        }
+    }
+
+    fn simple(&self, start: usize, end: usize) -> Option<Statement> {
+        let text = self.text(start, end);
+        if text.is_empty() {
+            return None;
+        }
+
+        Some(Statement {
+            code: normalize(&self.source[start..end])
+                .trim_end_matches(';')
+                .to_string(),
+            text,
+            body: None,
+        })
+    }
+
+    /// The source from `start` to `end`, with the indentation of the line it starts on taken
+    /// off each line
+    fn text(&self, start: usize, end: usize) -> String {
+        let line_start = self.source[..start].rfind('\n').map_or(0, |i| i + 1);
+        // not the column: a statement such as `} else {` starts after the indentation
+        let indent = self.source[line_start..start]
+            .bytes()
+            .take_while(u8::is_ascii_whitespace)
+            .count();
+
+        self.source[start..end]
+            .trim_end()
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let strip = match i {
+                    0 => 0,
+                    _ => line
+                        .bytes()
+                        .take(indent)
+                        .take_while(u8::is_ascii_whitespace)
+                        .count(),
+                };
+                line[strip..].trim_end()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The position after the comment starting at `pos`
+fn skip_comment(bytes: &[u8], pos: usize) -> usize {
+    if bytes[pos + 1] == b'/' {
+        return bytes[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(bytes.len(), |i| pos + i);
+    }
+
+    bytes[pos + 2..]
+        .windows(2)
+        .position(|pair| pair == b"*/")
+        .map_or(bytes.len(), |i| pos + 2 + i + 2)
+}
+
+/// The position after the string or character literal starting at `pos`
+fn skip_literal(bytes: &[u8], pos: usize) -> usize {
+    let quote = bytes[pos];
+    let mut i = pos + 1;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            b'\n' => return i,
+            _ => i += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+fn is_identifier(word: &str) -> bool {
+    !word.is_empty()
+        && word.chars().all(is_identifier_char)
+        && !word.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// `code` without comments, with runs of whitespace dropped, or made one space where it
+/// separates two words
+fn normalize(code: &str) -> String {
+    let bytes = code.as_bytes();
+    let mut out = String::new();
+    let mut space = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c == b'/' && matches!(bytes.get(i + 1), Some(b'/') | Some(b'*')) {
+            i = skip_comment(bytes, i);
+            space = true;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            space = true;
+            i += 1;
+            continue;
+        }
+
+        let end = match c {
+            b'"' | b'\'' => skip_literal(bytes, i),
+            _ => i + code[i..].chars().next().map_or(1, char::len_utf8),
+        };
+        let next = &code[i..end];
+        if space && out.ends_with(is_identifier_char) && next.starts_with(is_identifier_char) {
+            out.push(' ');
+        }
+        out += next;
+        space = false;
+        i = end;
+    }
+
+    out
+}
+
+/// The words of `code`, e.g. `public`, `class` and `MyBot`
+fn words(code: &str) -> Vec<&str> {
+    code.split(|c: char| !is_identifier_char(c))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn word_after(code: &str, keyword: &str) -> Option<String> {
+    let words = words(code);
+    let index = words.iter().position(|word| *word == keyword)?;
+    words.get(index + 1).map(|word| word.to_string())
+}
+
+/// The position of the `)` closing the `(` at `open`
+fn closing_paren(code: &str, open: usize) -> Option<usize> {
+    let bytes = code.as_bytes();
+    let mut depth = 0;
+    let mut i = open;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => {
+                i = skip_literal(bytes, i);
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// `code` split at the commas outside parentheses and literals
+fn split_arguments(code: &str) -> Vec<&str> {
+    let bytes = code.as_bytes();
+    let mut arguments = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => {
+                i = skip_literal(bytes, i);
+                continue;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            b',' if depth == 0 => {
+                arguments.push(&code[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if !code[start..].is_empty() {
+        arguments.push(&code[start..]);
+    }
+
+    arguments
+}
+
+/// A method call such as `arm.setPower(0.5)`: the object, the method and the arguments
+fn call(code: &str) -> Option<(&str, &str, Vec<&str>)> {
+    let open = code.find('(')?;
+    if closing_paren(code, open)? != code.len() - 1 {
+        return None;
+    }
+
+    let (object, method) = code[..open].rsplit_once('.')?;
+    if object.is_empty() || !is_identifier(method) {
+        return None;
+    }
+
+    Some((
+        object,
+        method,
+        split_arguments(&code[open + 1..code.len() - 1]),
+    ))
+}
+
+fn number(code: &str) -> Option<f32> {
+    code.trim_end_matches(['f', 'F', 'd', 'D']).parse().ok()
+}
+
+/// The text of a string literal
+fn string_literal(code: &str) -> Option<String> {
+    Some(code.strip_prefix('"')?.strip_suffix('"')?.to_string())
+}
+
+/// A declared variable and the code it's set to, if any
+type Declarator<'a> = (&'a str, Option<&'a str>);
+
+/// The variables declared by a declaration such as `private DcMotor arm = null`, with their
+/// type and the code they're set to
+fn declaration(code: &str) -> Option<(&str, Vec<Declarator<'_>>)> {
+    let mut rest = code;
+    while let Some(after) = ["private ", "public ", "protected ", "static ", "final "]
+        .iter()
+        .find_map(|modifier| rest.strip_prefix(modifier))
+    {
+        rest = after;
+    }
+
+    let (class, declarators) = rest.split_once(' ')?;
+    if !is_identifier(class) {
+        return None;
+    }
+
+    let mut variables = vec![];
+    for declarator in split_arguments(declarators) {
+        let (variable, value) = match declarator.split_once('=') {
+            Some((variable, value)) => (variable, Some(value)),
+            None => (declarator, None),
+        };
+        if !is_identifier(variable) {
+            return None;
+        }
+        variables.push((variable, value));
+    }
+
+    Some((class, variables))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Motor,
+    Servo,
+    ContinuousServo,
+}
+
+impl Kind {
+    fn from_class(class: &str) -> Option<Self> {
+        match class {
+            "DcMotor" | "DcMotorEx" => Some(Kind::Motor),
+            "Servo" | "ServoImplEx" => Some(Kind::Servo),
+            "CRServo" | "CRServoImplEx" => Some(Kind::ContinuousServo),
+            _ => None,
+        }
+    }
+}
+
+/// The device a hardware map lookup such as `hardwareMap.get(DcMotorEx.class, "arm")` finds,
+/// and its name in the robot configuration
+fn hardware_lookup(code: &str) -> Option<(Kind, String)> {
+    // a cast, as in `(DcMotorEx) hardwareMap.dcMotor.get("arm")`
+    let code = match code.strip_prefix('(').and_then(|rest| rest.split_once(')')) {
+        Some((class, rest)) if is_identifier(class) => rest,
+        _ => code,
+    };
+
+    match call(code)? {
+        ("hardwareMap", "get", arguments) if arguments.len() == 2 => Some((
+            Kind::from_class(arguments[0].strip_suffix(".class")?)?,
+            string_literal(arguments[1])?,
+        )),
+        (device_mapping, "get", arguments) if arguments.len() == 1 => {
+            let kind = match device_mapping {
+                "hardwareMap.dcMotor" => Kind::Motor,
+                "hardwareMap.servo" => Kind::Servo,
+                "hardwareMap.crservo" => Kind::ContinuousServo,
+                _ => return None,
+            };
+            Some((kind, string_literal(arguments[0])?))
+        }
+        _ => None,
+    }
+}
+
+/// A gamepad input, such as `gamepad2.left_bumper`
+#[derive(Debug, Clone, PartialEq)]
+struct Input {
+    gamepad: Gamepad,
+    name: String,
+}
+
+impl Input {
+    fn parse(code: &str, names: &[String]) -> Option<Self> {
+        let (gamepad, name) = code.split_once('.')?;
+        if !names.iter().any(|n| n == name) {
+            return None;
+        }
+
+        Some(Self {
+            gamepad: Gamepad::from_name(gamepad)?,
+            name: name.to_string(),
+        })
+    }
+
+    fn button(code: &str) -> Option<Self> {
+        let buttons: Vec<String> = BooleanButton::iter()
+            .filter(|button| *button != BooleanButton::default)
+            .map(|button| format!("{:?}", button))
+            .collect();
+        Self::parse(code, &buttons)
+    }
+
+    fn axis(code: &str) -> Option<Self> {
+        let axes: Vec<String> = Axis::iter()
+            .filter(|axis| *axis != Axis::default)
+            .map(|axis| format!("{:?}", axis))
+            .collect();
+        Self::parse(code, &axes)
+    }
+}
+
+/// A stick or trigger expression such as `-gamepad1.left_stick_y * 0.5`: the axis, the power it
+/// is scaled to and whether it's inverted
+fn axis_expression(code: &str) -> Option<(Input, f32, bool)> {
+    let mut input = None;
+    let mut power = 1.0;
+    let mut inverted = false;
+
+    for factor in code.split('*') {
+        let factor = match factor.strip_prefix('-') {
+            Some(factor) => {
+                inverted = !inverted;
+                factor
+            }
+            None => factor,
+        };
+        match Input::axis(factor) {
+            Some(axis) if input.is_none() => input = Some(axis),
+            Some(_) => return None,
+            None => power *= number(factor)?,
+        }
+    }
+
+    if power <= 0.0 || power > 1.0 {
+        return None;
+    }
+    Some((input?, power, inverted))
+}
+
+/// How the OpMode drives a device from the gamepads
+#[derive(Debug, Clone, PartialEq)]
+enum Binding {
+    None,
+    /// Fixed positions, or powers of a continuous servo, each picked with a button. The one
+    /// without a button is used while none of the others is pressed.
+    Positions(Vec<(Option<Input>, f32)>),
+    /// A button running a motor while it's held
+    Button {
+        input: Input,
+        power: f32,
+    },
+    /// A stick or trigger driving a motor
+    Axis {
+        input: Input,
+        power: f32,
+        inverted: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Device {
+    variable: String,
+    kind: Kind,
+    /// Name in the robot configuration, once the hardware map lookup is found
+    name: Option<String>,
+    /// The statement declaring the variable, given back if the device is never looked up
+    declaration: Option<Statement>,
+    reversed: bool,
+    run_mode: Option<&'static str>,
+    start_position: Option<f32>,
+    scale: Option<(f32, f32)>,
+    max_speed: Option<f32>,
+    binding: Binding,
+}
+
+impl Device {
+    fn new(variable: &str, kind: Kind) -> Self {
+        Self {
+            variable: variable.to_string(),
+            kind,
+            name: None,
+            declaration: None,
+            reversed: false,
+            run_mode: None,
+            start_position: None,
+            scale: None,
+            max_speed: None,
+            binding: Binding::None,
+        }
+    }
+
+    /// The name of the component, which is also its variable in the generated code
+    fn component_name(&self) -> String {
+        match &self.name {
+            Some(name) if is_identifier(name) => name.clone(),
+            _ => self.variable.clone(),
+        }
+    }
+
+    fn is_drive_motor(&self) -> bool {
+        let name = self.component_name().to_lowercase();
+        self.kind == Kind::Motor
+            && self.binding == Binding::None
+            && DRIVE_MOTOR_WORDS.iter().any(|word| name.contains(word))
+    }
+
+    fn to_component(&self) -> ComponentFile {
+        match self.kind {
+            Kind::Motor => self.motor_component(),
+            Kind::Servo | Kind::ContinuousServo => self.servo_component(),
+        }
+    }
+
+    fn direction(&self) -> &'static str {
+        match self.reversed {
+            true => "Reverse",
+            false => "Forward",
+        }
+    }
+
+    fn motor_component(&self) -> ComponentFile {
+        let run_mode = self.run_mode.unwrap_or("Run without encoders");
+        let mut controls = vec![
+            checkbox("DCM_IsDrivetrain", false),
+            text_input("DCM_Name", &self.component_name()),
+            combo_box("DCM_RunMode", run_mode),
+            combo_box("DCM_Direction", self.direction()),
+        ];
+
+        match &self.binding {
+            Binding::Positions(positions) => {
+                positions_controls(&mut controls, "DCM", positions);
+                controls.push(slider(
+                    "DCM_MaxSpeed",
+                    self.max_speed.unwrap_or(GUESSED_MAX_SPEED),
+                ));
+            }
+            Binding::Button { input, power } => {
+                controls.push(keybinding("DCM_Keybind", Some(input)));
+                controls.push(slider("DCM_MaxPower", *power));
+            }
+            Binding::Axis {
+                input,
+                power,
+                inverted,
+            } => {
+                controls.push(keybinding("DCM_Keybind", Some(input)));
+                controls.push(axis_shaping("DCM_AxisShaping", *inverted));
+                controls.push(slider("DCM_MaxPower", *power));
+            }
+            Binding::None => {}
+        }
+
+        ComponentFile {
+            script: "DC_Motor.lua".to_string(),
+            controls,
+        }
+    }
+
+    fn servo_component(&self) -> ComponentFile {
+        let mut controls = vec![
+            text_input("SERVO_Name", &self.component_name()),
+            combo_box(
+                "SERVO_Mode",
+                match self.kind {
+                    Kind::ContinuousServo => "Continuous",
+                    _ => "Positional",
+                },
+            ),
+            combo_box("SERVO_Direction", self.direction()),
+        ];
+
+        if let Some(position) = self.start_position {
+            controls.push(slider("SERVO_StartPosition", position));
+        }
+        if let Some((min, max)) = self.scale {
+            controls.push(slider("SERVO_ScaleMin", min));
+            controls.push(slider("SERVO_ScaleMax", max));
+        }
+        if let Binding::Positions(positions) = &self.binding {
+            positions_controls(&mut controls, "SERVO", positions);
+        }
+
+        ComponentFile {
+            script: "Servo.lua".to_string(),
+            controls,
+        }
+    }
+}
+
+// The controls of a component only need names and values: the module fills in the rest when the
+// component is loaded, as it does for a saved project.
+
+fn positions_controls(
+    controls: &mut Vec<Control>,
+    prefix: &str,
+    positions: &[(Option<Input>, f32)],
+) {
+    controls.push(slider(
+        &format!("{}_NumPositions", prefix),
+        positions.len() as f32,
+    ));
+    for (i, (input, value)) in positions.iter().enumerate() {
+        controls.push(slider(&format!("{}_Position{}", prefix, i + 1), *value));
+        controls.push(keybinding(
+            &format!("{}_Keybind{}", prefix, i + 1),
+            input.as_ref(),
+        ));
+    }
+}
+
+fn checkbox(name: &str, value: bool) -> Control {
+    Control::CheckboxType(CheckboxInput {
+        name: name.to_string(),
+        label: String::new(),
+        value,
+    })
+}
+
+fn text_input(name: &str, value: &str) -> Control {
+    Control::TextInputType(TextInput {
+        name: name.to_string(),
+        value: value.to_string(),
+        label: String::new(),
+    })
+}
+
+fn combo_box(name: &str, value: &str) -> Control {
+    Control::ComboBoxType(ComboBoxInput {
+        name: name.to_string(),
+        value: value.to_string(),
+        label: String::new(),
+        entries: vec![],
+        id: 0,
+        gamepad: None,
+        behaviour: None,
+        behaviours: vec![],
+        modifiers: vec![],
+    })
+}
+
+/// A keybinding to `input`, or to the default button when there's none
+fn keybinding(name: &str, input: Option<&Input>) -> Control {
+    Control::ComboBoxType(ComboBoxInput {
+        name: name.to_string(),
+        value: input.map_or("default_button".to_string(), |input| input.name.clone()),
+        label: String::new(),
+        entries: vec![],
+        id: 0,
+        gamepad: Some(input.map_or(Gamepad::default(), |input| input.gamepad)),
+        behaviour: None,
+        behaviours: vec![],
+        modifiers: vec![],
+    })
+}
+
+fn slider(name: &str, value: f32) -> Control {
+    Control::SliderType(Slider {
+        name: name.to_string(),
+        min: value,
+        max: value,
+        value,
+        step_by: 1.0,
+        deicimals: 0,
+        label: String::new(),
+        keybinding: None,
+    })
+}
+
+fn axis_shaping(name: &str, inverted: bool) -> Control {
+    Control::AxisShapingType(AxisShapingInput {
+        name: name.to_string(),
+        label: String::new(),
+        shaping: AxisShaping {
+            inverted,
+            ..Default::default()
+        },
+    })
+}
+
+/// The calls of an `if`, `else if` or `else` branch, and the button it's taken on
+struct Branch {
+    input: Option<Input>,
+    calls: Vec<String>,
+}
+
+impl Branch {
+    fn parse(statement: &Statement, first: bool) -> Option<Self> {
+        let mut rest = statement.code.as_str();
+        if !first {
+            rest = rest.strip_prefix("else")?;
+            rest = rest.strip_prefix(' ').unwrap_or(rest);
+        }
+
+        let input = match rest.strip_prefix("if(") {
+            Some(_) => {
+                let close = closing_paren(rest, 2)?;
+                let input = Input::button(&rest[3..close])?;
+                rest = &rest[close + 1..];
+                Some(input)
+            }
+            None if first => return None,
+            None => None,
+        };
+
+        let calls = match &statement.body {
+            Some(body) => body
+                .iter()
+                .filter(|statement| !statement.code.is_empty())
+                .map(|statement| match statement.body {
+                    Some(_) => None,
+                    None => Some(statement.code.clone()),
+                })
+                .collect::<Option<Vec<_>>>()?,
+            None => vec![rest.to_string()],
+        };
+        if calls.is_empty() || calls.iter().any(|call| call.is_empty()) {
+            return None;
+        }
+
+        Some(Self { input, calls })
+    }
+}
+
+#[derive(Default)]
+struct Importer {
+    devices: Vec<Device>,
+    regions: BTreeMap<String, Vec<String>>,
+    warnings: Vec<String>,
+    guessed_speed: bool,
+}
+
+impl Importer {
+    /// Keep `statement` as user code in `region`
+    fn keep(&mut self, region: &str, statement: &Statement) {
+        self.regions
+            .entry(region.to_string())
+            .or_default()
+            .extend(statement.text.lines().map(str::to_string));
+    }
+
+    /// Keep `statements` commented out in `region`, after `note`, for code with no place in
+    /// the generated OpMode
+    fn keep_commented(&mut self, region: &str, note: &str, statements: &[Statement]) {
+        if statements.is_empty() {
+            return;
+        }
+
+        let lines = self.regions.entry(region.to_string()).or_default();
+        lines.push(format!("// {}", note));
+        for statement in statements {
+            lines.extend(statement.text.lines().map(|line| format!("// {}", line)));
+        }
+        self.warnings
+            .push(format!("{} It's kept commented out.", note));
+    }
+
+    fn device(&mut self, variable: &str) -> Option<&mut Device> {
+        self.devices
+            .iter_mut()
+            .find(|device| device.variable == variable)
+    }
+
+    fn declare(&mut self, variable: &str, kind: Kind) -> &mut Device {
+        let index = match self
+            .devices
+            .iter()
+            .position(|device| device.variable == variable)
+        {
+            Some(index) => index,
+            None => {
+                self.devices.push(Device::new(variable, kind));
+                self.devices.len() - 1
+            }
+        };
+        &mut self.devices[index]
+    }
+
+    fn class(&mut self, members: &[Statement]) -> Result<(), String> {
+        let method = |name: &str| {
+            members.iter().find(|member| {
+                member.body.is_some() && method_name(&member.code).as_deref() == Some(name)
+            })
+        };
+
+        let linear = method("runOpMode");
+        let iterative = method("loop");
+        if linear.is_none() && iterative.is_none() {
+            return Err("The class has no runOpMode() or loop() method".to_string());
+        }
+
+        for member in members {
+            match (&member.body, method_name(&member.code)) {
+                (None, _) => self.field(member),
+                (Some(_), Some(name)) => match name.as_str() {
+                    "runOpMode" => {}
+                    "init" | "start" | "loop" if linear.is_none() => {}
+                    "init_loop" | "stop" if linear.is_none() => self.keep_commented(
+                        "members",
+                        &format!("{}() isn't called by the generated OpMode.", name),
+                        std::slice::from_ref(member),
+                    ),
+                    _ => self.keep("members", member),
+                },
+                (Some(_), None) => self.keep("members", member),
+            }
+        }
+
+        match linear {
+            Some(run_op_mode) => self.run_op_mode(run_op_mode.body.as_deref().unwrap_or_default()),
+            None => {
+                let body = |member: Option<&Statement>| {
+                    member.and_then(|m| m.body.clone()).unwrap_or_default()
+                };
+                for statement in body(method("init")) {
+                    self.init(&statement);
+                }
+                for statement in body(method("start")) {
+                    self.start(&statement);
+                }
+                self.loop_body(&body(iterative));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn field(&mut self, statement: &Statement) {
+        if statement
+            .code
+            .ends_with("ElapsedTime runtime=new ElapsedTime()")
+        {
+            return;
+        }
+
+        if let Some((class, variables)) = declaration(&statement.code) {
+            if let Some(kind) = Kind::from_class(class) {
+                let lookups: Option<Vec<Option<String>>> = variables
+                    .iter()
+                    .map(|(_, value)| match value {
+                        None | Some("null") => Some(None),
+                        Some(value) => hardware_lookup(value).map(|(_, name)| Some(name)),
+                    })
+                    .collect();
+
+                if let Some(lookups) = lookups {
+                    for ((variable, _), name) in variables.iter().zip(lookups) {
+                        let device = self.declare(variable, kind);
+                        device.declaration = Some(statement.clone());
+                        if name.is_some() {
+                            device.name = name;
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+
+        self.keep("fields", statement);
+    }
+
+    fn run_op_mode(&mut self, body: &[Statement]) {
+        let mut started = false;
+        let mut statements = body.iter();
+
+        for statement in statements.by_ref() {
+            if statement.code == "waitForStart()" {
+                started = true;
+                break;
+            }
+            self.init(statement);
+        }
+        if !started {
+            self.warnings.push(
+                "runOpMode() never calls waitForStart(); all of it was read as init code.".into(),
+            );
+            return;
+        }
+
+        let mut after_loop = vec![];
+        let mut in_loop = false;
+        let mut pending: Vec<&Statement> = statements.collect();
+        while !pending.is_empty() {
+            let statement = pending.remove(0);
+
+            if in_loop {
+                after_loop.push(statement.clone());
+                continue;
+            }
+
+            match (statement.code.as_str(), &statement.body) {
+                ("if(opModeIsActive())", Some(body)) => {
+                    for (i, inner) in body.iter().enumerate() {
+                        pending.insert(i, inner);
+                    }
+                }
+                (
+                    "while(opModeIsActive())"
+                    | "while(opModeIsActive()&&!isStopRequested())"
+                    | "while(!isStopRequested()&&opModeIsActive())",
+                    Some(body),
+                ) => {
+                    self.loop_body(body);
+                    in_loop = true;
+                }
+                _ => self.start(statement),
+            }
+        }
+
+        self.keep_commented(
+            "members",
+            "Code that ran after the loop, once the OpMode was stopped, has no place in the generated OpMode.",
+            &after_loop,
+        );
+    }
+
+    fn init(&mut self, statement: &Statement) {
+        let code = statement.code.as_str();
+        if statement.body.is_none() {
+            // the generated OpMode reports it's initialized, and sends that, before its init code
+            if code == r#"telemetry.addData("Status","Initialized")"#
+                || (code == "telemetry.update()" && !self.regions.contains_key("init"))
+            {
+                return;
+            }
+            // a local variable, as in `DcMotor arm = hardwareMap.get(DcMotor.class, "arm")`
+            if let Some((class, variables)) = declaration(code) {
+                if let ([(variable, Some(value))], Some(_)) =
+                    (variables.as_slice(), Kind::from_class(class))
+                {
+                    if let Some((kind, name)) = hardware_lookup(value) {
+                        self.declare(variable, kind).name = Some(name);
+                        return;
+                    }
+                }
+            }
+            if let Some((variable, value)) = code.split_once('=') {
+                if let Some((kind, name)) = hardware_lookup(value) {
+                    if is_identifier(variable) {
+                        self.declare(variable, kind).name = Some(name);
+                        return;
+                    }
+                }
+            }
+            if let Some((variable, method, arguments)) = call(code) {
+                if let Some(device) = self.device(variable) {
+                    if device.set_up(method, &arguments) {
+                        return;
+                    }
+                }
+            }
+        }
+
+        self.keep("init", statement);
+    }
+
+    fn start(&mut self, statement: &Statement) {
+        if statement.code != "runtime.reset()" {
+            self.keep("start", statement);
+        }
+    }
+
+    fn loop_body(&mut self, body: &[Statement]) {
+        let mut i = 0;
+        while i < body.len() {
+            let statement = &body[i];
+
+            if statement.code.starts_with("if(") {
+                let mut end = i + 1;
+                while end < body.len()
+                    && (body[end].code == "else" || body[end].code.starts_with("else "))
+                {
+                    end += 1;
+                }
+                let chain = &body[i..end];
+                if !self.map_chain(chain) {
+                    for statement in chain {
+                        self.keep("loop", statement);
+                    }
+                }
+                i = end;
+                continue;
+            }
+
+            // the generated loop ends by sending the telemetry
+            if statement.code != "telemetry.update()" && !self.map_axis(statement) {
+                self.keep("loop", statement);
+            }
+            i += 1;
+        }
+    }
+
+    /// Map `device.setPower(<stick>)` to a motor driven by the stick
+    fn map_axis(&mut self, statement: &Statement) -> bool {
+        if statement.body.is_some() {
+            return false;
+        }
+        let (variable, arguments) = match call(&statement.code) {
+            Some((variable, "setPower", arguments)) if arguments.len() == 1 => {
+                (variable, arguments)
+            }
+            _ => return false,
+        };
+        let (input, power, inverted) = match axis_expression(arguments[0]) {
+            Some(axis) => axis,
+            None => return false,
+        };
+
+        match self.device(variable) {
+            Some(device)
+                if device.kind == Kind::Motor
+                    && device.binding == Binding::None
+                    && device.run_mode != Some("Run to position") =>
+            {
+                device.binding = Binding::Axis {
+                    input,
+                    power,
+                    inverted,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Map an `if (gamepad1.a) { ... } else if ...` chain moving one device
+    fn map_chain(&mut self, chain: &[Statement]) -> bool {
+        let branches: Option<Vec<Branch>> = chain
+            .iter()
+            .enumerate()
+            .map(|(i, statement)| Branch::parse(statement, i == 0))
+            .collect();
+        let branches = match branches {
+            Some(branches) => branches,
+            None => return false,
+        };
+        // a plain `else` can only end the chain
+        if branches[..branches.len() - 1]
+            .iter()
+            .any(|branch| branch.input.is_none())
+        {
+            return false;
+        }
+
+        let mut variable = None;
+        let mut calls: Vec<Vec<(String, Vec<String>)>> = vec![];
+        for branch in &branches {
+            let mut branch_calls = vec![];
+            for code in &branch.calls {
+                let (object, method, arguments) = match call(code) {
+                    Some(call) => call,
+                    None => return false,
+                };
+                if *variable.get_or_insert(object) != object {
+                    return false;
+                }
+                branch_calls.push((
+                    method.to_string(),
+                    arguments.iter().map(|a| a.to_string()).collect(),
+                ));
+            }
+            calls.push(branch_calls);
+        }
+
+        let device = match variable.and_then(|variable| {
+            self.devices
+                .iter_mut()
+                .find(|device| device.variable == variable)
+        }) {
+            Some(device) => device,
+            None => return false,
+        };
+        let inputs: Vec<Option<Input>> = branches.into_iter().map(|b| b.input).collect();
+
+        match device.kind {
+            Kind::Motor => {
+                if let Some(guessed) = device.map_positions(&inputs, &calls) {
+                    self.guessed_speed |= guessed;
+                    return true;
+                }
+                device.map_button(&inputs, &calls)
+            }
+            Kind::Servo | Kind::ContinuousServo => device.map_servo_positions(&inputs, &calls),
+        }
+    }
+
+    fn finish(mut self, class_name: String) -> Imported {
+        let mut renames = vec![];
+        let mut undeclared = vec![];
+
+        for device in &self.devices {
+            match &device.name {
+                Some(name) => {
+                    if !is_identifier(name) {
+                        self.warnings.push(format!(
+                            "\"{}\" isn't a valid Java name, so the device is called {}. \
+                             Rename it to {} in the robot configuration.",
+                            name, device.variable, device.variable
+                        ));
+                    }
+                    if device.component_name() != device.variable {
+                        renames.push((device.variable.clone(), device.component_name()));
+                    }
+                }
+                None => {
+                    if let Some(declaration) = &device.declaration {
+                        if !undeclared
+                            .iter()
+                            .any(|d: &Statement| d.text == declaration.text)
+                        {
+                            undeclared.push(declaration.clone());
+                        }
+                    }
+                }
+            }
+        }
+        for declaration in &undeclared {
+            self.keep("fields", declaration);
+        }
+        self.devices.retain(|device| device.name.is_some());
+
+        if self.guessed_speed {
+            self.warnings.push(format!(
+                "Motors running to positions were only given a power, so their Max Speed is set \
+                 to {} ticks per second. Check it suits them.",
+                GUESSED_MAX_SPEED
+            ));
+        }
+
+        // kept code uses the generated names for the devices
+        for lines in self.regions.values_mut() {
+            for line in lines.iter_mut() {
+                for (from, to) in &renames {
+                    *line = rename(line, from, to);
+                }
+            }
+        }
+
+        let drivetrain: Vec<&Device> = self.devices.iter().filter(|d| d.is_drive_motor()).collect();
+        let motors: Vec<&Device> = self
+            .devices
+            .iter()
+            .filter(|d| d.kind == Kind::Motor && !d.is_drive_motor())
+            .collect();
+        let servos: Vec<&Device> = self
+            .devices
+            .iter()
+            .filter(|d| d.kind != Kind::Motor)
+            .collect();
+
+        let subsystem = |name: &str, devices: &[&Device]| SubsystemFile {
+            name: name.to_string(),
+            components: devices.iter().map(|device| device.to_component()).collect(),
+        };
+        let mut subsystems = vec![];
+        if !motors.is_empty() {
+            subsystems.push(subsystem("Motors", &motors));
+        }
+        if !servos.is_empty() {
+            subsystems.push(subsystem("Servos", &servos));
+        }
+
+        let report = ImportReport {
+            class_name: class_name.clone(),
+            components: self.devices.len(),
+            kept_lines: self
+                .regions
+                .values()
+                .flatten()
+                .filter(|line| !line.trim().is_empty())
+                .count(),
+            warnings: self.warnings,
+        };
+
+        Imported {
+            project: Project {
+                version: PROJECT_VERSION,
+                file_name: class_name.clone(),
+                profiles: vec![TeleOpProfile::new(&class_name)],
+                drivetrain: subsystem("Drivetrain", &drivetrain),
+                subsystems,
+                autonomous: Default::default(),
+                telemetry: Default::default(),
+                user_code: Default::default(),
+            },
+            regions: self.regions,
+            report,
+        }
+    }
+}
+
+impl Device {
+    /// Map a setup call from the init code, such as `setDirection`
+    fn set_up(&mut self, method: &str, arguments: &[&str]) -> bool {
+        let argument = match arguments {
+            [argument] => *argument,
+            [min, max] if method == "scaleRange" && self.kind == Kind::Servo => {
+                return match (number(min), number(max)) {
+                    (Some(min), Some(max)) => {
+                        self.scale = Some((min, max));
+                        true
+                    }
+                    _ => false,
+                };
+            }
+            _ => return false,
+        };
+        let constant = argument.rsplit('.').next().unwrap_or(argument);
+
+        match (self.kind, method) {
+            (_, "setDirection") if constant == "REVERSE" || constant == "FORWARD" => {
+                self.reversed = constant == "REVERSE";
+                true
+            }
+            (Kind::Motor, "setMode") => {
+                self.run_mode = match constant.trim_end_matches('S') {
+                    "RUN_TO_POSITION" => Some("Run to position"),
+                    "RUN_USING_ENCODER" => Some("Run using encoders"),
+                    "RUN_WITHOUT_ENCODER" => Some("Run without encoders"),
+                    // the generated init resets the encoder too
+                    "STOP_AND_RESET_ENCODER" => return true,
+                    _ => return false,
+                };
+                true
+            }
+            (Kind::Motor, "setTargetPosition") | (_, "setPower") => number(argument) == Some(0.0),
+            (Kind::Servo, "setPosition") => match number(argument) {
+                Some(position) => {
+                    self.start_position = Some(position);
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// The positions picked so far, with no binding counting as none
+    fn positions(&self) -> Option<Vec<(Option<Input>, f32)>> {
+        match &self.binding {
+            Binding::None => Some(vec![]),
+            Binding::Positions(positions) => Some(positions.clone()),
+            _ => None,
+        }
+    }
+
+    /// Map a chain of buttons each running the motor to a position. Returns whether the motor's
+    /// speed had to be guessed.
+    fn map_positions(
+        &mut self,
+        inputs: &[Option<Input>],
+        calls: &[Vec<(String, Vec<String>)>],
+    ) -> Option<bool> {
+        if self.run_mode.is_some_and(|mode| mode != "Run to position") {
+            let sets_mode = calls.iter().flatten().any(|(method, arguments)| {
+                method == "setMode" && arguments.iter().any(|a| a.ends_with("RUN_TO_POSITION"))
+            });
+            if !sets_mode {
+                return None;
+            }
+        }
+        let mut positions = self.positions()?;
+        let mut speed = self.max_speed;
+        let mut power = None;
+
+        for (input, calls) in inputs.iter().zip(calls) {
+            let mut target = None;
+            for (method, arguments) in calls {
+                let value = match arguments.as_slice() {
+                    [argument] => argument,
+                    _ => return None,
+                };
+                match method.as_str() {
+                    "setTargetPosition" if target.is_none() => target = Some(number(value)?),
+                    "setMode" if value.ends_with("RUN_TO_POSITION") => {}
+                    "setVelocity" => speed = Some(number(value)?.abs().max(speed.unwrap_or(0.0))),
+                    "setPower" => power = Some(number(value)?.abs()),
+                    _ => return None,
+                }
+            }
+            if input.is_none() && positions.iter().any(|(input, _)| input.is_none()) {
+                return None;
+            }
+            positions.push((input.clone(), target?));
+        }
+
+        let guessed = speed.is_none();
+        self.max_speed = speed.or(power.map(|power| power * GUESSED_MAX_SPEED));
+        self.run_mode = Some("Run to position");
+        self.binding = Binding::Positions(positions);
+        Some(guessed)
+    }
+
+    /// Map `if (button) motor.setPower(power);` to a motor run while the button is held
+    fn map_button(
+        &mut self,
+        inputs: &[Option<Input>],
+        calls: &[Vec<(String, Vec<String>)>],
+    ) -> bool {
+        if self.binding != Binding::None || self.run_mode == Some("Run to position") {
+            return false;
+        }
+
+        match (inputs, calls) {
+            ([Some(input)], [calls]) => match calls.as_slice() {
+                [(method, arguments)] if method == "setPower" && arguments.len() == 1 => {
+                    match number(&arguments[0]) {
+                        Some(power) if power > 0.0 && power <= 1.0 => {
+                            self.binding = Binding::Button {
+                                input: input.clone(),
+                                power,
+                            };
+                            true
+                        }
+                        _ => false,
+                    }
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Map a chain of buttons each moving the servo to a position, or running a continuous
+    /// servo at a power
+    fn map_servo_positions(
+        &mut self,
+        inputs: &[Option<Input>],
+        calls: &[Vec<(String, Vec<String>)>],
+    ) -> bool {
+        let setter = match self.kind {
+            Kind::Servo => "setPosition",
+            _ => "setPower",
+        };
+        let mut positions = match self.positions() {
+            Some(positions) => positions,
+            None => return false,
+        };
+
+        for (input, calls) in inputs.iter().zip(calls) {
+            let value = match calls.as_slice() {
+                [(method, arguments)] if method == setter && arguments.len() == 1 => {
+                    number(&arguments[0])
+                }
+                _ => None,
+            };
+            let value = match value {
+                Some(value) => value,
+                None => return false,
+            };
+            if input.is_none() && positions.iter().any(|(input, _)| input.is_none()) {
+                return false;
+            }
+            positions.push((input.clone(), value));
+        }
+
+        self.binding = Binding::Positions(positions);
+        true
+    }
+}
+
+/// The method a class member's header declares, e.g. `loop` for `@Override public void loop()`
+fn method_name(header: &str) -> Option<String> {
+    if words(header)
+        .iter()
+        .any(|word| matches!(*word, "class" | "interface" | "enum"))
+    {
+        return None;
+    }
+
+    let open = header.rfind('(')?;
+    let name = header[..open]
+        .rsplit(|c: char| !is_identifier_char(c))
+        .next()?;
+    match is_identifier(name) {
+        true => Some(name.to_string()),
+        false => None,
+    }
+}
+
+/// `line` with the word `from` replaced by `to`, outside string literals. Comments are renamed
+/// too, since commented-out code is kept.
+fn rename(line: &str, from: &str, to: &str) -> String {
+    let bytes = line.as_bytes();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c == b'"' || c == b'\'' {
+            let end = skip_literal(bytes, i);
+            out += &line[i..end];
+            i = end;
+            continue;
+        }
+
+        let starts_word = i == 0 || !is_identifier_char(bytes[i - 1] as char);
+        if starts_word
+            && line[i..].starts_with(from)
+            && !line[i + from.len()..].starts_with(is_identifier_char)
+        {
+            out += to;
+            i += from.len();
+            continue;
+        }
+
+        let end = i + line[i..].chars().next().map_or(1, char::len_utf8);
+        out += &line[i..end];
+        i = end;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINEAR: &str = r#"package org.firstinspires.ftc.teamcode;
+
+import com.qualcomm.robotcore.eventloop.opmode.LinearOpMode;
+import com.qualcomm.robotcore.eventloop.opmode.TeleOp;
+import com.qualcomm.robotcore.hardware.DcMotor;
+import com.qualcomm.robotcore.hardware.Servo;
+import java.util.List;
+
+@TeleOp(name = "Basic")
+public class BasicTeleOp extends LinearOpMode {
+    private DcMotor leftDrive = null;
+    private DcMotor lift;
+    private Servo claw;
+    private int presses = 0;
+
+    @Override
+    public void runOpMode() {
+        leftDrive = hardwareMap.get(DcMotor.class, "left_drive");
+        lift = hardwareMap.get(DcMotor.class, "liftMotor");
+        claw = hardwareMap.servo.get("claw");
+        lift.setDirection(DcMotor.Direction.REVERSE);
+        claw.setPosition(0.2);
+        telemetry.addData("Status", "Initialized");
+        telemetry.update();
+
+        waitForStart();
+
+        while (opModeIsActive()) {
+            lift.setPower(-gamepad2.left_stick_y * 0.5);
+            if (gamepad1.a) {
+                claw.setPosition(0.8);
+            } else if (gamepad1.b) {
+                claw.setPosition(0.2);
+            }
+            if (gamepad1.x) {
+                presses++;
+            }
+            telemetry.addData("Lift", lift.getCurrentPosition());
+            telemetry.update();
+        }
+        lift.setPower(0);
+    }
+}
+"#;
+
+    const ITERATIVE: &str = r#"@TeleOp
+public class ArmOpMode extends OpMode {
+    DcMotorEx arm;
+    CRServo intake;
+
+    @Override
+    public void init() {
+        arm = hardwareMap.get(DcMotorEx.class, "arm");
+        intake = hardwareMap.crservo.get("intake");
+        arm.setMode(DcMotor.RunMode.STOP_AND_RESET_ENCODER);
+        arm.setMode(DcMotor.RunMode.RUN_TO_POSITION);
+        arm.setTargetPosition(0);
+    }
+
+    @Override
+    public void start() {
+        runtime.reset();
+        telemetry.speak("Go");
+    }
+
+    @Override
+    public void loop() {
+        if (gamepad2.dpad_up) {
+            arm.setTargetPosition(1200);
+            arm.setVelocity(2000);
+        } else if (gamepad2.dpad_down) {
+            arm.setTargetPosition(0);
+            arm.setVelocity(1500);
+        }
+        if (gamepad1.right_bumper) {
+            intake.setPower(1.0);
+        } else if (gamepad1.left_bumper) {
+            intake.setPower(-1.0);
+        } else {
+            intake.setPower(0);
+        }
+    }
+
+    @Override
+    public void stop() {
+        intake.setPower(0);
+    }
+}
+"#;
+
+    /// The value of each control of `component` worth checking, keybindings with their gamepad
+    fn values(component: &ComponentFile) -> Vec<(String, String)> {
+        component
+            .controls
+            .iter()
+            .filter_map(|control| match control {
+                Control::TextInputType(input) => Some((input.name.clone(), input.value.clone())),
+                Control::ComboBoxType(input) => Some((
+                    input.name.clone(),
+                    match input.gamepad {
+                        Some(gamepad) => format!("{:?}.{}", gamepad, input.value),
+                        None => input.value.clone(),
+                    },
+                )),
+                Control::SliderType(slider) => {
+                    Some((slider.name.clone(), slider.value.to_string()))
+                }
+                Control::CheckboxType(checkbox) => {
+                    Some((checkbox.name.clone(), checkbox.value.to_string()))
+                }
+                Control::AxisShapingType(shaping) => Some((
+                    shaping.name.clone(),
+                    format!("inverted {}", shaping.shaping.inverted),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn expect(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn region(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn imports_a_linear_op_mode() {
+        let imported = import(LINEAR).unwrap();
+        let project = &imported.project;
+
+        assert_eq!(project.file_name, "BasicTeleOp");
+        assert_eq!(project.drivetrain.components.len(), 1);
+        assert_eq!(
+            values(&project.drivetrain.components[0]),
+            expect(&[
+                ("DCM_IsDrivetrain", "false"),
+                ("DCM_Name", "left_drive"),
+                ("DCM_RunMode", "Run without encoders"),
+                ("DCM_Direction", "Forward"),
+            ])
+        );
+
+        let names: Vec<&str> = project.subsystems.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Motors", "Servos"]);
+        assert_eq!(
+            values(&project.subsystems[0].components[0]),
+            expect(&[
+                ("DCM_IsDrivetrain", "false"),
+                ("DCM_Name", "liftMotor"),
+                ("DCM_RunMode", "Run without encoders"),
+                ("DCM_Direction", "Reverse"),
+                ("DCM_Keybind", "gamepad2.left_stick_y"),
+                ("DCM_AxisShaping", "inverted true"),
+                ("DCM_MaxPower", "0.5"),
+            ])
+        );
+        assert_eq!(
+            values(&project.subsystems[1].components[0]),
+            expect(&[
+                ("SERVO_Name", "claw"),
+                ("SERVO_Mode", "Positional"),
+                ("SERVO_Direction", "Forward"),
+                ("SERVO_StartPosition", "0.2"),
+                ("SERVO_NumPositions", "2"),
+                ("SERVO_Position1", "0.8"),
+                ("SERVO_Keybind1", "gamepad1.a"),
+                ("SERVO_Position2", "0.2"),
+                ("SERVO_Keybind2", "gamepad1.b"),
+            ])
+        );
+
+        // kept code uses the devices' new names
+        assert_eq!(
+            imported.regions,
+            BTreeMap::from([
+                ("imports".to_string(), region(&["import java.util.List;"])),
+                ("fields".to_string(), region(&["private int presses = 0;"])),
+                (
+                    "loop".to_string(),
+                    region(&[
+                        "if (gamepad1.x) {",
+                        "    presses++;",
+                        "}",
+                        r#"telemetry.addData("Lift", liftMotor.getCurrentPosition());"#,
+                    ])
+                ),
+                (
+                    "members".to_string(),
+                    region(&[
+                        "// Code that ran after the loop, once the OpMode was stopped, has no place in the generated OpMode.",
+                        "// liftMotor.setPower(0);",
+                    ])
+                ),
+            ])
+        );
+
+        let report = &imported.report;
+        assert_eq!(report.class_name, "BasicTeleOp");
+        assert_eq!(report.components, 3);
+        assert_eq!(report.kept_lines, 8);
+        assert_eq!(
+            report.warnings,
+            ["Code that ran after the loop, once the OpMode was stopped, has no place in the generated OpMode. It's kept commented out."]
+        );
+    }
+
+    #[test]
+    fn imports_an_iterative_op_mode() {
+        let imported = import(ITERATIVE).unwrap();
+        let project = &imported.project;
+
+        assert!(project.drivetrain.components.is_empty());
+        assert_eq!(
+            values(&project.subsystems[0].components[0]),
+            expect(&[
+                ("DCM_IsDrivetrain", "false"),
+                ("DCM_Name", "arm"),
+                ("DCM_RunMode", "Run to position"),
+                ("DCM_Direction", "Forward"),
+                ("DCM_NumPositions", "2"),
+                ("DCM_Position1", "1200"),
+                ("DCM_Keybind1", "gamepad2.dpad_up"),
+                ("DCM_Position2", "0"),
+                ("DCM_Keybind2", "gamepad2.dpad_down"),
+                ("DCM_MaxSpeed", "2000"),
+            ])
+        );
+        assert_eq!(
+            values(&project.subsystems[1].components[0]),
+            expect(&[
+                ("SERVO_Name", "intake"),
+                ("SERVO_Mode", "Continuous"),
+                ("SERVO_Direction", "Forward"),
+                ("SERVO_NumPositions", "3"),
+                ("SERVO_Position1", "1"),
+                ("SERVO_Keybind1", "gamepad1.right_bumper"),
+                ("SERVO_Position2", "-1"),
+                ("SERVO_Keybind2", "gamepad1.left_bumper"),
+                ("SERVO_Position3", "0"),
+                ("SERVO_Keybind3", "gamepad1.default_button"),
+            ])
+        );
+
+        assert_eq!(
+            imported.regions,
+            BTreeMap::from([
+                ("start".to_string(), region(&[r#"telemetry.speak("Go");"#])),
+                (
+                    "members".to_string(),
+                    region(&[
+                        "// stop() isn't called by the generated OpMode.",
+                        "// @Override",
+                        "// public void stop() {",
+                        "//     intake.setPower(0);",
+                        "// }",
+                    ])
+                ),
+            ])
+        );
+        assert_eq!(imported.report.components, 2);
+        assert_eq!(
+            imported.report.warnings,
+            ["stop() isn't called by the generated OpMode. It's kept commented out."]
+        );
+    }
+
+    #[test]
+    fn needs_a_class_with_an_op_mode_method() {
+        assert!(import("int x = 1;").is_err());
+        assert!(import("class Helper { void help() {} }").is_err());
+    }
+
+    #[test]
+    fn splits_blocks_around_comments_literals_and_initialisers() {
+        let source = r#"
+// a comment with { a brace
+int[] values = {1, 2, 3};
+String s = "}{";  // and a } trailing comment
+char c = '{';
+/* a block { comment */
+list.forEach(x -> { go(x); });
+if (a) {
+    x();
+} else if (b) {
+    y(); // }
+} else {
+    z();
+}
+"#;
+        let statements = Parser::new(source).block();
+
+        let codes: Vec<&str> = statements.iter().map(|s| s.code.as_str()).collect();
+        assert_eq!(
+            codes,
+            [
+                "int[]values={1,2,3}",
+                r#"String s="}{""#,
+                "char c='{'",
+                "list.forEach(x->{go(x);})",
+                "if(a)",
+                "else if(b)",
+                "else",
+            ]
+        );
+
+        assert_eq!(
+            statements[0].text,
+            "// a comment with { a brace\nint[] values = {1, 2, 3};"
+        );
+        assert_eq!(
+            statements[1].text,
+            r#"String s = "}{";  // and a } trailing comment"#
+        );
+        assert_eq!(
+            statements[3].text,
+            "/* a block { comment */\nlist.forEach(x -> { go(x); });"
+        );
+
+        let bodies: Vec<Vec<&str>> = statements[4..]
+            .iter()
+            .map(|s| {
+                s.body
+                    .as_ref()
+                    .unwrap()
+                    .iter()
+                    .map(|s| s.code.as_str())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(bodies, [["x()"], ["y()"], ["z()"]]);
+        assert_eq!(statements[5].text, "else if (b) {\n    y(); // }\n}");
+    }
+}
@@ -0,0 +1,268 @@
+use crate::app::java::{INDENT, USER_CODE_BEGIN, USER_CODE_END};
+
+/// A sequence of Java statements or class members, stored without indentation so it can be
+/// nested anywhere and still come out consistently indented.
+///
+/// Code from Lua modules and older generators arrives as text with whatever indentation its
+/// author used; [`Block::code`] throws that away and re-indents it from its braces.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Block {
+    lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Line {
+    depth: usize,
+    text: String,
+}
+
+impl Block {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(|line| line.text.is_empty())
+    }
+
+    /// Add a single line, e.g. `telemetry.update();`
+    pub fn line(&mut self, text: impl Into<String>) -> &mut Self {
+        self.lines.push(Line {
+            depth: 0,
+            text: text.into().trim().to_string(),
+        });
+        self
+    }
+
+    /// Add an empty line. Runs of empty lines, and empty lines at the start or end of a block
+    /// or just inside braces, are dropped when the block is rendered.
+    pub fn blank(&mut self) -> &mut Self {
+        self.line("")
+    }
+
+    /// Add free-form Java, re-indenting it by counting braces
+    pub fn code(&mut self, code: &str) -> &mut Self {
+        let mut depth: usize = 0;
+
+        for text in code.lines().map(str::trim) {
+            let (closes, opens) = brace_balance(text);
+
+            // a line starting with `}` belongs to the level it closes
+            let leading_closes = text.chars().take_while(|c| *c == '}').count();
+            let line_depth = depth.saturating_sub(leading_closes.min(closes));
+
+            let continuation = [".", "+ ", "- ", "&&", "||", "? ", ": "]
+                .iter()
+                .any(|prefix| text.starts_with(prefix));
+
+            self.lines.push(Line {
+                depth: line_depth + usize::from(continuation),
+                text: text.to_string(),
+            });
+
+            depth = (depth + opens).saturating_sub(closes);
+        }
+
+        self
+    }
+
+    /// Add `header {`, the contents of `body` one level deeper, and `}`
+    pub fn nest(&mut self, header: &str, body: &Block) -> &mut Self {
+        self.line(format!("{} {{", header.trim()));
+        self.append_indented(body, 1);
+        self.line("}")
+    }
+
+    /// Add an empty user code region called `name`, for the user to write their own code in
+    pub fn user_code(&mut self, name: &str) -> &mut Self {
+        self.line(format!("{} {}", USER_CODE_BEGIN, name))
+            .line(USER_CODE_END)
+    }
+
+    /// Add the contents of `other`, separated from what's already here by an empty line.
+    /// Does nothing if `other` is empty.
+    pub fn section(&mut self, other: &Block) -> &mut Self {
+        if !other.is_empty() {
+            self.blank();
+            self.append_indented(other, 0);
+        }
+        self
+    }
+
+    /// Add the contents of `other` directly after what's already here
+    pub fn append(&mut self, other: &Block) -> &mut Self {
+        self.append_indented(other, 0);
+        self
+    }
+
+    /// Split into its top-level statements or members, each with the empty lines before it.
+    /// Comments on lines of their own are split off as well.
+    pub fn top_level(&self) -> Vec<Block> {
+        let mut items = vec![];
+        let mut item = Block::new();
+
+        for line in &self.lines {
+            item.lines.push(line.clone());
+
+            let ends = line.depth == 0
+                && (line.text.starts_with("//")
+                    || line.text.ends_with(';')
+                    || line.text.ends_with('}'));
+            if ends {
+                items.push(std::mem::take(&mut item));
+            }
+        }
+
+        if !item.is_empty() {
+            items.push(item);
+        }
+        items
+    }
+
+    fn append_indented(&mut self, other: &Block, depth: usize) {
+        self.lines.extend(other.lines.iter().map(|line| Line {
+            depth: line.depth + depth,
+            text: line.text.clone(),
+        }));
+    }
+
+    /// Render the block with every line indented by at least `depth` levels
+    pub fn render(&self, depth: usize) -> String {
+        let mut out = String::new();
+        let mut pending_blank = false;
+        let mut previous: Option<&str> = None;
+
+        for line in &self.lines {
+            if line.text.is_empty() {
+                // only worth keeping between two lines, and not just after an opening brace
+                pending_blank = previous.is_some_and(|text| !text.ends_with('{'));
+                continue;
+            }
+
+            if pending_blank && !line.text.starts_with('}') {
+                out += "\n";
+            }
+            pending_blank = false;
+
+            out += &INDENT.repeat(depth + line.depth);
+            out += &line.text;
+            out += "\n";
+
+            previous = Some(&line.text);
+        }
+
+        out
+    }
+}
+
+/// Count the `}` and `{` in a line of Java, ignoring any inside strings, character literals
+/// and comments
+fn brace_balance(line: &str) -> (usize, usize) {
+    let mut closes = 0;
+    let mut opens = 0;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                while let Some(inner) = chars.next() {
+                    if inner == '\\' {
+                        chars.next();
+                    } else if inner == c {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => break,
+            '{' => opens += 1,
+            '}' => {
+                // a `}` closing a brace opened earlier on the same line cancels out
+                if opens > 0 {
+                    opens -= 1;
+                } else {
+                    closes += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (closes, opens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reindents_code_from_its_braces() {
+        let mut block = Block::new();
+        block.code(
+            "if (gamepad1.a) {\n            arm.setPower(1);\n} else {\n\t\tarm.setPower(0);\n   }",
+        );
+
+        assert_eq!(
+            block.render(1),
+            "    if (gamepad1.a) {\n        arm.setPower(1);\n    } else {\n        arm.setPower(0);\n    }\n"
+        );
+    }
+
+    #[test]
+    fn ignores_braces_in_strings_and_comments() {
+        let mut block = Block::new();
+        block.code(
+            "telemetry.addData(\"{\", '}');\n\
+             // if (x) {\n\
+             int[] ports = {0, 1};\n\
+             arm.setPower(0);",
+        );
+
+        assert_eq!(
+            block.render(0),
+            "telemetry.addData(\"{\", '}');\n// if (x) {\nint[] ports = {0, 1};\narm.setPower(0);\n"
+        );
+    }
+
+    #[test]
+    fn indents_continuation_lines() {
+        let mut block = Block::new();
+        block.code("double power = gamepad1.left_stick_y\n* 0.5\n+ offset;");
+
+        // only lines starting with a known continuation are indented
+        assert_eq!(
+            block.render(0),
+            "double power = gamepad1.left_stick_y\n* 0.5\n    + offset;\n"
+        );
+    }
+
+    #[test]
+    fn drops_needless_blank_lines() {
+        let mut body = Block::new();
+        body.blank()
+            .line("a();")
+            .blank()
+            .blank()
+            .line("b();")
+            .blank();
+        let mut block = Block::new();
+        block.blank().nest("void f()", &body).blank();
+
+        assert_eq!(block.render(0), "void f() {\n    a();\n\n    b();\n}\n");
+    }
+
+    #[test]
+    fn sections_are_separated_and_empty_ones_skipped() {
+        let mut block = Block::new();
+        block
+            .line("a();")
+            .section(&Block::new())
+            .section(Block::new().line("b();"))
+            .user_code("loop");
+
+        assert!(!block.is_empty());
+        assert_eq!(
+            block.render(0),
+            "a();\n\nb();\n// BEGIN USER CODE loop\n// END USER CODE\n"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use crate::app::java::block::Block;
+use crate::app::java::imports::Imports;
+use crate::app::java::{USER_CODE_BEGIN, USER_CODE_END};
+
+/// A whole `.java` file: package, imports and one top-level class
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompilationUnit {
+    pub package: String,
+    pub imports: Imports,
+    pub class: Class,
+}
+
+/// A class declaration. Members are written in order: fields, methods, then any free-form
+/// members added by generators.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Class {
+    pub annotations: Vec<String>,
+    /// Everything before the opening brace, e.g. `public class MyBot extends LinearOpMode`
+    pub declaration: String,
+    pub fields: Block,
+    pub methods: Vec<Method>,
+    pub members: Block,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Method {
+    pub annotations: Vec<String>,
+    /// Everything before the opening brace, e.g. `public void runOpMode()`
+    pub signature: String,
+    pub body: Block,
+}
+
+impl CompilationUnit {
+    pub fn new(package: &str, class: Class) -> Self {
+        Self {
+            package: package.to_string(),
+            imports: Imports::new(),
+            class,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!("package {};\n", self.package);
+
+        if !self.imports.is_empty() {
+            out += "\n";
+            out += &self.imports.render();
+        }
+        out += &format!("{} imports\n{}\n", USER_CODE_BEGIN, USER_CODE_END);
+
+        out += "\n";
+        out += &self.class.to_block().render(0);
+
+        out
+    }
+}
+
+impl Class {
+    pub fn new(declaration: &str) -> Self {
+        Self {
+            annotations: vec![],
+            declaration: declaration.to_string(),
+            fields: Block::new(),
+            methods: vec![],
+            members: Block::new(),
+        }
+    }
+
+    pub fn annotate(&mut self, annotation: &str) -> &mut Self {
+        self.annotations.push(annotation.to_string());
+        self
+    }
+
+    pub fn to_block(&self) -> Block {
+        let mut body = Block::new();
+
+        body.section(&self.fields);
+        for method in &self.methods {
+            body.section(&method.to_block());
+        }
+        body.section(&self.members);
+
+        let mut block = Block::new();
+        for annotation in &self.annotations {
+            block.line(annotation.as_str());
+        }
+        block.nest(&self.declaration, &body);
+
+        block
+    }
+}
+
+impl Method {
+    pub fn new(signature: &str) -> Self {
+        Self {
+            annotations: vec![],
+            signature: signature.to_string(),
+            body: Block::new(),
+        }
+    }
+
+    pub fn annotate(&mut self, annotation: &str) -> &mut Self {
+        self.annotations.push(annotation.to_string());
+        self
+    }
+
+    pub fn to_block(&self) -> Block {
+        let mut block = Block::new();
+
+        for annotation in &self.annotations {
+            block.line(annotation.as_str());
+        }
+        block.nest(&self.signature, &self.body);
+
+        block
+    }
+}
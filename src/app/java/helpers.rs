@@ -0,0 +1,69 @@
+use crate::app::java::block::Block;
+use crate::app::java::class::Class;
+
+/// A method generated code can call without defining it. It's added to the class once, however
+/// many components use it.
+pub struct Helper {
+    pub name: &'static str,
+    pub code: &'static str,
+}
+
+/// Every helper, in the order they're added to a class
+pub const HELPERS: &[Helper] = &[
+    Helper {
+        name: "shapeAxis",
+        code: "\
+// Shape a joystick axis: values within the deadband read as 0 and the rest is stretched back
+// to the full range, then bent from a linear (expo 0) towards a cubic (expo 1) response and
+// scaled.
+private double shapeAxis(double value, double deadband, double expo, double scale) {
+    if (Math.abs(value) <= deadband) {
+        return 0;
+    }
+    value = Math.signum(value) * (Math.abs(value) - deadband) / (1 - deadband);
+    value = (1 - expo) * value + expo * value * value * value;
+    return value * scale;
+}
+",
+    },
+    Helper {
+        name: "slewLimit",
+        code: "\
+// Move current towards target by no more than maxPerSecond for each second since the timer
+// was last reset, then reset the timer.
+private double slewLimit(double current, double target, double maxPerSecond, ElapsedTime timer) {
+    double maxStep = maxPerSecond * timer.seconds();
+    timer.reset();
+    return current + Range.clip(target - current, -maxStep, maxStep);
+}
+",
+    },
+    Helper {
+        name: "profileVelocity",
+        code: "\
+// The next velocity of a trapezoidal motion profile that is toGo short of its goal: speed up by
+// up to maxAcceleration per second towards maxVelocity, and slow down in time to stop on the
+// goal.
+private double profileVelocity(double toGo, double velocity, double maxVelocity, double maxAcceleration, double dt) {
+    double stoppingVelocity = Math.sqrt(2 * maxAcceleration * Math.abs(toGo));
+    double wanted = Math.signum(toGo) * Math.min(maxVelocity, stoppingVelocity);
+    double maxStep = maxAcceleration * dt;
+    return velocity + Range.clip(wanted - velocity, -maxStep, maxStep);
+}
+",
+    },
+];
+
+/// Add the helpers `class` calls but doesn't define to its members
+pub fn add_used(class: &mut Class) {
+    let code = class.to_block().render(0);
+
+    for helper in HELPERS {
+        let called = code.contains(&format!("{}(", helper.name));
+        let defined = code.contains(&format!(" {}(double", helper.name));
+
+        if called && !defined {
+            class.members.section(Block::new().code(helper.code));
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeSet;
+
+/// The imports of a Java file. Duplicates are dropped, and imports are written sorted and
+/// grouped by top-level package (`android`, `com`, `java`, `org`, ...) with static imports last.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Imports {
+    imports: BTreeSet<String>,
+    static_imports: BTreeSet<String>,
+}
+
+impl Imports {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Import a class by its fully qualified name, e.g. `com.qualcomm.robotcore.hardware.DcMotor`
+    pub fn add(&mut self, name: &str) -> &mut Self {
+        self.imports.insert(name.trim().to_string());
+        self
+    }
+
+    /// Add an `import static`, e.g. `org.firstinspires.ftc.teamcode.RobotHardware.*`
+    pub fn add_static(&mut self, name: &str) -> &mut Self {
+        self.static_imports.insert(name.trim().to_string());
+        self
+    }
+
+    /// Add every `import` statement in `code`, returning the lines that aren't imports
+    pub fn parse(&mut self, code: &str) -> String {
+        let mut rest = String::new();
+
+        for line in code.lines().map(str::trim) {
+            match parse_import(line) {
+                Some((name, true)) => {
+                    self.add_static(name);
+                }
+                Some((name, false)) => {
+                    self.add(name);
+                }
+                None if !line.is_empty() => {
+                    rest += line;
+                    rest += "\n";
+                }
+                None => {}
+            }
+        }
+
+        rest
+    }
+
+    pub fn extend(&mut self, other: &Imports) {
+        self.imports.extend(other.imports.iter().cloned());
+        self.static_imports
+            .extend(other.static_imports.iter().cloned());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.imports.is_empty() && self.static_imports.is_empty()
+    }
+
+    pub fn render(&self) -> String {
+        let mut groups: Vec<String> = vec![];
+        let mut group = String::new();
+        let mut last_package = "";
+
+        for name in &self.imports {
+            let package = name.split('.').next().unwrap_or_default();
+            if package != last_package && !group.is_empty() {
+                groups.push(std::mem::take(&mut group));
+            }
+            last_package = package;
+            group += &format!("import {};\n", name);
+        }
+        if !group.is_empty() {
+            groups.push(group);
+        }
+
+        if !self.static_imports.is_empty() {
+            groups.push(
+                self.static_imports
+                    .iter()
+                    .map(|name| format!("import static {};\n", name))
+                    .collect(),
+            );
+        }
+
+        groups.join("\n")
+    }
+}
+
+/// Split `import [static] a.b.C;` into the imported name and whether it's static
+fn parse_import(line: &str) -> Option<(&str, bool)> {
+    let name = line
+        .strip_prefix("import ")?
+        .trim()
+        .strip_suffix(';')?
+        .trim();
+
+    match name.strip_prefix("static ") {
+        Some(name) => Some((name.trim(), true)),
+        None => Some((name, false)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_duplicates_and_groups_by_package() {
+        let mut imports = Imports::new();
+        imports
+            .add("com.qualcomm.robotcore.hardware.DcMotor")
+            .add_static("org.firstinspires.ftc.teamcode.RobotHardware.*")
+            .add("java.util.List")
+            .add(" com.qualcomm.robotcore.hardware.DcMotor ")
+            .add("com.qualcomm.robotcore.hardware.Servo");
+
+        assert_eq!(
+            imports.render(),
+            "import com.qualcomm.robotcore.hardware.DcMotor;\n\
+             import com.qualcomm.robotcore.hardware.Servo;\n\
+             \n\
+             import java.util.List;\n\
+             \n\
+             import static org.firstinspires.ftc.teamcode.RobotHardware.*;\n"
+        );
+    }
+
+    #[test]
+    fn parses_imports_out_of_code() {
+        let mut imports = Imports::new();
+        let rest = imports.parse(
+            "import com.qualcomm.robotcore.hardware.Servo;\n\
+             \n\
+             // Error in Servo.lua\n\
+             import static java.lang.Math.abs;\n",
+        );
+
+        assert_eq!(rest, "// Error in Servo.lua\n");
+        assert_eq!(
+            imports.render(),
+            "import com.qualcomm.robotcore.hardware.Servo;\n\nimport static java.lang.Math.abs;\n"
+        );
+
+        let mut merged = Imports::new();
+        merged
+            .add("com.qualcomm.robotcore.hardware.Servo")
+            .extend(&imports);
+        assert_eq!(merged, imports);
+    }
+}
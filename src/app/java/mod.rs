@@ -0,0 +1,37 @@
+//! A small model of Java source that generated code is assembled from, so output is
+//! consistently indented and deterministic no matter how each generator formats its text.
+
+pub mod block;
+pub mod class;
+pub mod helpers;
+pub mod imports;
+pub mod opmode;
+pub mod robot_hardware;
+
+/// One level of indentation in generated Java
+pub const INDENT: &str = "    ";
+
+/// Opens a user code region, followed by the region's name. Code the user writes between this
+/// and [`USER_CODE_END`] is kept when the file is generated again.
+pub const USER_CODE_BEGIN: &str = "// BEGIN USER CODE";
+pub const USER_CODE_END: &str = "// END USER CODE";
+
+/// A generated `.java` file, holding one class
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaFile {
+    pub class_name: String,
+    pub code: String,
+}
+
+impl JavaFile {
+    pub fn new(class_name: &str, code: String) -> Self {
+        Self {
+            class_name: class_name.to_string(),
+            code,
+        }
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}.java", self.class_name)
+    }
+}
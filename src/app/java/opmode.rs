@@ -0,0 +1,223 @@
+use crate::app::java::block::Block;
+use crate::app::java::class::{Class, CompilationUnit, Method};
+use crate::app::java::helpers;
+use crate::app::java::imports::Imports;
+use crate::app::java::robot_hardware::ROBOT_HARDWARE_CLASS;
+
+pub const TEAMCODE_PACKAGE: &str = "org.firstinspires.ftc.teamcode";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OpModeKind {
+    #[default]
+    TeleOp,
+    Autonomous,
+}
+
+/// The sections of a generated OpMode that generators add their code to. The OpMode extends the
+/// project's [`RobotHardware`](crate::app::java::robot_hardware::RobotHardware) class, which
+/// the code for the devices is moved to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpMode {
+    /// Robot name the class and the name shown on the Driver Station are made from
+    pub name: String,
+    pub kind: OpModeKind,
+    pub imports: Imports,
+    pub fields: Block,
+    /// Runs once when INIT is pressed
+    pub init: Block,
+    /// Runs at the top of every loop iteration, before `loop_body`
+    pub loop_one_time_setup: Block,
+    pub loop_body: Block,
+    /// `telemetry.addData` lines at the end of every loop iteration
+    pub telemetry: Block,
+    /// Runs once after START in autonomous OpModes, instead of the loop
+    pub run: Block,
+    /// Extra methods and other class members
+    pub members: Block,
+}
+
+impl OpMode {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Add the `import` statements in `code`. Anything else, such as an error comment from a
+    /// broken module, is kept as a comment at the top of the class so it isn't lost.
+    pub fn add_includes(&mut self, code: &str) {
+        let rest = self.imports.parse(code);
+        self.fields.code(&rest);
+    }
+
+    pub fn autonomous(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: OpModeKind::Autonomous,
+            ..Default::default()
+        }
+    }
+
+    /// Name of the generated class, and so of its `.java` file
+    pub fn class_name(&self) -> String {
+        match self.kind {
+            OpModeKind::TeleOp => self.name.clone(),
+            OpModeKind::Autonomous => format!("{}Auto", self.name),
+        }
+    }
+
+    pub fn to_compilation_unit(&self) -> CompilationUnit {
+        let mut class = Class::new(&format!(
+            "public class {} extends {}",
+            self.class_name(),
+            ROBOT_HARDWARE_CLASS
+        ));
+        class.annotate(&match self.kind {
+            OpModeKind::TeleOp => format!(
+                r#"@TeleOp(name="{} Teleop", group="Linear Opmode")"#,
+                self.name
+            ),
+            OpModeKind::Autonomous => format!(
+                r#"@Autonomous(name="{} Auto", group="Linear Opmode")"#,
+                self.name
+            ),
+        });
+
+        class
+            .fields
+            .line("private ElapsedTime runtime = new ElapsedTime();")
+            .section(&self.fields)
+            .section(Block::new().user_code("fields"));
+
+        let mut loop_body = Block::new();
+        loop_body
+            .section(&self.loop_one_time_setup)
+            .section(&self.loop_body)
+            .section(Block::new().user_code("loop"))
+            .section(
+                Block::new()
+                    .append(&self.telemetry)
+                    .line("telemetry.update();"),
+            );
+
+        let mut run_op_mode = Method::new("public void runOpMode()");
+        run_op_mode.annotate("@Override");
+        run_op_mode
+            .body
+            .line(r#"telemetry.addData("Status", "Initialized");"#)
+            .line("telemetry.update();")
+            .section(
+                Block::new()
+                    .line("initHardware();")
+                    .append(&self.init)
+                    .user_code("init"),
+            )
+            .section(Block::new().line("waitForStart();"))
+            .section(
+                Block::new()
+                    .line("// Reset the timer (stopwatch) because we only care about time since the game")
+                    .line("// actually starts")
+                    .line("runtime.reset();"),
+            )
+            .section(Block::new().user_code("start"))
+            .blank();
+
+        match self.kind {
+            OpModeKind::TeleOp => {
+                run_op_mode
+                    .body
+                    .nest("while (opModeIsActive())", &loop_body);
+            }
+            OpModeKind::Autonomous => {
+                run_op_mode.body.append(&self.run);
+            }
+        }
+
+        class.methods.push(run_op_mode);
+        class.members = self.members.clone();
+        class.members.section(Block::new().user_code("members"));
+        helpers::add_used(&mut class);
+
+        let mut unit = CompilationUnit::new(TEAMCODE_PACKAGE, class);
+        unit.imports
+            .add(match self.kind {
+                OpModeKind::TeleOp => "com.qualcomm.robotcore.eventloop.opmode.TeleOp",
+                OpModeKind::Autonomous => "com.qualcomm.robotcore.eventloop.opmode.Autonomous",
+            })
+            .add("com.qualcomm.robotcore.util.ElapsedTime")
+            .add("com.qualcomm.robotcore.util.Range")
+            .extend(&self.imports);
+
+        unit
+    }
+
+    pub fn render(&self) -> String {
+        self.to_compilation_unit().render()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::java::USER_CODE_BEGIN;
+
+    fn teleop() -> OpMode {
+        let mut opmode = OpMode::new("MyBot");
+        opmode.add_includes("import com.qualcomm.robotcore.hardware.DcMotor;\n");
+        opmode
+            .loop_body
+            .code("if (gamepad1.a) {\narm.setPower(1);\n}");
+        opmode
+            .telemetry
+            .line(r#"telemetry.addData("Arm", arm.getPower());"#);
+        opmode
+    }
+
+    #[test]
+    fn renders_a_teleop() {
+        let code = teleop().render();
+
+        assert!(code.starts_with("package org.firstinspires.ftc.teamcode;\n"));
+        assert!(code.contains("import com.qualcomm.robotcore.hardware.DcMotor;\n"));
+        assert!(code.contains("import com.qualcomm.robotcore.eventloop.opmode.TeleOp;\n"));
+        assert!(code.contains(
+            "@TeleOp(name=\"MyBot Teleop\", group=\"Linear Opmode\")\n\
+             public class MyBot extends RobotHardware {\n"
+        ));
+        assert!(code.contains(
+            "        while (opModeIsActive()) {\n\
+             \x20           if (gamepad1.a) {\n\
+             \x20               arm.setPower(1);\n\
+             \x20           }\n\
+             \n\
+             \x20           // BEGIN USER CODE loop\n\
+             \x20           // END USER CODE\n\
+             \n\
+             \x20           telemetry.addData(\"Arm\", arm.getPower());\n\
+             \x20           telemetry.update();\n\
+             \x20       }\n"
+        ));
+        for region in ["imports", "fields", "init", "start", "loop", "members"] {
+            assert!(code.contains(&format!("{} {}\n", USER_CODE_BEGIN, region)));
+        }
+    }
+
+    #[test]
+    fn renders_the_same_code_every_time() {
+        assert_eq!(teleop().render(), teleop().render());
+    }
+
+    #[test]
+    fn renders_an_autonomous_without_a_loop() {
+        let mut opmode = OpMode::autonomous("MyBot");
+        opmode.run.line("driveInches(24);");
+        let code = opmode.render();
+
+        assert_eq!(opmode.class_name(), "MyBotAuto");
+        assert!(code.contains("public class MyBotAuto extends RobotHardware {\n"));
+        assert!(code.contains("@Autonomous(name=\"MyBot Auto\", group=\"Linear Opmode\")"));
+        assert!(code.contains("        driveInches(24);\n"));
+        assert!(!code.contains("opModeIsActive"));
+    }
+}
@@ -0,0 +1,191 @@
+use crate::app::java::block::Block;
+use crate::app::java::class::{Class, CompilationUnit, Method};
+use crate::app::java::helpers;
+use crate::app::java::imports::Imports;
+use crate::app::java::opmode::{OpMode, TEAMCODE_PACKAGE};
+
+/// Name of the generated hardware class, and so of its `.java` file
+pub const ROBOT_HARDWARE_CLASS: &str = "RobotHardware";
+
+/// The robot's devices and their setup, shared by every OpMode generated from a project.
+///
+/// It's an abstract `LinearOpMode` the OpModes extend, so the code components generate for the
+/// loop reads each device as one of the OpMode's own fields, just as it would in a single class.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotHardware {
+    pub imports: Imports,
+    /// Each field declaration, kept whole so they can be compared with another robot's
+    pub fields: Vec<Block>,
+    /// Runs from `initHardware()`, when INIT is pressed
+    pub init: Block,
+    /// Methods components add, available to every OpMode
+    pub members: Block,
+}
+
+impl RobotHardware {
+    /// Move the code components generated into `opmode` for its fields, init and methods out
+    /// into a hardware class, leaving the loop code. The OpMode keeps a copy of the imports,
+    /// since its loop uses the same classes.
+    pub fn take_from(opmode: &mut OpMode) -> Self {
+        Self {
+            imports: opmode.imports.clone(),
+            fields: std::mem::take(&mut opmode.fields).top_level(),
+            init: std::mem::take(&mut opmode.init),
+            members: std::mem::take(&mut opmode.members),
+        }
+    }
+
+    pub fn to_compilation_unit(&self) -> CompilationUnit {
+        let mut class = Class::new(&format!(
+            "public abstract class {} extends LinearOpMode",
+            ROBOT_HARDWARE_CLASS
+        ));
+        class
+            .annotate(
+                "// The robot's devices, found in the hardware map and set up by initHardware().",
+            )
+            .annotate("// Every OpMode generated from this project extends this class.");
+
+        class.fields = inheritable(&joined(&self.fields));
+        class.fields.section(Block::new().user_code("fields"));
+
+        let mut init = Method::new("protected void initHardware()");
+        init.body
+            .append(&self.init)
+            .section(Block::new().user_code("init"));
+        class.methods.push(init);
+
+        class.members = inheritable(&self.members);
+        class.members.section(Block::new().user_code("members"));
+        helpers::add_used(&mut class);
+
+        let mut unit = CompilationUnit::new(TEAMCODE_PACKAGE, class);
+        unit.imports
+            .add("com.qualcomm.robotcore.eventloop.opmode.LinearOpMode")
+            .add("com.qualcomm.robotcore.util.ElapsedTime")
+            .add("com.qualcomm.robotcore.util.Range")
+            .extend(&self.imports);
+
+        unit
+    }
+
+    /// The fields declared here but not by `shared`, such as the state of a toggle only one
+    /// TeleOp binds, for that TeleOp to declare itself
+    pub fn fields_not_in(&self, shared: &RobotHardware) -> Block {
+        let shared_fields: Vec<String> =
+            shared.fields.iter().map(|field| field.render(0)).collect();
+
+        let fields: Vec<Block> = self
+            .fields
+            .iter()
+            .filter(|field| !shared_fields.contains(&field.render(0)))
+            .cloned()
+            .collect();
+        joined(&fields)
+    }
+
+    pub fn render(&self) -> String {
+        self.to_compilation_unit().render()
+    }
+}
+
+fn joined(blocks: &[Block]) -> Block {
+    let mut joined = Block::new();
+    for block in blocks {
+        joined.append(block);
+    }
+    joined
+}
+
+/// `block` with its top-level `private` members made `protected`, so the OpModes extending the
+/// hardware class can use them
+fn inheritable(block: &Block) -> Block {
+    let code: Vec<String> = block
+        .render(0)
+        .lines()
+        .map(|line| match line.strip_prefix("private ") {
+            Some(rest) => format!("protected {}", rest),
+            None => line.to_string(),
+        })
+        .collect();
+
+    let mut inheritable = Block::new();
+    inheritable.code(&code.join("\n"));
+    inheritable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hardware with an arm motor and `fields` after it
+    fn hardware(fields: &str) -> RobotHardware {
+        let mut opmode = OpMode::new("MyBot");
+        opmode
+            .fields
+            .line("// armMotor")
+            .line("private DcMotorEx armMotor = null;")
+            .blank()
+            .code(fields);
+        RobotHardware::take_from(&mut opmode)
+    }
+
+    #[test]
+    fn takes_everything_but_the_loop_from_an_opmode() {
+        let mut opmode = OpMode::new("MyBot");
+        opmode
+            .imports
+            .add("com.qualcomm.robotcore.hardware.DcMotorEx");
+        opmode
+            .fields
+            .line("private DcMotorEx armMotor = null;")
+            .code("private double[] armPositions = {\n0,\n100\n};");
+        opmode
+            .init
+            .line(r#"armMotor = hardwareMap.get(DcMotorEx.class, "armMotor");"#);
+        opmode.members.nest(
+            "private void stopArm()",
+            Block::new().line("armMotor.setPower(0);"),
+        );
+        opmode
+            .loop_body
+            .line("armMotor.setPower(gamepad1.left_stick_y);");
+
+        let hardware = RobotHardware::take_from(&mut opmode);
+
+        assert_eq!(hardware.fields.len(), 2);
+        assert_eq!(
+            hardware.fields[1].render(0),
+            "private double[] armPositions = {\n    0,\n    100\n};\n"
+        );
+        assert!(opmode.fields.is_empty() && opmode.init.is_empty() && opmode.members.is_empty());
+        assert_eq!(opmode.imports, hardware.imports);
+        assert!(!opmode.loop_body.is_empty());
+
+        let code = hardware.render();
+        assert!(code.contains("public abstract class RobotHardware extends LinearOpMode {"));
+        assert!(code.contains("    protected DcMotorEx armMotor = null;\n"));
+        assert!(
+            code.contains("    protected void initHardware() {\n        armMotor = hardwareMap")
+        );
+        assert!(code.contains("    protected void stopArm() {\n"));
+    }
+
+    #[test]
+    fn declares_only_the_fields_the_shared_hardware_lacks() {
+        let shared =
+            hardware("private boolean armToggle = false;\nprivate int[] presets = {\n0,\n100\n};");
+        let teleop = hardware(
+            "private boolean armToggle = false;\n\
+             private boolean clawToggle = false;\n\
+             private int[] presets = {\n0,\n200\n};",
+        );
+
+        assert_eq!(
+            teleop.fields_not_in(&shared).render(0),
+            "private boolean clawToggle = false;\n\
+             private int[] presets = {\n    0,\n    200\n};\n"
+        );
+        assert!(shared.fields_not_in(&shared).is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::app::generators::autonomous::routine::Autonomous;
+use crate::app::generators::control::Control;
+use crate::app::generators::lua_generator::LuaGenerator;
+use crate::app::generators::module_library;
+use crate::app::generators::subsystem::subsystem::Subsystem;
+use crate::app::generators::telemetry::Telemetry;
+use crate::app::teleop_profile::TeleOpProfile;
+use crate::app::user_code::UserCode;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// File extension used for saved robot projects
+pub const PROJECT_EXTENSION: &str = "ftcreate";
+
+/// Bump this whenever the on-disk layout changes in a way older builds can't read.
+///
+/// 1. Subsystems and their components
+/// 2. TeleOp profiles, the autonomous routine, telemetry and user code
+pub const PROJECT_VERSION: u32 = 2;
+
+/// A robot configuration as it is written to a `.ftcreate` file.
+///
+/// Only what's needed to rebuild the robot is stored: the script each component was created from
+/// and the values of its controls. Lua state is rebuilt from those when the project is opened.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Project {
+    pub version: u32,
+    /// The first TeleOp's name, which is all older projects have
+    pub file_name: String,
+    #[serde(default)]
+    pub profiles: Vec<TeleOpProfile>,
+    pub drivetrain: SubsystemFile,
+    pub subsystems: Vec<SubsystemFile>,
+    #[serde(default)]
+    pub autonomous: Autonomous,
+    #[serde(default)]
+    pub telemetry: Telemetry,
+    #[serde(default)]
+    pub user_code: UserCode,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct SubsystemFile {
+    pub name: String,
+    pub components: Vec<ComponentFile>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ComponentFile {
+    pub script: String,
+    pub controls: Vec<Control>,
+}
+
+#[derive(Debug)]
+pub enum ProjectError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::Io(e) => write!(f, "{}", e),
+            ProjectError::Format(e) => write!(f, "Not a valid FTCreate project: {}", e),
+            ProjectError::UnsupportedVersion(v) => write!(
+                f,
+                "Project was saved by a newer FTCreate (file version {}, this build reads up to {})",
+                v, PROJECT_VERSION
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for ProjectError {
+    fn from(e: std::io::Error) -> Self {
+        ProjectError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ProjectError {
+    fn from(e: serde_json::Error) -> Self {
+        ProjectError::Format(e)
+    }
+}
+
+impl Project {
+    pub fn load(path: &Path) -> Result<Self, ProjectError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ProjectError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ProjectError> {
+        Self::from_json_up_to(json, PROJECT_VERSION)
+    }
+
+    /// Read a project the way a build that reads up to file version `supported` does
+    fn from_json_up_to(json: &str, supported: u32) -> Result<Self, ProjectError> {
+        let project: Project = serde_json::from_str(json)?;
+
+        if project.version > supported {
+            return Err(ProjectError::UnsupportedVersion(project.version));
+        }
+
+        Ok(project)
+    }
+
+    pub fn to_json(&self) -> Result<String, ProjectError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl SubsystemFile {
+    pub fn from_subsystem(subsystem: &Subsystem) -> Self {
+        Self {
+            name: subsystem.name.clone(),
+            components: subsystem
+                .control_handler
+                .generators
+                .iter()
+                .map(|generator| ComponentFile {
+                    script: generator.script.clone(),
+                    controls: generator.controls.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Rebuild a subsystem, offering `scripts` as the modules available to add to it
+    pub fn to_subsystem(&self, scripts: &[String]) -> Subsystem {
+        let mut subsystem = Subsystem::new(self.name.clone());
+
+        subsystem.control_handler.scripts = scripts.to_vec();
+        subsystem.control_handler.generators = self
+            .components
+            .iter()
+            .map(|component| {
+                LuaGenerator::with_controls(
+                    &module_library::resolve_script(&component.script, scripts),
+                    component.controls.clone(),
+                )
+            })
+            .collect();
+
+        subsystem
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::TemplateApp;
+
+    /// A project as the first version of FTCreate to save projects wrote it, before TeleOp
+    /// profiles, autonomous routines, telemetry and user code
+    const VERSION_1: &str = r#"{
+        "version": 1,
+        "file_name": "MyBot",
+        "drivetrain": { "name": "Drivetrain", "components": [] },
+        "subsystems": [{
+            "name": "Arm",
+            "components": [{
+                "script": "./lua_modules/DC_Motor.lua",
+                "controls": [
+                    { "TextInputType": { "name": "DCM_Name", "value": "armMotor", "label": "Name" } },
+                    { "CheckboxType": { "name": "DCM_IsDrivetrain", "value": false, "label": "Drive" } }
+                ]
+            }]
+        }]
+    }"#;
+
+    #[test]
+    fn reads_projects_saved_before_later_features() {
+        let project = Project::from_json(VERSION_1).unwrap();
+
+        assert_eq!(project.file_name, "MyBot");
+        assert!(project.profiles.is_empty());
+        assert!(project.autonomous.steps.is_empty());
+        assert_eq!(project.subsystems[0].components[0].controls.len(), 2);
+    }
+
+    #[test]
+    fn names_the_teleop_of_an_old_project_after_the_file() {
+        let mut app = TemplateApp::default();
+        app.apply_project(&Project::from_json(VERSION_1).unwrap());
+
+        let project = app.to_project();
+
+        assert_eq!(project.profiles.len(), 1);
+        assert_eq!(project.profiles[0].name, "MyBot");
+        assert_eq!(project.file_name, "MyBot");
+    }
+
+    #[test]
+    fn saves_and_opens_a_project_unchanged() {
+        let mut app = TemplateApp::default();
+        app.apply_project(&Project::from_json(VERSION_1).unwrap());
+        let project = app.to_project();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(format!("MyBot.{}", PROJECT_EXTENSION));
+        project.save(&path).unwrap();
+        let opened = Project::load(&path).unwrap();
+
+        assert_eq!(opened.to_json().unwrap(), project.to_json().unwrap());
+        assert_eq!(opened.version, PROJECT_VERSION);
+    }
+
+    #[test]
+    fn refuses_projects_from_a_newer_version() {
+        let json = VERSION_1.replace("\"version\": 1", "\"version\": 99");
+
+        assert!(matches!(
+            Project::from_json(&json),
+            Err(ProjectError::UnsupportedVersion(99))
+        ));
+    }
+
+    #[test]
+    fn builds_that_read_version_1_refuse_saved_projects() {
+        let json = TemplateApp::default().to_project().to_json().unwrap();
+
+        assert!(Project::from_json_up_to(VERSION_1, 1).is_ok());
+        assert!(matches!(
+            Project::from_json_up_to(&json, 1),
+            Err(ProjectError::UnsupportedVersion(PROJECT_VERSION))
+        ));
+    }
+
+    #[test]
+    fn refuses_files_that_arent_projects() {
+        assert!(matches!(
+            Project::from_json(r#"{ "label": "FTCreate" }"#),
+            Err(ProjectError::Format(_))
+        ));
+        assert!(matches!(
+            Project::load(Path::new("/nonexistent/MyBot.ftcreate")),
+            Err(ProjectError::Io(_))
+        ));
+    }
+}
@@ -22,6 +22,28 @@ pub fn code_view_ui(ui: &mut egui::Ui, mut code: &str, width: f32) {
     );
 }
 
+/// Edit some code with syntax highlighting
+pub fn code_editor_ui(ui: &mut egui::Ui, code: &mut String, width: f32) -> egui::Response {
+    let language = "java";
+    let theme = CodeTheme::from_memory(ui.ctx());
+
+    let mut layouter = |ui: &egui::Ui, string: &str, _wrap_width: f32| {
+        let mut layout_job = highlight(ui.ctx(), &theme, string, language);
+        layout_job.wrap.max_width = width; // no wrapping
+        ui.fonts(|f| f.layout_job(layout_job))
+    };
+
+    ui.add(
+        egui::TextEdit::multiline(code)
+            .font(egui::TextStyle::Monospace) // for cursor height
+            .code_editor()
+            .desired_rows(1)
+            .desired_width(width)
+            .lock_focus(true)
+            .layouter(&mut layouter),
+    )
+}
+
 /// Memoized Code highlighting
 pub fn highlight(ctx: &egui::Context, theme: &CodeTheme, code: &str, language: &str) -> LayoutJob {
     impl egui::util::cache::ComputerMut<(&CodeTheme, &str, &str), LayoutJob> for Highlighter {
@@ -0,0 +1,512 @@
+use crate::app::generators::autonomous::routine::Component;
+use crate::app::generators::control::{Control, UiElement};
+use crate::app::generators::keybinding::registry::component_name;
+use crate::app::generators::subsystem::subsystem::Subsystem;
+use crate::app::java::robot_hardware::ROBOT_HARDWARE_CLASS;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Sliders whose names end with one of these limit a component's power or speed, and can be
+/// set per TeleOp
+const POWER_LIMIT_SUFFIXES: &[&str] = &["MaxPower", "MaxSpeed"];
+
+/// One TeleOp generated from the project's robot, such as a competition TeleOp and a slower
+/// practice mode. It drives the same hardware, with some keybindings or power limits changed
+/// and some subsystems left out.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TeleOpProfile {
+    /// Shown on the Driver Station, and names the OpMode's class and file
+    pub name: String,
+    /// Subsystems this TeleOp doesn't drive. The hardware class still sets up their devices.
+    pub disabled_subsystems: Vec<String>,
+    pub overrides: Vec<ControlOverride>,
+}
+
+/// The robot as one TeleOp drives it, from [`TeleOpProfile::apply`]. Building it clones and
+/// reloads every component, so it's kept by the [`TeleOpProfile::source`] it was built from.
+pub struct AppliedProfile {
+    pub drivetrain: Subsystem,
+    pub subsystems: Vec<Subsystem>,
+}
+
+/// A control of one component, set differently in one TeleOp
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ControlOverride {
+    pub subsystem: String,
+    /// Position of the component in its subsystem
+    pub component: usize,
+    /// The control as this TeleOp sets it
+    pub control: Control,
+}
+
+impl Default for TeleOpProfile {
+    fn default() -> Self {
+        Self::new("FTCreate")
+    }
+}
+
+impl TeleOpProfile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            disabled_subsystems: vec![],
+            overrides: vec![],
+        }
+    }
+
+    pub fn is_enabled(&self, subsystem: &str) -> bool {
+        !self
+            .disabled_subsystems
+            .iter()
+            .any(|name| name == subsystem)
+    }
+
+    /// Whether this TeleOp drives the robot any differently from how it's configured
+    pub fn changes_robot(&self) -> bool {
+        !self.disabled_subsystems.is_empty() || !self.overrides.is_empty()
+    }
+
+    /// Forget this TeleOp's changes to a component removed from `subsystem`, and move those
+    /// of the components after it to their new positions
+    pub fn remove_component(&mut self, subsystem: &str, component: usize) {
+        self.overrides
+            .retain(|o| !(o.subsystem == subsystem && o.component == component));
+
+        for o in &mut self.overrides {
+            if o.subsystem == subsystem && o.component > component {
+                o.component -= 1;
+            }
+        }
+    }
+
+    /// Keep this TeleOp's changes to a subsystem when it's renamed
+    pub fn rename_subsystem(&mut self, old: &str, new: &str) {
+        for name in &mut self.disabled_subsystems {
+            if name == old {
+                *name = new.to_string();
+            }
+        }
+        for o in &mut self.overrides {
+            if o.subsystem == old {
+                o.subsystem = new.to_string();
+            }
+        }
+    }
+
+    /// Forget this TeleOp's changes to a deleted subsystem, so they don't carry over to a new
+    /// one given the same name
+    pub fn remove_subsystem(&mut self, subsystem: &str) {
+        self.disabled_subsystems.retain(|name| name != subsystem);
+        self.overrides.retain(|o| o.subsystem != subsystem);
+    }
+
+    fn override_index(&self, subsystem: &str, component: usize, control: &str) -> Option<usize> {
+        self.overrides.iter().position(|o| {
+            o.subsystem == subsystem && o.component == component && o.control.get_name() == control
+        })
+    }
+
+    /// A hash of everything [`TeleOpProfile::apply`] builds from: this TeleOp, and each
+    /// component's module and controls. It changes whenever the applied robot would.
+    pub fn source(&self, drivetrain: &Subsystem, subsystems: &[Subsystem]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(self)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+
+        for subsystem in std::iter::once(drivetrain).chain(subsystems) {
+            subsystem.name.hash(&mut hasher);
+            for generator in &subsystem.control_handler.generators {
+                generator.script.hash(&mut hasher);
+                generator.script_data.hash(&mut hasher);
+                serde_json::to_string(&generator.controls)
+                    .unwrap_or_default()
+                    .hash(&mut hasher);
+                format!("{:?}", generator.sensor_conditions).hash(&mut hasher);
+            }
+        }
+
+        hasher.finish()
+    }
+
+    /// Copies of `drivetrain` and `subsystems` as this TeleOp drives them, loaded and ready to
+    /// generate. A disabled drivetrain is left without components; other disabled subsystems are
+    /// left out.
+    pub fn apply(
+        &self,
+        drivetrain: &Subsystem,
+        subsystems: &[Subsystem],
+    ) -> (Subsystem, Vec<Subsystem>) {
+        let drivetrain = if self.is_enabled(&drivetrain.name) {
+            self.apply_to(drivetrain)
+        } else {
+            Subsystem::new(drivetrain.name.clone())
+        };
+        let subsystems = subsystems
+            .iter()
+            .filter(|subsystem| self.is_enabled(&subsystem.name))
+            .map(|subsystem| self.apply_to(subsystem))
+            .collect();
+
+        (drivetrain, subsystems)
+    }
+
+    fn apply_to(&self, subsystem: &Subsystem) -> Subsystem {
+        let mut copy = Subsystem::new(subsystem.name.clone());
+        copy.control_handler = subsystem.control_handler.clone();
+
+        let originals = subsystem.control_handler.generators.iter();
+        for (index, (generator, original)) in copy
+            .control_handler
+            .generators
+            .iter_mut()
+            .zip(originals)
+            .enumerate()
+        {
+            generator.sensor_conditions = original.sensor_conditions.clone();
+
+            for control in &mut generator.controls {
+                if let Some(i) = self.override_index(&subsystem.name, index, &control.get_name()) {
+                    self.overrides[i].apply(control);
+                }
+            }
+        }
+
+        // errors are kept on the components and generated as `// ERROR` comments
+        let _ = copy.control_handler.tick_lua();
+        copy
+    }
+
+    /// Edit the name, the subsystems driven and the controls changed. `subsystems` names every
+    /// subsystem, the drivetrain first, and `components` are the robot's components as
+    /// configured.
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        subsystems: &[String],
+        components: &[Component<'_>],
+    ) {
+        for subsystem in subsystems {
+            let mut enabled = self.is_enabled(subsystem);
+            if ui.checkbox(&mut enabled, subsystem).changed() {
+                if enabled {
+                    self.disabled_subsystems.retain(|name| name != subsystem);
+                } else {
+                    self.disabled_subsystems.push(subsystem.clone());
+                }
+            }
+            if !enabled {
+                continue;
+            }
+
+            ui.indent(subsystem, |ui| {
+                for component in components.iter().filter(|c| c.subsystem == subsystem) {
+                    let controls: Vec<&Control> = component
+                        .generator
+                        .controls
+                        .iter()
+                        .filter(|control| can_override(control))
+                        .collect();
+                    if controls.is_empty() {
+                        continue;
+                    }
+
+                    ui.label(component_name(component));
+                    ui.push_id((subsystem, component.index), |ui| {
+                        for control in controls {
+                            self.render_control(ui, component, control);
+                        }
+                    });
+                }
+            });
+        }
+    }
+
+    /// A checkbox to change `control` in this TeleOp, and the control as changed
+    fn render_control(&mut self, ui: &mut egui::Ui, component: &Component<'_>, control: &Control) {
+        let name = control.get_name();
+        let index = self.override_index(component.subsystem, component.index, &name);
+
+        let mut overridden = index.is_some();
+        let label = match control {
+            Control::SliderType(slider) => slider.label.clone(),
+            Control::ComboBoxType(combo_box) => combo_box.label.clone(),
+            _ => name.clone(),
+        };
+        if ui
+            .checkbox(&mut overridden, format!("Change {}", label))
+            .changed()
+        {
+            match index {
+                Some(i) => {
+                    self.overrides.remove(i);
+                }
+                None => self.overrides.push(ControlOverride {
+                    subsystem: component.subsystem.to_string(),
+                    component: component.index,
+                    control: control.clone(),
+                }),
+            }
+        }
+
+        if let Some(i) = self.override_index(component.subsystem, component.index, &name) {
+            ui.indent(&name, |ui| match &mut self.overrides[i].control {
+                Control::SliderType(slider) => slider.render(ui, None),
+                Control::ComboBoxType(combo_box) => combo_box.render(ui, None),
+                _ => {}
+            });
+        }
+    }
+}
+
+impl ControlOverride {
+    /// Set `control` the way this TeleOp does
+    fn apply(&self, control: &mut Control) {
+        match (control, &self.control) {
+            (Control::SliderType(slider), Control::SliderType(changed)) => {
+                slider.value = changed.value;
+            }
+            (Control::ComboBoxType(combo_box), Control::ComboBoxType(changed)) => {
+                combo_box.value = changed.value.clone();
+                combo_box.gamepad = changed.gamepad;
+                combo_box.behaviour = changed.behaviour;
+                combo_box.modifiers = changed.modifiers.clone();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Pick, add and delete TeleOps, and edit the one picked. `subsystems` and `components` are
+/// as for [`TeleOpProfile::render`]. Returns whether a different TeleOp was picked.
+pub fn render_list(
+    ui: &mut egui::Ui,
+    profiles: &mut Vec<TeleOpProfile>,
+    selected: &mut usize,
+    subsystems: &[String],
+    components: &[Component<'_>],
+) -> bool {
+    let mut picked = false;
+
+    ui.horizontal_wrapped(|ui| {
+        for (i, profile) in profiles.iter().enumerate() {
+            if ui.selectable_label(i == *selected, &profile.name).clicked() {
+                *selected = i;
+                picked = true;
+            }
+        }
+
+        // new TeleOps start as a copy of the one picked, since they're usually variations of it
+        if ui.button("Add TeleOp").clicked() {
+            let mut profile = profiles[*selected].clone();
+            profile.name = unique_name(&profile.name, profiles);
+            profiles.push(profile);
+            *selected = profiles.len() - 1;
+            picked = true;
+        }
+    });
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.label("Teleop name: ");
+        ui.text_edit_singleline(&mut profiles[*selected].name);
+    });
+
+    if clashing_names(profiles).contains(&profiles[*selected].name) {
+        ui.label("⚠ Another generated file has this name. Rename it to upload the code.");
+    }
+
+    if profiles.len() > 1 && ui.button("Delete TeleOp").clicked() {
+        profiles.remove(*selected);
+        *selected = selected.saturating_sub(1);
+        return true;
+    }
+
+    ui.add_space(10.0);
+    ui.label("Subsystems driven, and the settings this TeleOp changes:");
+    profiles[*selected].render(ui, subsystems, components);
+
+    picked
+}
+
+/// `name`, without any number it ends with, followed by the lowest number no TeleOp's name
+/// has yet, e.g. `Practice2` for a copy of `Practice`
+pub fn unique_name(name: &str, profiles: &[TeleOpProfile]) -> String {
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit());
+
+    (2..)
+        .map(|n| format!("{}{}", name, n))
+        .find(|candidate| profiles.iter().all(|profile| profile.name != *candidate))
+        .unwrap()
+}
+
+/// Names given to more than one generated file: to two TeleOps, or to a TeleOp and the
+/// hardware class. The files would overwrite each other.
+pub fn clashing_names(profiles: &[TeleOpProfile]) -> Vec<String> {
+    let mut names: Vec<String> = profiles
+        .iter()
+        .enumerate()
+        .filter(|(i, profile)| {
+            profile.name == ROBOT_HARDWARE_CLASS
+                || profiles[..*i]
+                    .iter()
+                    .any(|other| other.name == profile.name)
+        })
+        .map(|(_, profile)| profile.name.clone())
+        .collect();
+
+    names.dedup();
+    names
+}
+
+/// Whether `control` can be set per TeleOp: keybindings and power or speed limits
+pub fn can_override(control: &Control) -> bool {
+    match control {
+        Control::ComboBoxType(combo_box) => combo_box.gamepad.is_some(),
+        Control::SliderType(slider) => POWER_LIMIT_SUFFIXES
+            .iter()
+            .any(|suffix| slider.name.ends_with(suffix)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::generators::lua_generator::LuaGenerator;
+    use crate::app::generators::ui_elements::Slider;
+
+    fn max_power(subsystem: &str, component: usize, value: f32) -> ControlOverride {
+        ControlOverride {
+            subsystem: subsystem.into(),
+            component,
+            control: Control::SliderType(Slider {
+                name: "DCM_MaxPower".into(),
+                min: 0.0,
+                max: 1.0,
+                value,
+                step_by: 0.05,
+                deicimals: 2,
+                label: "Max power".into(),
+                keybinding: None,
+            }),
+        }
+    }
+
+    fn keys(profile: &TeleOpProfile) -> Vec<(String, usize)> {
+        profile
+            .overrides
+            .iter()
+            .map(|o| (o.subsystem.clone(), o.component))
+            .collect()
+    }
+
+    fn practice() -> TeleOpProfile {
+        TeleOpProfile {
+            name: "Practice".into(),
+            disabled_subsystems: vec!["Lift".into()],
+            overrides: vec![
+                max_power("Arm", 0, 0.2),
+                max_power("Arm", 1, 0.3),
+                max_power("Arm", 2, 0.4),
+                max_power("Lift", 1, 0.5),
+            ],
+        }
+    }
+
+    #[test]
+    fn moves_overrides_up_when_a_component_is_removed() {
+        let mut profile = practice();
+        profile.remove_component("Arm", 1);
+
+        assert_eq!(
+            keys(&profile),
+            vec![("Arm".into(), 0), ("Arm".into(), 1), ("Lift".into(), 1)]
+        );
+        // the override that was on the third component follows it
+        assert!(matches!(
+            &profile.overrides[1].control,
+            Control::SliderType(slider) if slider.value == 0.4
+        ));
+    }
+
+    #[test]
+    fn keeps_overrides_through_a_rename() {
+        let mut profile = practice();
+        profile.rename_subsystem("Lift", "Elevator");
+
+        assert_eq!(profile.disabled_subsystems, vec!["Elevator"]);
+        assert_eq!(keys(&profile)[3], ("Elevator".into(), 1));
+        assert!(profile.is_enabled("Lift"));
+    }
+
+    #[test]
+    fn forgets_a_deleted_subsystem() {
+        let mut profile = practice();
+        profile.remove_subsystem("Lift");
+
+        assert!(profile.disabled_subsystems.is_empty());
+        assert!(keys(&profile)
+            .iter()
+            .all(|(subsystem, _)| subsystem == "Arm"));
+    }
+
+    #[test]
+    fn changes_source_only_when_the_teleop_or_robot_does() {
+        let mut arm = Subsystem::new("Arm".into());
+        arm.control_handler
+            .generators
+            .push(LuaGenerator::with_controls(
+                "lua_modules/DC_Motor.lua",
+                vec![max_power("Arm", 0, 1.0).control],
+            ));
+        let drivetrain = Subsystem::new("Drivetrain".into());
+        let mut subsystems = vec![arm];
+        let mut profile = practice();
+
+        let source = profile.source(&drivetrain, &subsystems);
+        assert_eq!(profile.source(&drivetrain, &subsystems), source);
+
+        profile.overrides[0] = max_power("Arm", 0, 0.1);
+        let changed_teleop = profile.source(&drivetrain, &subsystems);
+        assert_ne!(changed_teleop, source);
+
+        if let Control::SliderType(slider) =
+            &mut subsystems[0].control_handler.generators[0].controls[0]
+        {
+            slider.value = 0.9;
+        }
+        assert_ne!(profile.source(&drivetrain, &subsystems), changed_teleop);
+    }
+
+    #[test]
+    fn names_a_copy_after_the_names_already_taken() {
+        let profiles = vec![
+            TeleOpProfile::new("Competition"),
+            TeleOpProfile::new("Competition2"),
+            TeleOpProfile::new("Competition4"),
+        ];
+
+        assert_eq!(unique_name("Competition", &profiles), "Competition3");
+        assert_eq!(unique_name("Competition4", &profiles), "Competition3");
+    }
+
+    #[test]
+    fn finds_names_shared_by_generated_files() {
+        let profiles = vec![
+            TeleOpProfile::new("Competition"),
+            TeleOpProfile::new("Practice"),
+            TeleOpProfile::new("Competition"),
+            TeleOpProfile::new("Competition"),
+            TeleOpProfile::new(ROBOT_HARDWARE_CLASS),
+        ];
+
+        assert_eq!(
+            clashing_names(&profiles),
+            vec!["Competition".to_string(), ROBOT_HARDWARE_CLASS.to_string()]
+        );
+        assert!(clashing_names(&profiles[..2]).is_empty());
+    }
+}
@@ -0,0 +1,739 @@
+use crate::app::java::{JavaFile, USER_CODE_BEGIN, USER_CODE_END};
+use crate::app::syntax_highlighting;
+use std::collections::BTreeMap;
+
+/// The user's edits to the generated files, kept when the files are generated again.
+///
+/// Code in a user code region follows the region wherever it's generated. Edits anywhere else
+/// are carried over by a three-way merge of the code the edits were made to, the edited code
+/// and the newly generated code. Where the user and the generator changed the same lines
+/// differently, the merge waits for the user to pick what to keep.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct UserCode {
+    /// Files the user has edited, by class name
+    files: BTreeMap<String, EditedFile>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct EditedFile {
+    /// The generated code the edits were made to
+    base: String,
+    /// The file as the user left it
+    edited: String,
+    /// Code from regions that aren't generated any more, for the user to move or throw away
+    orphans: Vec<Orphan>,
+    /// A merge with newer generated code that conflicts with the edits
+    #[serde(skip)]
+    pending: Option<PendingMerge>,
+}
+
+/// Code left without a place in the file when its region stopped being generated, e.g. when the
+/// subsystem it was written for was deleted
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+struct Orphan {
+    region: String,
+    code: String,
+}
+
+#[derive(Debug, Clone)]
+struct PendingMerge {
+    generated: String,
+    hunks: Vec<Hunk>,
+    orphans: Vec<Orphan>,
+}
+
+/// Part of a three-way merge
+#[derive(Debug, Clone, PartialEq)]
+enum Hunk {
+    /// Lines that merged cleanly: unchanged, or changed on one side only
+    Merged(Vec<String>),
+    /// Lines the user and the generator both changed, differently
+    Conflict {
+        edited: Vec<String>,
+        generated: Vec<String>,
+        choice: Option<Choice>,
+    },
+}
+
+/// What to keep in place of a conflict
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Choice {
+    Edited,
+    Generated,
+    Both,
+}
+
+impl UserCode {
+    /// Replace each of `files` the user has edited with the edits merged into it. Files whose
+    /// merge conflicts keep the code as it was last edited until the conflicts are settled;
+    /// see [`UserCode::conflicted`].
+    pub fn apply(&mut self, files: &mut [JavaFile]) {
+        for file in files {
+            if let Some(edited) = self.files.get_mut(&file.class_name) {
+                edited.update(&file.code);
+                file.code = edited.edited.clone();
+            }
+        }
+
+        // edits the generated code caught up with aren't edits any more
+        self.files
+            .retain(|_, file| file.edited != file.base || !file.orphans.is_empty());
+    }
+
+    /// Record the user changing `class_name` from `code` to `edited`
+    pub fn edit(&mut self, class_name: &str, code: &str, edited: String) {
+        let file = self
+            .files
+            .entry(class_name.to_string())
+            .or_insert_with(|| EditedFile {
+                base: code.to_string(),
+                ..Default::default()
+            });
+        file.edited = edited;
+    }
+
+    /// Record code the user brought with them as written in the user code regions of
+    /// `class_name`, generated as `code`: `regions` has the lines for each region, indented
+    /// from the region's start. Code for regions `code` doesn't have is kept as orphaned code.
+    pub fn fill(&mut self, class_name: &str, code: &str, regions: &BTreeMap<String, Vec<String>>) {
+        let indented: BTreeMap<String, Vec<String>> = regions
+            .iter()
+            .map(|(name, lines)| {
+                let indent = code
+                    .lines()
+                    .find(|line| {
+                        line.trim().strip_prefix(USER_CODE_BEGIN).map(str::trim) == Some(name)
+                    })
+                    .map_or("", |line| &line[..line.len() - line.trim_start().len()]);
+                let lines = lines
+                    .iter()
+                    .map(|line| match line.is_empty() {
+                        true => String::new(),
+                        false => format!("{}{}", indent, line),
+                    })
+                    .collect();
+                (name.clone(), lines)
+            })
+            .collect();
+
+        let (edited, missing) = fill_regions(code, &indented);
+        self.edit(class_name, code, edited);
+        if let Some(file) = self.files.get_mut(class_name) {
+            file.orphans
+                .extend(missing.into_iter().map(|(region, lines)| Orphan {
+                    region,
+                    code: lines.join("\n"),
+                }));
+        }
+    }
+
+    /// Keep the code in the user code regions called `old` when they're generated as `new`
+    /// instead, e.g. after the subsystem they're in is renamed
+    pub fn rename_region(&mut self, old: &str, new: &str) {
+        for file in self.files.values_mut() {
+            file.base = rename_region(&file.base, old, new);
+            file.edited = rename_region(&file.edited, old, new);
+        }
+    }
+
+    /// Throw away the edits to `class_name`
+    pub fn revert(&mut self, class_name: &str) {
+        self.files.remove(class_name);
+    }
+
+    pub fn is_edited(&self, class_name: &str) -> bool {
+        self.files.contains_key(class_name)
+    }
+
+    /// Classes whose edits conflict with their generated code
+    pub fn conflicted(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|(_, file)| file.pending.is_some())
+            .map(|(class_name, _)| class_name.clone())
+            .collect()
+    }
+
+    /// Whether `class_name` has conflicts or orphaned code for the user to deal with
+    pub fn needs_attention(&self, class_name: &str) -> bool {
+        self.files
+            .get(class_name)
+            .is_some_and(|file| file.pending.is_some() || !file.orphans.is_empty())
+    }
+
+    /// Settle the conflicts of `class_name`, and move or throw away its orphaned code
+    pub fn render_problems(&mut self, ui: &mut egui::Ui, class_name: &str) {
+        let file = match self.files.get_mut(class_name) {
+            Some(file) => file,
+            None => return,
+        };
+        let width = ui.available_width();
+
+        if let Some(pending) = &mut file.pending {
+            ui.label("⚠ Your edits conflict with the regenerated code. Pick what to keep:");
+
+            for (i, hunk) in pending.hunks.iter_mut().enumerate() {
+                if let Hunk::Conflict {
+                    edited,
+                    generated,
+                    choice,
+                } = hunk
+                {
+                    ui.push_id(i, |ui| {
+                        ui.group(|ui| {
+                            ui.label("Your code:");
+                            syntax_highlighting::code_view_ui(ui, &edited.join("\n"), width);
+                            ui.label("Generated code:");
+                            syntax_highlighting::code_view_ui(ui, &generated.join("\n"), width);
+
+                            ui.horizontal(|ui| {
+                                ui.selectable_value(choice, Some(Choice::Edited), "Keep mine");
+                                ui.selectable_value(
+                                    choice,
+                                    Some(Choice::Generated),
+                                    "Use generated",
+                                );
+                                ui.selectable_value(choice, Some(Choice::Both), "Keep both");
+                            });
+                        });
+                    });
+                }
+            }
+
+            let settled = pending.hunks.iter().all(|hunk| match hunk {
+                Hunk::Conflict { choice, .. } => choice.is_some(),
+                Hunk::Merged(_) => true,
+            });
+            if ui
+                .add_enabled(settled, egui::Button::new("Apply merge"))
+                .clicked()
+            {
+                if let Some(pending) = file.pending.take() {
+                    file.accept(pending);
+                }
+            }
+        }
+
+        let mut discarded = None;
+        for (i, orphan) in file.orphans.iter().enumerate() {
+            ui.push_id(("orphan", i), |ui| {
+                ui.label(format!(
+                    "⚠ \"{}\" isn't generated any more. Move this code somewhere else, then discard it:",
+                    orphan.region
+                ));
+                syntax_highlighting::code_view_ui(ui, &orphan.code, width);
+                if ui.button("Discard").clicked() {
+                    discarded = Some(i);
+                }
+            });
+        }
+        if let Some(i) = discarded {
+            file.orphans.remove(i);
+        }
+    }
+}
+
+impl EditedFile {
+    /// Bring the edits up to date with `generated`, the code as generated now
+    fn update(&mut self, generated: &str) {
+        if self.base == generated && self.pending.is_none() {
+            return;
+        }
+        // still waiting for the user to settle the same conflicts
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|pending| pending.generated == generated)
+        {
+            return;
+        }
+
+        let regions = regions(&self.edited);
+        let (base, _) = fill_regions(&self.base, &regions);
+        let (theirs, missing) = fill_regions(generated, &regions);
+
+        let pending = PendingMerge {
+            generated: generated.to_string(),
+            hunks: merge(&lines(&base), &lines(&self.edited), &lines(&theirs)),
+            orphans: missing
+                .into_iter()
+                .map(|(region, code)| Orphan {
+                    region,
+                    code: code.join("\n"),
+                })
+                .collect(),
+        };
+
+        if pending
+            .hunks
+            .iter()
+            .any(|hunk| matches!(hunk, Hunk::Conflict { .. }))
+        {
+            self.pending = Some(pending);
+        } else {
+            self.pending = None;
+            self.accept(pending);
+        }
+    }
+
+    /// Take the result of a merge whose conflicts are all settled
+    fn accept(&mut self, pending: PendingMerge) {
+        let mut code: Vec<String> = vec![];
+        for hunk in pending.hunks {
+            match hunk {
+                Hunk::Merged(lines) => code.extend(lines),
+                Hunk::Conflict {
+                    edited,
+                    generated,
+                    choice,
+                } => match choice {
+                    Some(Choice::Edited) | None => code.extend(edited),
+                    Some(Choice::Generated) => code.extend(generated),
+                    Some(Choice::Both) => code.extend(edited.into_iter().chain(generated)),
+                },
+            }
+        }
+
+        self.edited = join(&code);
+        self.base = pending.generated;
+        self.orphans.extend(pending.orphans);
+    }
+}
+
+/// The lines inside each user code region of `code`, by region name
+fn regions(code: &str) -> BTreeMap<String, Vec<String>> {
+    let mut regions = BTreeMap::new();
+    let mut open: Option<(String, Vec<String>)> = None;
+
+    for line in code.lines() {
+        let trimmed = line.trim();
+        match open.take() {
+            Some((name, lines)) if trimmed == USER_CODE_END => {
+                regions.insert(name, lines);
+            }
+            Some((name, mut lines)) => {
+                lines.push(line.to_string());
+                open = Some((name, lines));
+            }
+            None => {
+                if let Some(name) = trimmed.strip_prefix(USER_CODE_BEGIN) {
+                    open = Some((name.trim().to_string(), vec![]));
+                }
+            }
+        }
+    }
+
+    regions
+}
+
+/// `code` with each of its user code regions holding the lines `regions` has for it. Also
+/// returns the regions with code in them that `code` has no place for.
+fn fill_regions(
+    code: &str,
+    regions: &BTreeMap<String, Vec<String>>,
+) -> (String, Vec<(String, Vec<String>)>) {
+    let mut filled: Vec<String> = vec![];
+    let mut placed: Vec<&str> = vec![];
+    let mut skipping = false;
+
+    for line in code.lines() {
+        let trimmed = line.trim();
+        if skipping {
+            if trimmed != USER_CODE_END {
+                continue;
+            }
+            skipping = false;
+        } else if let Some(name) = trimmed.strip_prefix(USER_CODE_BEGIN) {
+            filled.push(line.to_string());
+            if let Some((name, lines)) = regions.get_key_value(name.trim()) {
+                filled.extend(lines.iter().cloned());
+                placed.push(name);
+            }
+            skipping = true;
+            continue;
+        }
+        filled.push(line.to_string());
+    }
+
+    let missing = regions
+        .iter()
+        .filter(|(name, lines)| {
+            !placed.contains(&name.as_str()) && lines.iter().any(|line| !line.trim().is_empty())
+        })
+        .map(|(name, lines)| (name.clone(), lines.clone()))
+        .collect();
+
+    (join(&filled), missing)
+}
+
+/// `code` with its user code regions called `old` called `new`
+fn rename_region(code: &str, old: &str, new: &str) -> String {
+    code.split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim();
+            match trimmed.strip_prefix(USER_CODE_BEGIN).map(str::trim) {
+                Some(name) if name == old => {
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    let ending = &line[indent.len() + trimmed.len()..];
+                    format!("{}{} {}{}", indent, USER_CODE_BEGIN, new, ending)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn lines(code: &str) -> Vec<&str> {
+    code.lines().collect()
+}
+
+fn join(lines: &[String]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Three-way merge `edited` and `generated`, which were both changed from `base`, line by line.
+///
+/// Lines of `base` that both sides kept split the files into hunks. A hunk only one side
+/// changed takes that side's lines; a hunk both changed the same way takes either; anything
+/// else is a conflict.
+fn merge(base: &[&str], edited: &[&str], generated: &[&str]) -> Vec<Hunk> {
+    let to_edited = matches(base, edited);
+    let to_generated = matches(base, generated);
+
+    let mut hunks: Vec<Hunk> = vec![];
+    let (mut b, mut e, mut g) = (0, 0, 0);
+
+    let stable = (0..base.len())
+        .filter_map(|i| Some((i, to_edited[i]?, to_generated[i]?)))
+        .chain(std::iter::once((base.len(), edited.len(), generated.len())));
+
+    for (next_b, next_e, next_g) in stable {
+        let hunk = merge_hunk(&base[b..next_b], &edited[e..next_e], &generated[g..next_g]);
+        push(&mut hunks, hunk);
+
+        if next_b < base.len() {
+            push(&mut hunks, Hunk::Merged(vec![base[next_b].to_string()]));
+        }
+        (b, e, g) = (next_b + 1, next_e + 1, next_g + 1);
+    }
+
+    hunks
+}
+
+fn merge_hunk(base: &[&str], edited: &[&str], generated: &[&str]) -> Hunk {
+    let owned = |lines: &[&str]| lines.iter().map(|line| line.to_string()).collect();
+
+    if edited == base || edited == generated {
+        Hunk::Merged(owned(generated))
+    } else if generated == base {
+        Hunk::Merged(owned(edited))
+    } else {
+        Hunk::Conflict {
+            edited: owned(edited),
+            generated: owned(generated),
+            choice: None,
+        }
+    }
+}
+
+/// Add `hunk`, joining it to the last hunk if both merged cleanly
+fn push(hunks: &mut Vec<Hunk>, hunk: Hunk) {
+    match (hunks.last_mut(), hunk) {
+        (_, Hunk::Merged(lines)) if lines.is_empty() => {}
+        (Some(Hunk::Merged(last)), Hunk::Merged(lines)) => last.extend(lines),
+        (_, hunk) => hunks.push(hunk),
+    }
+}
+
+/// For each line of `a`, the line of `b` it's paired with in a longest common subsequence of
+/// the two, if any
+fn matches(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let width = b.len() + 1;
+    // lengths[i * width + j] is the length of the longest common subsequence of a[i..] and b[j..]
+    let mut lengths = vec![0u32; (a.len() + 1) * width];
+
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i * width + j] = if a[i] == b[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut matched = vec![None; a.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            matched[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    matched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENERATED: &str = "\
+class TeleOp {
+    void init() {
+        arm.init();
+    }
+
+    void loop() {
+        arm.update();
+
+        // BEGIN USER CODE loop Arm
+        // END USER CODE
+    }
+}
+";
+
+    fn generate(user_code: &mut UserCode, code: &str) -> String {
+        let mut files = vec![JavaFile::new("TeleOp", code.to_string())];
+        user_code.apply(&mut files);
+        files.remove(0).code
+    }
+
+    fn hunk(lines: &[&str]) -> Hunk {
+        Hunk::Merged(lines.iter().map(|line| line.to_string()).collect())
+    }
+
+    #[test]
+    fn matches_lines_of_a_longest_common_subsequence() {
+        assert_eq!(
+            matches(&["a", "b", "c", "d"], &["a", "c", "x", "d"]),
+            vec![Some(0), None, Some(1), Some(3)]
+        );
+        assert_eq!(matches(&["a"], &[]), vec![None]);
+    }
+
+    #[test]
+    fn merges_changes_made_on_one_side() {
+        let base = ["a", "b", "c", "d", "e"];
+        let edited = ["a", "B", "c", "d", "e"];
+        let generated = ["a", "b", "c", "d", "E", "f"];
+
+        assert_eq!(
+            merge(&base, &edited, &generated),
+            vec![hunk(&["a", "B", "c", "d", "E", "f"])]
+        );
+    }
+
+    #[test]
+    fn conflicts_where_both_sides_change_the_same_lines() {
+        let base = ["a", "b", "c"];
+        let edited = ["a", "mine", "c"];
+        let generated = ["a", "theirs", "c"];
+
+        assert_eq!(
+            merge(&base, &edited, &generated),
+            vec![
+                hunk(&["a"]),
+                Hunk::Conflict {
+                    edited: vec!["mine".to_string()],
+                    generated: vec!["theirs".to_string()],
+                    choice: None,
+                },
+                hunk(&["c"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn fills_regions_wherever_they_are_generated() {
+        let regions = BTreeMap::from([
+            ("a".to_string(), vec!["  one();".to_string()]),
+            ("gone".to_string(), vec!["  two();".to_string()]),
+            ("empty".to_string(), vec![String::new()]),
+        ]);
+        let code = "\
+x();
+  // BEGIN USER CODE a
+  old();
+  // END USER CODE
+";
+
+        let (filled, missing) = fill_regions(code, &regions);
+        assert_eq!(
+            filled,
+            "\
+x();
+  // BEGIN USER CODE a
+  one();
+  // END USER CODE
+"
+        );
+        assert_eq!(
+            missing,
+            vec![("gone".to_string(), vec!["  two();".to_string()])]
+        );
+    }
+
+    #[test]
+    fn carries_edits_over_to_regenerated_code() {
+        let mut user_code = UserCode::default();
+        user_code.edit(
+            "TeleOp",
+            GENERATED,
+            GENERATED.replace("arm.init();", "arm.init(true);"),
+        );
+
+        let generated = GENERATED.replace("arm.update();", "arm.update();\n        lift.update();");
+        let code = generate(&mut user_code, &generated);
+
+        assert_eq!(code, generated.replace("arm.init();", "arm.init(true);"));
+        assert!(user_code.conflicted().is_empty());
+        assert!(user_code.is_edited("TeleOp"));
+    }
+
+    #[test]
+    fn moves_region_code_with_its_region() {
+        let mut user_code = UserCode::default();
+        user_code.edit(
+            "TeleOp",
+            GENERATED,
+            GENERATED.replace(
+                "// BEGIN USER CODE loop Arm\n",
+                "// BEGIN USER CODE loop Arm\n        arm.hold();\n",
+            ),
+        );
+
+        // the region moves into init, and the loop is generated differently
+        let generated = "\
+class TeleOp {
+    void init() {
+        arm.init();
+
+        // BEGIN USER CODE loop Arm
+        // END USER CODE
+    }
+
+    void loop() {
+        arm.update(gamepad1);
+    }
+}
+";
+        let code = generate(&mut user_code, generated);
+
+        assert_eq!(
+            code,
+            generated.replace(
+                "// BEGIN USER CODE loop Arm\n",
+                "// BEGIN USER CODE loop Arm\n        arm.hold();\n",
+            )
+        );
+        assert!(user_code.conflicted().is_empty());
+    }
+
+    #[test]
+    fn keeps_code_of_regions_that_are_gone() {
+        let mut user_code = UserCode::default();
+        user_code.edit(
+            "TeleOp",
+            GENERATED,
+            GENERATED.replace(
+                "// BEGIN USER CODE loop Arm\n",
+                "// BEGIN USER CODE loop Arm\n        arm.hold();\n",
+            ),
+        );
+
+        let generated = GENERATED.replace(
+            "\n        // BEGIN USER CODE loop Arm\n        // END USER CODE\n",
+            "",
+        );
+        let code = generate(&mut user_code, &generated);
+
+        assert_eq!(code, generated);
+        assert!(user_code.needs_attention("TeleOp"));
+        let orphans = &user_code.files["TeleOp"].orphans;
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].region, "loop Arm");
+        assert_eq!(orphans[0].code, "        arm.hold();");
+    }
+
+    #[test]
+    fn follows_a_renamed_region() {
+        let mut user_code = UserCode::default();
+        user_code.edit(
+            "TeleOp",
+            GENERATED,
+            GENERATED.replace(
+                "// BEGIN USER CODE loop Arm\n",
+                "// BEGIN USER CODE loop Arm\n        arm.hold();\n",
+            ),
+        );
+
+        user_code.rename_region("loop Arm", "loop Elbow");
+        let generated = GENERATED.replace("loop Arm", "loop Elbow");
+        let code = generate(&mut user_code, &generated);
+
+        assert_eq!(
+            code,
+            generated.replace(
+                "// BEGIN USER CODE loop Elbow\n",
+                "// BEGIN USER CODE loop Elbow\n        arm.hold();\n",
+            )
+        );
+        assert!(!user_code.needs_attention("TeleOp"));
+    }
+
+    #[test]
+    fn drops_edits_the_generated_code_caught_up_with() {
+        let mut user_code = UserCode::default();
+        let edited = GENERATED.replace("arm.init();", "arm.init(true);");
+        user_code.edit("TeleOp", GENERATED, edited.clone());
+
+        assert_eq!(generate(&mut user_code, &edited), edited);
+        assert!(!user_code.is_edited("TeleOp"));
+    }
+
+    #[test]
+    fn waits_for_conflicts_to_be_settled() {
+        for (choice, expected) in [
+            (Choice::Edited, "arm.init(true);"),
+            (Choice::Generated, "arm.init(false);"),
+            (Choice::Both, "arm.init(true);\n        arm.init(false);"),
+        ] {
+            let mut user_code = UserCode::default();
+            let edited = GENERATED.replace("arm.init();", "arm.init(true);");
+            user_code.edit("TeleOp", GENERATED, edited.clone());
+
+            let generated = GENERATED.replace("arm.init();", "arm.init(false);");
+            // the edited code is kept until the user picks what to keep
+            assert_eq!(generate(&mut user_code, &generated), edited);
+            assert_eq!(user_code.conflicted(), vec!["TeleOp".to_string()]);
+            assert_eq!(generate(&mut user_code, &generated), edited);
+
+            let file = user_code.files.get_mut("TeleOp").unwrap();
+            let mut pending = file.pending.take().unwrap();
+            for hunk in &mut pending.hunks {
+                if let Hunk::Conflict { choice: c, .. } = hunk {
+                    *c = Some(choice);
+                }
+            }
+            file.accept(pending);
+
+            let merged = GENERATED.replace("arm.init();", expected);
+            assert_eq!(generate(&mut user_code, &generated), merged);
+            assert!(user_code.conflicted().is_empty());
+            assert_eq!(
+                user_code.is_edited("TeleOp"),
+                choice != Choice::Generated,
+                "{:?}",
+                choice
+            );
+        }
+    }
+}
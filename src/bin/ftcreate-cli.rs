@@ -0,0 +1,177 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+//! Generate the Java for a saved FTCreate project without opening a window.
+//!
+//! ```text
+//! ftcreate-cli <project.ftcreate | OpMode.java> [--modules <dir>]... [--output-dir <dir>]
+//!              [--gamepad-map <map.svg | map.png>] [--robot-config <config.xml>]
+//! ```
+//!
+//! The built-in Lua modules and the user modules folder are always available; each `--modules`
+//! folder is searched after them and overrides modules with the same file name.
+//!
+//! Every file of the project is written to the current directory, or to `--output-dir`:
+//! `RobotHardware.java`, each TeleOp as `<Teleop name>.java` and, if the project has an
+//! autonomous routine, `<first Teleop name>Auto.java`. Exits with a non-zero status if the
+//! project can't be read, a Lua module fails to load or one of its generator functions errors.
+//! Gamepad inputs bound by more than one component of a TeleOp are reported as warnings.
+//! `--gamepad-map` also writes a printable map of both gamepads showing what each input is bound
+//! to, as an SVG or, given a `.png` path, a PNG. `--robot-config` checks every device name
+//! against a hardware configuration saved from the Robot Controller and warns about those it
+//! doesn't have.
+//!
+//! Given a `.java` TeleOp in place of a project, the robot is rebuilt from it as FTCreate's
+//! "Import Java OpMode" does, and what the import made of it is printed before the files are
+//! written.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    if let Err(message) = run(std::env::args().skip(1).collect()) {
+        eprintln!("ftcreate-cli: {}", message);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+const USAGE: &str =
+    "usage: ftcreate-cli <project.ftcreate | OpMode.java> [--modules <dir>]... [--output-dir <dir>] [--gamepad-map <map.svg | map.png>] [--robot-config <config.xml>]";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run(args: Vec<String>) -> Result<(), String> {
+    use ftcreate::Gamepad;
+    use std::path::PathBuf;
+
+    let mut project_path: Option<PathBuf> = None;
+    let mut module_paths: Vec<PathBuf> = vec![];
+    let mut output_dir = PathBuf::from(".");
+    let mut gamepad_map: Option<PathBuf> = None;
+    let mut robot_config: Option<PathBuf> = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--modules" | "-m" => {
+                module_paths.push(args.next().ok_or(USAGE)?.into());
+            }
+            "--output-dir" | "-o" => {
+                output_dir = args.next().ok_or(USAGE)?.into();
+            }
+            "--gamepad-map" | "-g" => {
+                gamepad_map = Some(args.next().ok_or(USAGE)?.into());
+            }
+            "--robot-config" | "-r" => {
+                robot_config = Some(args.next().ok_or(USAGE)?.into());
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if project_path.is_none() && !arg.starts_with('-') => {
+                project_path = Some(arg.into());
+            }
+            _ => return Err(format!("unexpected argument '{}'\n{}", arg, USAGE)),
+        }
+    }
+
+    let project_path = project_path.ok_or(USAGE)?;
+
+    let mut app = ftcreate::TemplateApp::with_modules(&module_paths)
+        .map_err(|e| format!("failed to load Lua modules: {}", e))?;
+
+    if project_path
+        .extension()
+        .is_some_and(|extension| extension == "java")
+    {
+        let source = std::fs::read_to_string(&project_path)
+            .map_err(|e| format!("{}: {}", project_path.display(), e))?;
+        let report = app
+            .import_opmode(&source)
+            .map_err(|e| format!("{}: {}", project_path.display(), e))?;
+        println!("Imported {}", report);
+    } else {
+        let project = ftcreate::Project::load(&project_path)
+            .map_err(|e| format!("{}: {}", project_path.display(), e))?;
+        app.apply_project(&project);
+    }
+
+    app.refresh_generators().map_err(|e| e.to_string())?;
+    app.check_generators().map_err(|e| e.to_string())?;
+
+    for (teleop, keybindings) in app.teleop_keybindings() {
+        for (input, bindings) in keybindings.conflicts() {
+            eprintln!("warning: {} is bound more than once in {}:", input, teleop);
+            for binding in bindings {
+                eprintln!("    {}", binding);
+            }
+        }
+    }
+
+    if let Some(path) = robot_config {
+        let xml =
+            std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let config = ftcreate::HardwareConfig::parse(&xml)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        for (subsystem, name) in app.unconfigured_hardware(&config) {
+            eprintln!(
+                "warning: {} in {} isn't in the robot's configuration",
+                name, subsystem
+            );
+        }
+    }
+
+    let clashing = app.clashing_names();
+    if !clashing.is_empty() {
+        return Err(format!(
+            "more than one file would be named {}; rename the TeleOps in FTCreate first",
+            clashing.join(", ")
+        ));
+    }
+
+    let files = app.java_files();
+    let conflicted = app.conflicted_files();
+    if !conflicted.is_empty() {
+        return Err(format!(
+            "hand edits to {} conflict with the generated code; settle them in FTCreate first",
+            conflicted.join(", ")
+        ));
+    }
+
+    // an imported TeleOp is generated under its own name
+    let same_file = |output: &PathBuf| match (output.canonicalize(), project_path.canonicalize()) {
+        (Ok(output), Ok(input)) => output == input,
+        _ => false,
+    };
+    if let Some(file) = files
+        .iter()
+        .find(|file| same_file(&output_dir.join(file.file_name())))
+    {
+        return Err(format!(
+            "writing {} would overwrite the OpMode it was imported from; pick another --output-dir",
+            file.file_name()
+        ));
+    }
+
+    for file in files {
+        let output = output_dir.join(file.file_name());
+        std::fs::write(&output, &file.code).map_err(|e| format!("{}: {}", output.display(), e))?;
+        println!("Wrote {}", output.display());
+    }
+
+    if let Some(path) = gamepad_map {
+        let keybindings = app.keybindings();
+        let gamepads = [Gamepad::gamepad1, Gamepad::gamepad2];
+        match path.extension().is_some_and(|extension| extension == "png") {
+            true => ftcreate::gamepad_map::png(&keybindings, &gamepads)
+                .save(&path)
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+            false => std::fs::write(&path, ftcreate::gamepad_map::svg(&keybindings, &gamepads))
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+        }
+        println!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
@@ -2,4 +2,8 @@
 
 mod app;
 mod config;
+pub use app::gamepad_map;
+pub use app::generators::keybinding::keybinding::Gamepad;
+pub use app::hardware_config::HardwareConfig;
+pub use app::project::Project;
 pub use app::TemplateApp;
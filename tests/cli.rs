@@ -0,0 +1,58 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn cli(args: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ftcreate-cli"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn generates_a_saved_project() {
+    let output_dir = tempfile::tempdir().unwrap();
+
+    let output = cli(&[
+        &fixture("MyBot.ftcreate"),
+        Path::new("--output-dir"),
+        output_dir.path(),
+    ]);
+
+    assert!(output.status.success(), "{:?}", output);
+    let hardware = std::fs::read_to_string(output_dir.path().join("RobotHardware.java")).unwrap();
+    assert!(hardware.contains(r#"armMotor = hardwareMap.get(DcMotorEx.class, "armMotor");"#));
+    let teleop = std::fs::read_to_string(output_dir.path().join("MyBot.java")).unwrap();
+    assert!(teleop.contains("public class MyBot extends RobotHardware {"));
+}
+
+#[test]
+fn fails_when_the_project_cant_be_read() {
+    let output = cli(&[Path::new("/nonexistent/MyBot.ftcreate")]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("/nonexistent/MyBot.ftcreate"));
+}
+
+#[test]
+fn wont_overwrite_the_opmode_it_imports() {
+    let dir = tempfile::tempdir().unwrap();
+    let opmode = dir.path().join("Lift.java");
+    std::fs::copy(fixture("Lift.java"), &opmode).unwrap();
+
+    let output = cli(&[&opmode, Path::new("--output-dir"), dir.path()]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        std::fs::read_to_string(&opmode).unwrap(),
+        std::fs::read_to_string(fixture("Lift.java")).unwrap()
+    );
+    assert!(!dir.path().join("RobotHardware.java").exists());
+}
@@ -0,0 +1,21 @@
+package org.firstinspires.ftc.teamcode;
+
+import com.qualcomm.robotcore.eventloop.opmode.LinearOpMode;
+import com.qualcomm.robotcore.eventloop.opmode.TeleOp;
+import com.qualcomm.robotcore.hardware.DcMotor;
+
+@TeleOp(name = "Lift")
+public class Lift extends LinearOpMode {
+    private DcMotor lift;
+
+    @Override
+    public void runOpMode() {
+        lift = hardwareMap.get(DcMotor.class, "liftMotor");
+
+        waitForStart();
+
+        while (opModeIsActive()) {
+            lift.setPower(-gamepad2.left_stick_y);
+        }
+    }
+}